@@ -0,0 +1,232 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An sRGB color, written in ADF as HTML hexadecimal (e.g. `#daa520`).
+/// Shorthand `#rgb` and CSS color names are read too, since documents from
+/// other editors use them, and written back as `#rrggbb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected a #rrggbb, #rgb or CSS named color, got {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+// Atlassian editor palette shared by the text color, background color and
+// table cell pickers: (light mode hex, name, dark mode hex).
+const PALETTE: [(&str, &str, &str); 28] = [
+    ("#172b4d", "dark-gray", "#b6c2cf"),
+    ("#97a0af", "gray", "#8c9bab"),
+    ("#b3bac5", "light-gray", "#454f59"),
+    ("#f4f5f7", "subtle-gray", "#22272b"),
+    ("#ffffff", "white", "#1d2125"),
+    ("#0747a6", "dark-blue", "#85b8ff"),
+    ("#4c9aff", "blue", "#579dff"),
+    ("#b3d4ff", "light-blue", "#0055cc"),
+    ("#deebff", "subtle-blue", "#092957"),
+    ("#008da6", "dark-teal", "#9dd9ee"),
+    ("#00b8d9", "teal", "#6cc3e0"),
+    ("#b3f5ff", "light-teal", "#206a83"),
+    ("#e6fcff", "subtle-teal", "#164555"),
+    ("#006644", "dark-green", "#7ee2b8"),
+    ("#36b37e", "green", "#4bce97"),
+    ("#abf5d1", "light-green", "#216e4e"),
+    ("#e3fcef", "subtle-green", "#164b35"),
+    ("#ff991f", "orange", "#fea362"),
+    ("#fff0b3", "light-yellow", "#7f5f01"),
+    ("#fffae6", "subtle-yellow", "#533f04"),
+    ("#bf2600", "dark-red", "#fd9891"),
+    ("#ff5630", "red", "#f87168"),
+    ("#ffbdad", "light-red", "#ae2e24"),
+    ("#ffebe6", "subtle-red", "#5d1f1a"),
+    ("#403294", "dark-purple", "#b8acf6"),
+    ("#6554c0", "purple", "#9f8fef"),
+    ("#c0b6f2", "light-purple", "#5e4db2"),
+    ("#eae6ff", "subtle-purple", "#352c63"),
+];
+
+// CSS named colors, sorted by name for binary search.
+const CSS_NAMES: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff), ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000), ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a), ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00), ("chocolate", 0xd2691e), ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c), ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b), ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc), ("darkred", 0x8b0000), ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b), ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3), ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff), ("dimgray", 0x696969),
+    ("dimgrey", 0x696969), ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22), ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700), ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000), ("greenyellow", 0xadff2f),
+    ("grey", 0x808080), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c), ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa), ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6), ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3), ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585), ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5), ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb), ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399), ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513), ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee), ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd), ("slategray", 0x708090),
+    ("slategrey", 0x708090), ("snow", 0xfffafa), ("springgreen", 0x00ff7f), ("steelblue", 0x4682b4), ("tan", 0xd2b48c),
+    ("teal", 0x008080), ("thistle", 0xd8bfd8), ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3), ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// Lowercase `#rrggbb`, the form ADF stores.
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Name of this color in the Atlassian editor palette, if it is one.
+    pub fn palette_name(self) -> Option<&'static str> {
+        let hex = self.to_hex();
+        PALETTE.iter().find(|(light, _, _)| *light == hex).map(|(_, name, _)| *name)
+    }
+
+    /// Palette color for a name such as `"red"` or `"subtle-blue"`.
+    pub fn from_palette_name(name: &str) -> Option<Self> {
+        PALETTE
+            .iter()
+            .find(|(_, palette_name, _)| *palette_name == name)
+            .and_then(|(light, _, _)| light.parse().ok())
+    }
+
+    /// Counterpart of this color on a dark background. Palette colors use the
+    /// Atlassian dark theme values; anything else keeps its hue with the
+    /// lightness inverted.
+    pub fn to_dark_mode(self) -> Self {
+        let hex = self.to_hex();
+        if let Some((_, _, dark)) = PALETTE.iter().find(|(light, _, _)| *light == hex) {
+            return dark.parse().expect("palette colors are valid");
+        }
+        self.invert_lightness()
+    }
+
+    fn invert_lightness(self) -> Self {
+        let [r, g, b] = [self.r, self.g, self.b].map(|c| c as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        // Inverting HSL lightness is the same as shifting every channel by
+        // (1 - max - min), which leaves hue and saturation untouched.
+        let shift = 1.0 - max - min;
+        let channel = |c: f32| ((c + shift).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::new(channel(r), channel(g), channel(b))
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseColorError(s.to_string());
+        let Some(hex) = s.strip_prefix('#') else {
+            let name = s.to_ascii_lowercase();
+            let index = CSS_NAMES.binary_search_by(|(css, _)| (*css).cmp(name.as_str())).map_err(|_| error())?;
+            let [_, r, g, b] = CSS_NAMES[index].1.to_be_bytes();
+            return Ok(Color::new(r, g, b));
+        };
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| error());
+        match hex.len() {
+            6 => Ok(Color::new(channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?)),
+            // Each digit of #rgb is doubled: #f80 is #ff8800.
+            3 => {
+                let short = |i: usize| channel(&hex[i..=i]).map(|digit| digit * 17);
+                Ok(Color::new(short(0)?, short(1)?, short(2)?))
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod color_tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_hex_case_insensitively() {
+        assert_eq!("#DAA520".parse(), Ok(Color::new(0xda, 0xa5, 0x20)));
+        assert_eq!(Color::new(0xda, 0xa5, 0x20).to_hex(), "#daa520");
+    }
+
+    #[test]
+    fn should_parse_short_hex_and_css_names() {
+        assert_eq!("#F80".parse(), Ok(Color::new(0xff, 0x88, 0x00)));
+        assert_eq!("Goldenrod".parse(), Ok(Color::new(0xda, 0xa5, 0x20)));
+        assert_eq!("red".parse::<Color>().unwrap().to_hex(), "#ff0000");
+        let background: Color = serde_json::from_str("\"#fff\"").unwrap();
+        assert_eq!(serde_json::to_string(&background).unwrap(), "\"#ffffff\"");
+    }
+
+    #[test]
+    fn should_reject_malformed_colors() {
+        for input in ["daa520", "#daa52", "#daa5200", "#gggggg", "#ff", "#+1f", "reddish", ""] {
+            assert!(input.parse::<Color>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn should_map_between_hex_and_palette_names() {
+        let red: Color = "#ff5630".parse().unwrap();
+        assert_eq!(red.palette_name(), Some("red"));
+        assert_eq!(Color::from_palette_name("red"), Some(red));
+        assert_eq!(Color::new(1, 2, 3).palette_name(), None);
+    }
+
+    #[test]
+    fn should_remap_colors_for_dark_mode() {
+        assert_eq!(Color::from_palette_name("dark-gray").unwrap().to_dark_mode().to_hex(), "#b6c2cf");
+        assert_eq!(Color::new(0, 0, 0).to_dark_mode(), Color::new(255, 255, 255));
+        assert_eq!(Color::new(0x80, 0, 0).to_dark_mode(), Color::new(0xff, 0x7f, 0x7f));
+    }
+}
@@ -0,0 +1,307 @@
+use std::fmt;
+
+use crate::model::Node;
+use crate::table::{TableError, TableGrid};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Field separator, `,` for CSV and `\t` for TSV.
+    pub delimiter: char,
+    /// Whether the first record is a header row (`tableHeader` cells).
+    pub header: bool,
+    /// How slots covered by a `colspan` or `rowspan` are exported.
+    pub spans: SpanFill,
+    /// Prefix exported fields starting with `=`, `+`, `-`, `@`, tab or
+    /// carriage return with `'`, so spreadsheets opening the file show them
+    /// as text instead of evaluating them as formulas.
+    pub formula_guard: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions::csv()
+    }
+}
+
+impl CsvOptions {
+    pub fn csv() -> Self {
+        CsvOptions { delimiter: ',', header: true, spans: SpanFill::Repeat, formula_guard: false }
+    }
+
+    pub fn tsv() -> Self {
+        CsvOptions { delimiter: '\t', ..CsvOptions::csv() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanFill {
+    /// Repeat the spanning cell's text in every slot it covers.
+    Repeat,
+    /// Write the text once, in the top-left slot, and leave the rest empty.
+    Blank,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvError {
+    /// The input has no records.
+    Empty,
+    /// A quoted field is never closed.
+    UnterminatedQuote { line: usize },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Empty => write!(f, "input has no records"),
+            CsvError::UnterminatedQuote { line } => write!(f, "quoted field starting on line {} is never closed", line),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// Builds a `table` node from CSV or TSV text. Short records are padded with
+/// empty cells, and line breaks inside quoted fields become hard breaks.
+pub fn table_from_csv(input: &str, options: &CsvOptions) -> Result<Node, CsvError> {
+    let records = parse_records(input, options.delimiter)?;
+    let columns = records.iter().map(Vec::len).max().ok_or(CsvError::Empty)?;
+
+    let rows = records
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut record)| {
+            record.resize(columns, String::new());
+            let header = options.header && index == 0;
+            Node::table_row(
+                record
+                    .iter()
+                    .map(|field| {
+                        let content = vec![Node::paragraph(inline_text(field))];
+                        if header {
+                            Node::table_header(content)
+                        } else {
+                            Node::table_cell(content)
+                        }
+                    })
+                    .collect(),
+            )
+        })
+        .collect();
+    Ok(Node::table(rows))
+}
+
+/// Writes a table as CSV or TSV, flattening rich cell content to its text.
+pub fn table_to_csv(table: &Node, options: &CsvOptions) -> Result<String, TableError> {
+    let grid = TableGrid::new(table)?;
+    let mut out = String::new();
+    for row in 0..grid.rows() {
+        for column in 0..grid.columns() {
+            if column > 0 {
+                out.push(options.delimiter);
+            }
+            let cell = grid.cell(row, column).expect("grid slots are all covered");
+            if options.spans == SpanFill::Repeat || (cell.row, cell.column) == (row, column) {
+                push_field(&mut out, &cell.node.text_content(), options);
+            }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Exports every table in the document, in document order.
+pub fn tables_to_csv(doc: &Node, options: &CsvOptions) -> Result<Vec<String>, TableError> {
+    let mut tables = Vec::new();
+    let mut stack = vec![doc];
+    while let Some(node) = stack.pop() {
+        if let Node::Table { .. } = node {
+            tables.push(table_to_csv(node, options)?);
+            continue;
+        }
+        stack.extend(node.content().into_iter().flatten().rev());
+    }
+    Ok(tables)
+}
+
+fn inline_text(field: &str) -> Vec<Node> {
+    let mut content = Vec::new();
+    for (index, line) in field.lines().enumerate() {
+        if index > 0 {
+            content.push(Node::HardBreak { attrs: None });
+        }
+        if !line.is_empty() {
+            content.push(Node::text(line));
+        }
+    }
+    content
+}
+
+// RFC 4180 records: fields may be quoted, with `""` for a literal quote and
+// line breaks allowed inside quotes. A trailing line break ends the input.
+fn parse_records(input: &str, delimiter: char) -> Result<Vec<Vec<String>>, CsvError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+    let mut quote_line = None;
+
+    while let Some(c) = chars.next() {
+        if quote_line.is_some() {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quote_line = None,
+                c => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quote_line = Some(line),
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                line += 1;
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if let Some(line) = quote_line {
+        return Err(CsvError::UnterminatedQuote { line });
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn push_field(out: &mut String, text: &str, options: &CsvOptions) {
+    let guarded;
+    let text = if options.formula_guard && text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        guarded = format!("'{}", text);
+        &guarded
+    } else {
+        text
+    };
+    if text.contains([options.delimiter, '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&text.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod csv_tests {
+    use super::*;
+    use crate::model::TableCellAttrs;
+
+    #[test]
+    fn should_import_csv_with_header_and_quoted_fields() {
+        let table = table_from_csv("Name,Notes\r\nAda,\"Line one\nLine \"\"two\"\"\"\nBob\n", &CsvOptions::csv()).unwrap();
+
+        assert_eq!(
+            table,
+            Node::table(vec![
+                Node::table_row(vec![
+                    Node::table_header(vec![Node::paragraph(vec![Node::text("Name")])]),
+                    Node::table_header(vec![Node::paragraph(vec![Node::text("Notes")])]),
+                ]),
+                Node::table_row(vec![
+                    Node::table_cell(vec![Node::paragraph(vec![Node::text("Ada")])]),
+                    Node::table_cell(vec![Node::paragraph(vec![
+                        Node::text("Line one"),
+                        Node::HardBreak { attrs: None },
+                        Node::text("Line \"two\""),
+                    ])]),
+                ]),
+                Node::table_row(vec![
+                    Node::table_cell(vec![Node::paragraph(vec![Node::text("Bob")])]),
+                    Node::table_cell(vec![Node::paragraph(vec![])]),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_import_tsv_without_header() {
+        let options = CsvOptions { header: false, ..CsvOptions::tsv() };
+        let table = table_from_csv("a\tb,c", &options).unwrap();
+        let grid = TableGrid::new(&table).unwrap();
+
+        assert_eq!((grid.rows(), grid.columns(), grid.header_rows()), (1, 2, 0));
+        assert_eq!(grid.cell(0, 1).unwrap().node.text_content(), "b,c");
+    }
+
+    #[test]
+    fn should_report_csv_errors() {
+        assert_eq!(table_from_csv("", &CsvOptions::csv()), Err(CsvError::Empty));
+        assert_eq!(
+            table_from_csv("a\n\"b,c\nd", &CsvOptions::csv()),
+            Err(CsvError::UnterminatedQuote { line: 2 })
+        );
+    }
+
+    #[test]
+    fn should_export_tables_expanding_spans() {
+        let wide = TableCellAttrs::default().with_colspan(2);
+        let doc = Node::doc(vec![
+            Node::paragraph(vec![Node::text("Report")]),
+            Node::table(vec![
+                Node::table_row(vec![Node::TableHeader {
+                    content: vec![Node::paragraph(vec![Node::text("Total, all")])],
+                    attrs: Some(wide),
+                }]),
+                Node::table_row(vec![
+                    Node::table_cell(vec![
+                        Node::paragraph(vec![Node::text("say \"hi\"")]),
+                        Node::paragraph(vec![Node::text("twice")]),
+                    ]),
+                    Node::table_cell(vec![Node::paragraph(vec![Node::text("2")])]),
+                ]),
+            ]),
+        ]);
+
+        assert_eq!(
+            tables_to_csv(&doc, &CsvOptions::csv()).unwrap(),
+            vec!["\"Total, all\",\"Total, all\"\n\"say \"\"hi\"\"\ntwice\",2\n"]
+        );
+        let blank = CsvOptions { spans: SpanFill::Blank, ..CsvOptions::tsv() };
+        assert_eq!(
+            tables_to_csv(&doc, &blank).unwrap(),
+            vec!["Total, all\t\n\"say \"\"hi\"\"\ntwice\"\t2\n"]
+        );
+    }
+
+    #[test]
+    fn should_guard_formulas_when_asked() {
+        let cell = |text: &str| Node::table_cell(vec![Node::paragraph(vec![Node::text(text)])]);
+        let table = Node::table(vec![Node::table_row(vec![
+            cell("=HYPERLINK(\"http://x\")"),
+            cell("-2"),
+            cell("@SUM(A1)"),
+            cell("a=b"),
+        ])]);
+        let guarded = CsvOptions { formula_guard: true, ..CsvOptions::csv() };
+
+        assert_eq!(
+            table_to_csv(&table, &CsvOptions::csv()).unwrap(),
+            "\"=HYPERLINK(\"\"http://x\"\")\",-2,@SUM(A1),a=b\n"
+        );
+        assert_eq!(
+            table_to_csv(&table, &guarded).unwrap(),
+            "\"'=HYPERLINK(\"\"http://x\"\")\",'-2,'@SUM(A1),a=b\n"
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::convert::markup::{self, Custom, Element, Hooks, Markup, Reader};
+use crate::model::{ExpandAttrs, Mark, MediaAttrs, MediaSingleAttrs, MediaSingleLayout, MediaType, Node};
+use crate::url::safe_url;
+
+/// Imports HTML, such as a pasted web page, as a `doc`.
+///
+/// Headings, paragraphs, lists, tables (with their spans), `<pre>`,
+/// `<blockquote>`, `<hr>` and `<details>` become the matching nodes, and
+/// `<img>` an external media node whose id is the image URL. Inline
+/// formatting, colours set through `style` and links are kept as marks; loose
+/// inline content is wrapped in paragraphs. Scripts, styles, form controls and
+/// links or images whose URL could run script are dropped, as is every
+/// attribute not mapped to ADF.
+///
+/// HTML nests more freely than ADF, so content is then fitted to ADF's rules:
+/// a heading inside a list item becomes a paragraph, a table inside a table
+/// cell is replaced by its cells' content, a `<details>` below the top level
+/// becomes a nested expand, and so on.
+pub fn from_html(html: &str) -> Node {
+    let blocks = Reader::new(&Html).blocks(&markup::parse(html));
+    Node::doc(fit(blocks, Context::Doc))
+}
+
+struct Html;
+
+impl Hooks for Html {
+    fn element(&self, element: &Element, _: &[Mark], reader: &Reader) -> Option<Custom> {
+        Some(match element.name.as_str() {
+            "img" => {
+                let Some(src) = element.attr("src").and_then(safe_url) else { return Some(Custom::Skip) };
+                let mut attrs = MediaAttrs::new(MediaType::External, src, "");
+                let size = |name| element.attr(name).and_then(|size| size.trim().parse().ok());
+                if let (Some(width), Some(height)) = (size("width"), size("height")) {
+                    attrs = attrs.with_size(width, height);
+                }
+                Custom::Blocks(vec![Node::MediaSingle {
+                    content: vec![Node::Media { attrs }],
+                    attrs: MediaSingleAttrs::new(MediaSingleLayout::Center),
+                }])
+            }
+            "details" => {
+                let title = element.child("summary").map(|summary| summary.text()).unwrap_or_default();
+                let body: Vec<Markup> = element
+                    .children
+                    .iter()
+                    .filter(|child| !matches!(child, Markup::Element(summary) if summary.name == "summary"))
+                    .cloned()
+                    .collect();
+                let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+                Custom::Blocks(vec![Node::Expand {
+                    content: reader.blocks(&body),
+                    attrs: ExpandAttrs::new(title),
+                    marks: None,
+                }])
+            }
+            _ => return None,
+        })
+    }
+}
+
+// Where a block sits, which decides the blocks allowed next to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Doc,
+    Blockquote,
+    Cell,
+    Expand,
+    ListItem,
+    NestedExpand,
+    Panel,
+}
+
+fn allowed(context: Context, node: &Node) -> bool {
+    match node {
+        Node::Paragraph { .. } | Node::BulletList { .. } | Node::OrderedList { .. } | Node::CodeBlock { .. } => true,
+        Node::MediaSingle { .. } => true,
+        Node::TaskList { .. } => context != Context::Blockquote,
+        Node::MediaGroup { .. } => context != Context::ListItem,
+        Node::Heading { .. } | Node::Rule | Node::DecisionList { .. } => {
+            !matches!(context, Context::ListItem | Context::Blockquote)
+        }
+        Node::Blockquote { .. } => !matches!(context, Context::ListItem | Context::Blockquote | Context::Panel),
+        Node::Panel { .. } => !matches!(context, Context::ListItem | Context::Blockquote | Context::Panel),
+        Node::Table { .. } => matches!(context, Context::Doc | Context::Expand),
+        Node::Expand { .. } => context == Context::Doc,
+        Node::NestedExpand { .. } => matches!(context, Context::Cell | Context::Expand),
+        _ => false,
+    }
+}
+
+// Fits blocks, and everything inside them, to what ADF allows in `context`.
+fn fit(blocks: Vec<Node>, context: Context) -> Vec<Node> {
+    let mut fitted = Vec::new();
+    for mut block in blocks {
+        fit_children(&mut block);
+        if allowed(context, &block) {
+            fitted.push(block);
+            continue;
+        }
+        match &mut block {
+            Node::Heading { content, .. } => fitted.push(Node::paragraph(std::mem::take(content))),
+            Node::Expand { content, attrs, .. } if matches!(context, Context::Cell | Context::Expand) => {
+                let content = fit(std::mem::take(content), Context::NestedExpand);
+                fitted.push(Node::NestedExpand { content, attrs: attrs.clone() });
+            }
+            Node::Rule => {}
+            Node::Table { content, .. } => {
+                for row in content {
+                    for cell in row.content_mut().into_iter().flatten() {
+                        let content = cell.content_mut().map(std::mem::take).unwrap_or_default();
+                        fitted.extend(fit(content, context));
+                    }
+                }
+            }
+            other => {
+                let content = other.content_mut().map(std::mem::take).unwrap_or_default();
+                fitted.extend(fit(content, context));
+            }
+        }
+    }
+    fitted
+}
+
+fn fit_children(node: &mut Node) {
+    let (content, context) = match node {
+        Node::Blockquote { content } => (content, Context::Blockquote),
+        Node::Panel { content, .. } => (content, Context::Panel),
+        Node::Expand { content, .. } => (content, Context::Expand),
+        Node::NestedExpand { content, .. } => (content, Context::NestedExpand),
+        Node::TableCell { content, .. } | Node::TableHeader { content, .. } => (content, Context::Cell),
+        Node::ListItem { content } => {
+            *content = fit(std::mem::take(content), Context::ListItem);
+            match content.as_slice() {
+                [Node::Paragraph { content: empty, .. }, Node::Paragraph { .. }, ..] if empty.is_empty() => {
+                    // The placeholder for an item that opened with a heading.
+                    content.remove(0);
+                }
+                [Node::Paragraph { .. }, ..] => {}
+                _ => content.insert(0, Node::paragraph(Vec::new())),
+            }
+            return;
+        }
+        Node::BulletList { content } | Node::OrderedList { content, .. } | Node::Table { content, .. } => {
+            content.iter_mut().for_each(fit_children);
+            return;
+        }
+        Node::TableRow { content } => {
+            content.iter_mut().for_each(fit_children);
+            return;
+        }
+        _ => return,
+    };
+    *content = fit(std::mem::take(content), context);
+    if content.is_empty() {
+        content.push(Node::paragraph(Vec::new()));
+    }
+}
+
+#[cfg(test)]
+mod html_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+    use crate::model::{LinkAttrs, TableCellAttrs};
+
+    fn assert_fits(node: &Node) {
+        let context = match node {
+            Node::Doc { .. } => Some(Context::Doc),
+            Node::Blockquote { .. } => Some(Context::Blockquote),
+            Node::Panel { .. } => Some(Context::Panel),
+            Node::Expand { .. } => Some(Context::Expand),
+            Node::NestedExpand { .. } => Some(Context::NestedExpand),
+            Node::TableCell { .. } | Node::TableHeader { .. } => Some(Context::Cell),
+            Node::ListItem { .. } => Some(Context::ListItem),
+            _ => None,
+        };
+        for child in node.content().into_iter().flatten() {
+            if let Some(context) = context {
+                assert!(allowed(context, child), "{:?} is not allowed in {:?}", child, context);
+            }
+            assert_fits(child);
+        }
+    }
+
+    #[test]
+    fn should_import_common_html() {
+        let doc = from_html(concat!(
+            "<html><head><title>Page</title><style>p { color: red }</style></head><body>\n",
+            "<h1>Guide</h1>Intro with <b>bold</b> and <a href=\"https://example.com\">a link</a>.\n",
+            "<table><tr><th colspan=\"2\">Both</th></tr><tr><td>a<br>b</td><td></td></tr></table>\n",
+            "<details><summary> More\n info </summary><p>Hidden</p></details>\n",
+            "<img src=\"https://example.com/cat.png\" width=\"40\" height=\"30\">",
+            "</body></html>",
+        ));
+        let Node::Doc { content, .. } = &doc else { unreachable!() };
+
+        assert_eq!(content, &vec![
+            Node::heading(1, vec![Node::text("Guide")]),
+            Node::paragraph(vec![
+                Node::text("Intro with "),
+                Node::text_with_marks("bold", vec![Mark::Strong]),
+                Node::text(" and "),
+                Node::text_with_marks("a link", vec![Mark::Link { attrs: LinkAttrs::new("https://example.com") }]),
+                Node::text("."),
+            ]),
+            Node::table(vec![
+                Node::table_row(vec![Node::TableHeader {
+                    content: vec![paragraph("Both")],
+                    attrs: Some(TableCellAttrs::default().with_colspan(2)),
+                }]),
+                Node::table_row(vec![
+                    Node::table_cell(vec![Node::paragraph(vec![
+                        Node::text("a"),
+                        Node::HardBreak { attrs: None },
+                        Node::text("b"),
+                    ])]),
+                    Node::table_cell(vec![Node::paragraph(Vec::new())]),
+                ]),
+            ]),
+            Node::Expand { content: vec![paragraph("Hidden")], attrs: ExpandAttrs::new("More info"), marks: None },
+            Node::MediaSingle {
+                content: vec![Node::Media {
+                    attrs: MediaAttrs::new(MediaType::External, "https://example.com/cat.png", "").with_size(40, 30),
+                }],
+                attrs: MediaSingleAttrs::new(MediaSingleLayout::Center),
+            },
+        ]);
+    }
+
+    #[test]
+    fn should_drop_scripts_and_unsafe_urls() {
+        let doc = from_html(concat!(
+            "<p onclick=\"steal()\">Hi<script>alert('x')</script> ",
+            "<a href=\" JavaScript:alert(1)\">there</a><img src=\"javascript:alert(2)\"></p>",
+            "<noscript>Enable JS</noscript><iframe src=\"https://evil.example\">frame</iframe>",
+        ));
+
+        assert_eq!(doc, Node::doc(vec![paragraph("Hi there")]));
+    }
+
+    #[test]
+    fn should_fit_nesting_to_adf() {
+        let doc = from_html(concat!(
+            "<ul><li><h2>Title</h2><blockquote><p>quoted</p></blockquote>",
+            "<table><tr><td>cell</td></tr></table></li></ul>",
+            "<blockquote><h3>Heading</h3><hr></blockquote>",
+            "<table><tr><td><details><summary>Inner</summary>",
+            "<table><tr><td>deep</td></tr></table></details></td></tr></table>",
+        ));
+
+        assert_fits(&doc);
+        let Node::Doc { content, .. } = &doc else { unreachable!() };
+        assert_eq!(content[0], Node::bullet_list(vec![Node::list_item(vec![
+            paragraph("Title"),
+            paragraph("quoted"),
+            paragraph("cell"),
+        ])]));
+        assert_eq!(content[1], Node::Blockquote { content: vec![paragraph("Heading")] });
+        assert_eq!(
+            content[2],
+            Node::table(vec![Node::table_row(vec![Node::table_cell(vec![Node::NestedExpand {
+                content: vec![paragraph("deep")],
+                attrs: ExpandAttrs::new("Inner"),
+            }])])])
+        );
+    }
+
+    #[test]
+    fn should_flatten_deep_nesting_and_skip_many_scripts() {
+        let deep = format!("{}deep{}", "<div><blockquote>".repeat(50_000), "<script>x</SCRIPT>".repeat(60_000));
+        let doc = from_html(&deep);
+
+        assert_fits(&doc);
+        assert_eq!(doc.text_content(), "deep");
+    }
+}
@@ -0,0 +1,733 @@
+//! Reading HTML-like markup into nodes, shared by the converters for formats
+//! built on HTML.
+//!
+//! [`parse`] is forgiving in the way browsers are: unclosed elements are
+//! closed by their parent, stray end tags are dropped, and `<li>`, `<p>` and
+//! table cells close their open siblings. [`Reader`] then maps the plain HTML
+//! elements to nodes, handing every element to its [`Hooks`] first so that a
+//! format can claim its own.
+//!
+//! Elements nested deeper than [`ParseLimits::max_depth`] are flattened into
+//! their ancestor, so that hostile input cannot exhaust the stack of the
+//! recursive [`Reader`].
+
+use std::str::FromStr;
+
+use crate::lexer::ParseLimits;
+use crate::model::{
+    Color, ColorAttrs, LinkAttrs, Mark, Node, OrderedListAttrs, SubSupAttrs, TableCellAttrs,
+};
+use crate::url::safe_url;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Markup {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Element {
+    /// Lowercased, with any namespace prefix kept (`ac:image`).
+    pub(crate) name: String,
+    pub(crate) attrs: Vec<(String, String)>,
+    pub(crate) children: Vec<Markup>,
+}
+
+impl Element {
+    pub(crate) fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Markup::Element(element) => Some(element),
+            Markup::Text(_) => None,
+        })
+    }
+
+    /// The first child element with the given name.
+    pub(crate) fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|element| element.name == name)
+    }
+
+    /// All text below the element, as written.
+    pub(crate) fn text(&self) -> String {
+        let mut text = String::new();
+        collect_text(&self.children, &mut text);
+        text
+    }
+}
+
+// Children are dropped one level at a time rather than recursively.
+impl Drop for Element {
+    fn drop(&mut self) {
+        let mut pending = std::mem::take(&mut self.children);
+        while let Some(child) = pending.pop() {
+            if let Markup::Element(mut element) = child {
+                pending.append(&mut element.children);
+            }
+        }
+    }
+}
+
+fn collect_text(markup: &[Markup], out: &mut String) {
+    for child in markup {
+        match child {
+            Markup::Text(text) => out.push_str(text),
+            Markup::Element(element) => collect_text(&element.children, out),
+        }
+    }
+}
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+const RAW_TEXT: &[&str] = &["script", "style", "textarea", "title"];
+// Elements whose start implicitly ends an open `<p>`.
+const CLOSES_PARAGRAPH: &[&str] = &[
+    "address", "article", "aside", "blockquote", "div", "dl", "fieldset", "footer", "form", "h1", "h2", "h3", "h4",
+    "h5", "h6", "header", "hr", "main", "nav", "ol", "p", "pre", "section", "table", "ul",
+];
+
+/// Parses markup into a forest of elements and text. Never fails: anything
+/// that is not well-formed markup is kept as text.
+pub(crate) fn parse(input: &str) -> Vec<Markup> {
+    // The root is not an element of the input.
+    let max_depth = ParseLimits::default().max_depth + 1;
+    let mut stack: Vec<Element> = vec![Element::default()];
+    let mut at = 0;
+    while at < input.len() {
+        let rest = &input[at..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            at += 4 + comment.find("-->").map_or(comment.len(), |end| end + 3);
+        } else if let Some(data) = rest.strip_prefix("<![CDATA[") {
+            let end = data.find("]]>").unwrap_or(data.len());
+            push_text(&mut stack, &data[..end]);
+            at += 9 + (end + 3).min(data.len());
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            at += rest.find('>').map_or(rest.len(), |end| end + 1);
+        } else if let Some(name) = rest.strip_prefix("</").and_then(tag_name) {
+            let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+            let name = name.to_lowercase();
+            if let Some(open) = stack.iter().rposition(|element| element.name == name).filter(|open| *open > 0) {
+                close_to(&mut stack, open);
+            }
+            at += end;
+        } else if let Some((element, self_closing, length)) = rest.strip_prefix('<').and_then(start_tag) {
+            at += length;
+            implied_close(&mut stack, &element.name);
+            if RAW_TEXT.contains(&element.name.as_str()) && !self_closing {
+                let close = format!("</{}", element.name);
+                let body = &input[at..];
+                let end = find_ignoring_case(body, &close).unwrap_or(body.len());
+                let mut element = element;
+                if end > 0 {
+                    element.children.push(Markup::Text(body[..end].to_string()));
+                }
+                at += end + body[end..].find('>').map_or(body.len() - end, |close| close + 1);
+                attach(&mut stack, element);
+            } else if self_closing || VOID.contains(&element.name.as_str()) {
+                attach(&mut stack, element);
+            } else if stack.len() >= max_depth {
+                // Too deep: the tag is dropped and its content joins the
+                // innermost open element.
+            } else {
+                stack.push(element);
+            }
+        } else {
+            let end = rest.char_indices().skip(1).find(|(_, c)| *c == '<').map_or(rest.len(), |(end, _)| end);
+            push_text(&mut stack, &decode_entities(&rest[..end]));
+            at += end;
+        }
+    }
+    close_to(&mut stack, 1);
+    stack.pop().map(|mut root| std::mem::take(&mut root.children)).unwrap_or_default()
+}
+
+fn find_ignoring_case(text: &str, pattern: &str) -> Option<usize> {
+    text.as_bytes().windows(pattern.len()).position(|window| window.eq_ignore_ascii_case(pattern.as_bytes()))
+}
+
+fn attach(stack: &mut [Element], element: Element) {
+    stack.last_mut().expect("the root is never popped").children.push(Markup::Element(element));
+}
+
+fn push_text(stack: &mut [Element], text: &str) {
+    let children = &mut stack.last_mut().expect("the root is never popped").children;
+    match children.last_mut() {
+        Some(Markup::Text(last)) => last.push_str(text),
+        _ => children.push(Markup::Text(text.to_string())),
+    }
+}
+
+// Closes every element from `index` up, attaching each to its parent.
+fn close_to(stack: &mut Vec<Element>, index: usize) {
+    while stack.len() > index {
+        let element = stack.pop().expect("stack is longer than index");
+        attach(stack, element);
+    }
+}
+
+fn implied_close(stack: &mut Vec<Element>, name: &str) {
+    let open = |stack: &[Element], names: &[&str], boundaries: &[&str]| {
+        stack
+            .iter()
+            .rposition(|element| names.contains(&element.name.as_str()) || boundaries.contains(&element.name.as_str()))
+            .filter(|index| *index > 0 && names.contains(&stack[*index].name.as_str()))
+    };
+    let index = match name {
+        "li" => open(stack, &["li"], &["ul", "ol"]),
+        "dt" | "dd" => open(stack, &["dt", "dd"], &["dl"]),
+        "td" | "th" => open(stack, &["td", "th"], &["tr", "table"]),
+        "tr" => open(stack, &["tr"], &["table"]),
+        "thead" | "tbody" | "tfoot" => open(stack, &["thead", "tbody", "tfoot"], &["table"]),
+        name if CLOSES_PARAGRAPH.contains(&name) => {
+            // Only an unclosed paragraph that is the innermost open element.
+            stack.last().filter(|element| element.name == "p").map(|_| stack.len() - 1)
+        }
+        _ => None,
+    };
+    if let Some(index) = index {
+        close_to(stack, index);
+    }
+}
+
+fn tag_name(text: &str) -> Option<&str> {
+    let end = text.find(|c: char| !(c.is_alphanumeric() || matches!(c, ':' | '-' | '_' | '.'))).unwrap_or(text.len());
+    let name = &text[..end];
+    name.starts_with(|c: char| c.is_ascii_alphabetic()).then_some(name)
+}
+
+// An element from the text after `<`, whether it closed itself, and the
+// length of the tag including the `<`.
+fn start_tag(text: &str) -> Option<(Element, bool, usize)> {
+    let name = tag_name(text)?;
+    let mut element = Element { name: name.to_lowercase(), attrs: Vec::new(), children: Vec::new() };
+    let mut at = name.len();
+    loop {
+        at += text[at..].len() - text[at..].trim_start().len();
+        let rest = &text[at..];
+        if rest.is_empty() {
+            return Some((element, false, at + 1));
+        } else if rest.starts_with("/>") {
+            return Some((element, true, at + 3));
+        } else if rest.starts_with('>') {
+            return Some((element, false, at + 2));
+        }
+        let end = rest.find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/')).unwrap_or(rest.len());
+        let attr = &rest[..end];
+        if attr.is_empty() {
+            // A stray `/` or `=`.
+            at += 1;
+            continue;
+        }
+        at += attr.len();
+        let after = text[at..].trim_start();
+        let value = if let Some(value) = after.strip_prefix('=') {
+            let value = value.trim_start();
+            at = text.len() - value.len();
+            match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = value[1..].find(quote).map_or(value.len(), |end| end + 1);
+                    at += (end + 1).min(value.len());
+                    decode_entities(&value[1..end])
+                }
+                _ => {
+                    let end = value.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(value.len());
+                    at += end;
+                    decode_entities(&value[..end])
+                }
+            }
+        } else {
+            String::new()
+        };
+        element.attrs.push((attr.to_lowercase(), value));
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..].find(';').filter(|end| *end <= 10).and_then(|end| {
+            let name = &rest[1..end + 1];
+            let c = match name.strip_prefix('#') {
+                Some(number) => match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => number.parse().ok().and_then(char::from_u32),
+                },
+                None => named_entity(name),
+            };
+            c.map(|c| (c, end + 2))
+        });
+        match decoded {
+            Some((c, length)) => {
+                out.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn named_entity(name: &str) -> Option<char> {
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bull" => '•',
+        "middot" => '·',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "times" => '×',
+        "deg" => '°',
+        "euro" => '€',
+        _ => return None,
+    })
+}
+
+/// What a format makes of one of its own elements.
+pub(crate) enum Custom {
+    Blocks(Vec<Node>),
+    #[cfg_attr(not(feature = "storage"), allow(dead_code))]
+    Inline(Vec<Node>),
+    /// Drop the element and everything in it.
+    Skip,
+}
+
+pub(crate) trait Hooks {
+    /// Nodes for an element the format handles itself, or `None` to read it
+    /// as plain HTML. `marks` are those of the enclosing inline elements.
+    fn element(&self, element: &Element, marks: &[Mark], reader: &Reader) -> Option<Custom>;
+}
+
+// Elements whose content is never document text.
+const DROPPED: &[&str] = &[
+    "button", "head", "iframe", "input", "math", "noscript", "object", "option", "script", "select", "style", "svg",
+    "template", "textarea", "title",
+];
+// Elements that start a paragraph of their own rather than continue the
+// surrounding text.
+const CONTAINERS: &[&str] = &[
+    "address", "article", "aside", "center", "dd", "div", "dl", "dt", "figcaption", "figure", "footer", "header",
+    "li", "main", "nav", "p", "section",
+];
+
+enum Flow {
+    Inline(Node),
+    Block(Node),
+}
+
+/// Maps markup to blocks. Whitespace is collapsed as a browser would, links
+/// are only kept for `http`, `https`, `mailto` and `tel` and relative URLs,
+/// and the content of scripts, styles and form controls is dropped.
+pub(crate) struct Reader<'a> {
+    hooks: &'a dyn Hooks,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(hooks: &'a dyn Hooks) -> Self {
+        Reader { hooks }
+    }
+
+    /// Blocks for a run of markup, wrapping loose inline content in
+    /// paragraphs.
+    pub(crate) fn blocks(&self, markup: &[Markup]) -> Vec<Node> {
+        let mut flow = Vec::new();
+        self.flow(markup, &[], &mut flow);
+        into_blocks(flow, &|content| Some(Node::paragraph(content)))
+    }
+
+    /// Inline nodes for a run of markup. Blocks inside it keep their inline
+    /// content, separated by hard breaks.
+    #[cfg_attr(not(feature = "storage"), allow(dead_code))]
+    pub(crate) fn inline(&self, markup: &[Markup], marks: &[Mark]) -> Vec<Node> {
+        let mut flow = Vec::new();
+        self.flow(markup, marks, &mut flow);
+        let mut inline = Vec::new();
+        for item in flow {
+            match item {
+                Flow::Inline(node) => inline.push(node),
+                Flow::Block(block) => {
+                    if !inline.is_empty() {
+                        inline.push(Node::HardBreak { attrs: None });
+                    }
+                    inline.extend(inline_content(block));
+                }
+            }
+        }
+        tidy(inline)
+    }
+
+    fn flow(&self, markup: &[Markup], marks: &[Mark], out: &mut Vec<Flow>) {
+        for child in markup {
+            let element = match child {
+                Markup::Text(text) => {
+                    out.push(Flow::Inline(text_node(collapse(text), marks)));
+                    continue;
+                }
+                Markup::Element(element) => element,
+            };
+            match self.hooks.element(element, marks, self) {
+                Some(Custom::Blocks(blocks)) => out.extend(blocks.into_iter().map(Flow::Block)),
+                Some(Custom::Inline(nodes)) => out.extend(nodes.into_iter().map(Flow::Inline)),
+                Some(Custom::Skip) => {}
+                None => self.element(element, marks, out),
+            }
+        }
+    }
+
+    fn element(&self, element: &Element, marks: &[Mark], out: &mut Vec<Flow>) {
+        let name = element.name.as_str();
+        let mark = match name {
+            "b" | "strong" => Some(Mark::Strong),
+            "cite" | "dfn" | "em" | "i" | "var" => Some(Mark::Em),
+            "ins" | "u" => Some(Mark::Underline),
+            "del" | "s" | "strike" => Some(Mark::Strike),
+            "code" | "kbd" | "samp" | "tt" => Some(Mark::Code),
+            "sub" => Some(Mark::SubSup { attrs: SubSupAttrs::Sub }),
+            "sup" => Some(Mark::SubSup { attrs: SubSupAttrs::Sup }),
+            "a" => element.attr("href").and_then(safe_url).map(|href| Mark::Link { attrs: LinkAttrs::new(href) }),
+            _ => None,
+        };
+        if let Some(mark) = mark {
+            return self.flow(&element.children, &with_mark(marks, mark), out);
+        }
+        let level = name.strip_prefix('h').and_then(|level| level.parse::<i8>().ok());
+        if let Some(level) = level.filter(|level| (1..=6).contains(level)) {
+            let mut flow = Vec::new();
+            self.flow(&element.children, marks, &mut flow);
+            let blocks = into_blocks(flow, &|content| Some(Node::heading(level, content)));
+            return out.extend(blocks.into_iter().map(Flow::Block));
+        }
+        let block = match name {
+            _ if DROPPED.contains(&name) => return,
+            "br" => return out.push(Flow::Inline(Node::HardBreak { attrs: None })),
+            "span" | "font" => {
+                let marks = style_marks(element).into_iter().fold(marks.to_vec(), |all, mark| with_mark(&all, mark));
+                return self.flow(&element.children, &marks, out);
+            }
+            _ if CONTAINERS.contains(&name) => {
+                let mut flow = Vec::new();
+                self.flow(&element.children, marks, &mut flow);
+                let blocks = into_blocks(flow, &|content| Some(Node::paragraph(content)));
+                return out.extend(blocks.into_iter().map(Flow::Block));
+            }
+            "hr" => Node::Rule,
+            "blockquote" => Node::Blockquote { content: self.blocks(&element.children) },
+            "pre" => {
+                let code = element.text();
+                let code = code.strip_prefix('\n').unwrap_or(&code).trim_end_matches('\n').to_string();
+                let language = element
+                    .child("code")
+                    .and_then(|code| code.attr("class"))
+                    .and_then(|class| class.split_whitespace().find_map(|class| class.strip_prefix("language-")));
+                Node::code_block(language, code)
+            }
+            "ul" | "ol" => {
+                let items: Vec<Node> = element
+                    .children
+                    .iter()
+                    .filter_map(|child| match child {
+                        Markup::Element(item) if item.name == "li" => Some(list_item(self.blocks(&item.children))),
+                        Markup::Text(text) if text.trim().is_empty() => None,
+                        other => Some(list_item(self.blocks(std::slice::from_ref(other)))),
+                    })
+                    .collect();
+                if items.is_empty() {
+                    return;
+                }
+                match element.attr("start").and_then(|start| start.trim().parse().ok()) {
+                    Some(order) if name == "ol" && order != 1 => {
+                        Node::OrderedList { content: items, attrs: Some(OrderedListAttrs::new(order)) }
+                    }
+                    _ if name == "ol" => Node::ordered_list(items),
+                    _ => Node::bullet_list(items),
+                }
+            }
+            "table" => {
+                let mut rows = Vec::new();
+                self.rows(element, &mut rows);
+                if rows.is_empty() {
+                    return;
+                }
+                Node::table(rows)
+            }
+            _ => return self.flow(&element.children, marks, out),
+        };
+        out.push(Flow::Block(block));
+    }
+
+    fn rows(&self, element: &Element, rows: &mut Vec<Node>) {
+        for child in element.elements() {
+            match child.name.as_str() {
+                "tr" => {
+                    let cells: Vec<Node> = child
+                        .elements()
+                        .filter(|cell| cell.name == "td" || cell.name == "th")
+                        .map(|cell| self.cell(cell))
+                        .collect();
+                    if !cells.is_empty() {
+                        rows.push(Node::table_row(cells));
+                    }
+                }
+                "thead" | "tbody" | "tfoot" => self.rows(child, rows),
+                _ => {}
+            }
+        }
+    }
+
+    fn cell(&self, cell: &Element) -> Node {
+        let mut content = self.blocks(&cell.children);
+        if content.is_empty() {
+            content.push(Node::paragraph(Vec::new()));
+        }
+        let span = |name| cell.attr(name).and_then(|span| span.trim().parse::<u16>().ok()).filter(|span| *span > 1);
+        let mut attrs = TableCellAttrs::default();
+        if let Some(colspan) = span("colspan") {
+            attrs = attrs.with_colspan(colspan);
+        }
+        if let Some(rowspan) = span("rowspan") {
+            attrs = attrs.with_rowspan(rowspan);
+        }
+        let attrs = (attrs != TableCellAttrs::default()).then_some(attrs);
+        if cell.name == "th" {
+            Node::TableHeader { content, attrs }
+        } else {
+            Node::TableCell { content, attrs }
+        }
+    }
+}
+
+// Groups inline runs into blocks made by `wrap`, dropping runs that are only
+// whitespace.
+fn into_blocks(flow: Vec<Flow>, wrap: &dyn Fn(Vec<Node>) -> Option<Node>) -> Vec<Node> {
+    let mut blocks = Vec::new();
+    let mut inline = Vec::new();
+    let flush = |inline: &mut Vec<Node>, blocks: &mut Vec<Node>| {
+        let content = tidy(std::mem::take(inline));
+        if !content.is_empty() {
+            blocks.extend(wrap(content));
+        }
+    };
+    for item in flow {
+        match item {
+            Flow::Inline(node) => inline.push(node),
+            Flow::Block(block) => {
+                flush(&mut inline, &mut blocks);
+                blocks.push(block);
+            }
+        }
+    }
+    flush(&mut inline, &mut blocks);
+    blocks
+}
+
+// Merges neighbouring text with the same marks, collapses the space between
+// them, and trims the run's ends and around hard breaks.
+fn tidy(nodes: Vec<Node>) -> Vec<Node> {
+    let mut merged: Vec<Node> = Vec::new();
+    for mut node in nodes {
+        if let Node::Text { text, .. } = &mut node {
+            let previous = merged.last().is_none_or(|last| match last {
+                Node::Text { text, .. } => text.ends_with(' '),
+                Node::HardBreak { .. } => true,
+                _ => false,
+            });
+            if previous && text.starts_with(' ') {
+                text.remove(0);
+            }
+            if text.is_empty() {
+                continue;
+            }
+        }
+        if let Node::HardBreak { .. } = node {
+            trim_last(&mut merged);
+        }
+        match (merged.last_mut(), &mut node) {
+            (Some(Node::Text { text, marks }), Node::Text { text: next, marks: next_marks }) if marks == next_marks => {
+                text.push_str(next)
+            }
+            _ => merged.push(node),
+        }
+    }
+    trim_last(&mut merged);
+    merged
+}
+
+fn trim_last(nodes: &mut Vec<Node>) {
+    if let Some(Node::Text { text, .. }) = nodes.last_mut() {
+        text.truncate(text.trim_end_matches(' ').len());
+        if text.is_empty() {
+            nodes.pop();
+        }
+    }
+}
+
+fn collapse(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !space {
+                out.push(' ');
+            }
+            space = true;
+        } else {
+            out.push(c);
+            space = false;
+        }
+    }
+    out
+}
+
+fn inline_content(mut block: Node) -> Vec<Node> {
+    match &mut block {
+        node if node.is_inline() => vec![std::mem::replace(node, Node::Rule)],
+        Node::Paragraph { content, .. } | Node::Heading { content, .. } => std::mem::take(content),
+        node => node.content_mut().map(std::mem::take).into_iter().flatten().flat_map(inline_content).collect(),
+    }
+}
+
+fn list_item(mut content: Vec<Node>) -> Node {
+    // An item must open with a paragraph.
+    if !matches!(content.first(), Some(Node::Paragraph { .. })) {
+        content.insert(0, Node::paragraph(Vec::new()));
+    }
+    Node::list_item(content)
+}
+
+fn style_marks(element: &Element) -> Vec<Mark> {
+    let mut marks = Vec::new();
+    let style = element.attr("style").unwrap_or_default();
+    for declaration in style.split(';') {
+        let Some((property, value)) = declaration.split_once(':') else { continue };
+        let color = parse_color(value.trim());
+        match (property.trim().to_lowercase().as_str(), color) {
+            ("color", Some(color)) => marks.push(Mark::TextColor { attrs: ColorAttrs::new(color) }),
+            ("background-color", Some(color)) => marks.push(Mark::BackgroundColor { attrs: ColorAttrs::new(color) }),
+            _ => {}
+        }
+    }
+    if let Some(color) = element.attr("color").filter(|_| element.name == "font").and_then(parse_color) {
+        marks.push(Mark::TextColor { attrs: ColorAttrs::new(color) });
+    }
+    marks
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim_end_matches("!important").trim();
+    if let Some(rgb) = value.strip_prefix("rgb(").and_then(|rgb| rgb.strip_suffix(')')) {
+        let channels: Vec<u8> = rgb.split(',').filter_map(|channel| channel.trim().parse().ok()).collect();
+        return match channels.as_slice() {
+            [r, g, b] => Some(Color::new(*r, *g, *b)),
+            _ => None,
+        };
+    }
+    Color::from_str(&value.to_lowercase()).ok().or_else(|| Color::from_palette_name(&value.to_lowercase()))
+}
+
+pub(crate) fn with_mark(marks: &[Mark], mark: Mark) -> Vec<Mark> {
+    let mut marks = marks.to_vec();
+    // Code combines with links only.
+    if marks.contains(&Mark::Code) && !matches!(mark, Mark::Link { .. }) {
+        return marks;
+    }
+    if mark == Mark::Code {
+        marks.retain(|mark| matches!(mark, Mark::Link { .. }));
+    }
+    marks.retain(|existing| std::mem::discriminant(existing) != std::mem::discriminant(&mark));
+    marks.push(mark);
+    marks
+}
+
+pub(crate) fn text_node(text: String, marks: &[Mark]) -> Node {
+    Node::Text { text, marks: (!marks.is_empty()).then(|| marks.to_vec()) }
+}
+
+#[cfg(test)]
+mod markup_tests {
+    use super::*;
+
+    struct NoHooks;
+
+    impl Hooks for NoHooks {
+        fn element(&self, _: &Element, _: &[Mark], _: &Reader) -> Option<Custom> {
+            None
+        }
+    }
+
+    fn element(markup: &Markup) -> &Element {
+        match markup {
+            Markup::Element(element) => element,
+            Markup::Text(text) => panic!("expected an element, found {:?}", text),
+        }
+    }
+
+    #[test]
+    fn should_parse_forgiving_markup() {
+        let markup = parse(
+            "<ul><li>One &amp; <b>two<li>Three</ul><p a=1 b='x &lt; y' c>para<p>next</i><!-- gone --><![CDATA[<raw>]]>",
+        );
+
+        let list = element(&markup[0]);
+        assert_eq!(list.elements().map(|item| item.text()).collect::<Vec<_>>(), vec!["One & two", "Three"]);
+        let first = element(&markup[1]);
+        assert_eq!(first.attrs, vec![
+            ("a".to_string(), "1".to_string()),
+            ("b".to_string(), "x < y".to_string()),
+            ("c".to_string(), String::new()),
+        ]);
+        assert_eq!(first.text(), "para");
+        assert_eq!(element(&markup[2]).text(), "next<raw>");
+    }
+
+    #[test]
+    fn should_read_html_blocks_and_marks() {
+        let markup = parse(concat!(
+            "<h2>Title</h2>\n<div>loose <em>text</em></div>\n",
+            "<p>A  <a href=\"javascript:alert(1)\">bad</a> <a href=\"/ok\"><code>ok</code></a><br> next</p>",
+            "<ol start=\"3\"><li><ul><li>deep</li></ul></li></ol>",
+        ));
+        let blocks = Reader::new(&NoHooks).blocks(&markup);
+
+        assert_eq!(blocks, vec![
+            Node::heading(2, vec![Node::text("Title")]),
+            Node::paragraph(vec![Node::text("loose "), Node::text_with_marks("text", vec![Mark::Em])]),
+            Node::paragraph(vec![
+                Node::text("A bad "),
+                Node::text_with_marks("ok", vec![Mark::Link { attrs: LinkAttrs::new("/ok") }, Mark::Code]),
+                Node::HardBreak { attrs: None },
+                Node::text("next"),
+            ]),
+            Node::OrderedList {
+                content: vec![Node::list_item(vec![
+                    Node::paragraph(Vec::new()),
+                    Node::bullet_list(vec![Node::list_item(vec![Node::paragraph(vec![Node::text("deep")])])]),
+                ])],
+                attrs: Some(OrderedListAttrs::new(3)),
+            },
+        ]);
+    }
+}
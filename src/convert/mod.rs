@@ -0,0 +1,10 @@
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "html-import")]
+pub mod html;
+#[cfg(any(feature = "html-import", feature = "storage"))]
+mod markup;
+#[cfg(feature = "storage")]
+pub mod storage;
+#[cfg(feature = "wiki")]
+pub mod wiki;
@@ -0,0 +1,606 @@
+use crate::convert::markup::{self, Custom, Element, Hooks, Reader};
+use crate::date::UtcOffset;
+use crate::model::{
+    DateAttrs, EmojiAttrs, ExpandAttrs, InlineCardAttrs, LinkAttrs, Mark, MediaAttrs, MediaSingleAttrs,
+    MediaSingleLayout, MediaType, MentionAttrs, Node, PanelAttrs, PanelType, StatusAttrs, StatusColor, SubSupAttrs,
+    TaskItemAttrs, TaskListAttrs, TaskState,
+};
+use crate::url::safe_url;
+
+/// Confluence storage format for a node.
+///
+/// Panels become the `info`, `tip`, `note`, `warning` and `panel` macros,
+/// code blocks the `code` macro, expands the `expand` macro and status
+/// lozenges the `status` macro. Media is written as attached images named by
+/// the media id. Decision lists, which storage format lacks, become bullet
+/// lists.
+pub fn to_storage(node: &Node) -> String {
+    let mut out = String::new();
+    write_block(node, &mut out);
+    out
+}
+
+/// Parses Confluence storage format into a `doc`. Like a browser, the parser
+/// never rejects its input, and macros it does not know are replaced by
+/// their body, if they have one.
+///
+/// Tasks keep their `ac:task-id` as `localId`. Task lists have none in
+/// storage format, so theirs is left empty for
+/// [`assign_local_ids`](crate::local_id::assign_local_ids) to fill.
+///
+/// An `ac:image` of a `ri:url` becomes external media. One of a
+/// `ri:attachment` is only known by its filename, which the media API cannot
+/// look up: it becomes `file` media with the filename as `id` and an empty
+/// `collection`, a placeholder to map to the uploaded file (e.g. by a
+/// [`MediaResolver`](crate::resolve::MediaResolver) keyed by filename)
+/// before the document is stored.
+pub fn from_storage(xhtml: &str) -> Node {
+    Node::doc(Reader::new(&Storage).blocks(&markup::parse(xhtml)))
+}
+
+// ------ Writing -------
+
+fn write_blocks(content: &[Node], out: &mut String) {
+    for node in content {
+        write_block(node, out);
+    }
+}
+
+fn write_block(node: &Node, out: &mut String) {
+    match node {
+        Node::Doc { content, .. } | Node::ListItem { content } => write_blocks(content, out),
+        Node::Paragraph { content, .. } => {
+            out.push_str("<p>");
+            write_inline(content, out);
+            out.push_str("</p>");
+        }
+        Node::Heading { content, attrs } => {
+            let level = attrs.level().clamp(1, 6);
+            out.push_str(&format!("<h{}>", level));
+            write_inline(content, out);
+            out.push_str(&format!("</h{}>", level));
+        }
+        Node::BulletList { content } | Node::DecisionList { content, .. } => {
+            out.push_str("<ul>");
+            write_items(content, out);
+            out.push_str("</ul>");
+        }
+        Node::OrderedList { content, attrs } => {
+            match attrs.as_ref().map(|attrs| attrs.order()).filter(|order| *order != 1) {
+                Some(order) => out.push_str(&format!("<ol start=\"{}\">", order)),
+                None => out.push_str("<ol>"),
+            }
+            write_items(content, out);
+            out.push_str("</ol>");
+        }
+        Node::Blockquote { content } => {
+            out.push_str("<blockquote>");
+            write_blocks(content, out);
+            out.push_str("</blockquote>");
+        }
+        Node::CodeBlock { content, attrs } => {
+            let code: String = content.iter().flatten().map(Node::text_content).collect();
+            out.push_str(r#"<ac:structured-macro ac:name="code">"#);
+            let language = attrs.as_ref().map(|attrs| attrs.language()).filter(|language| !language.is_empty());
+            if let Some(language) = language {
+                write_parameter("language", language, out);
+            }
+            // `]]>` cannot appear inside CDATA, so it is split across two sections.
+            out.push_str(&format!(
+                "<ac:plain-text-body><![CDATA[{}]]></ac:plain-text-body></ac:structured-macro>",
+                code.replace("]]>", "]]]]><![CDATA[>")
+            ));
+        }
+        Node::Panel { content, attrs } => {
+            let name = match attrs.panel_type() {
+                PanelType::Info => "info",
+                PanelType::Success => "tip",
+                PanelType::Warning => "note",
+                PanelType::Error => "warning",
+                _ => "panel",
+            };
+            write_macro(name, &[], content, out);
+        }
+        Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+            write_macro("expand", &[("title", attrs.title())], content, out)
+        }
+        Node::Rule => out.push_str("<hr />"),
+        Node::Table { content, .. } => {
+            out.push_str("<table><tbody>");
+            write_blocks(content, out);
+            out.push_str("</tbody></table>");
+        }
+        Node::TableRow { content } => {
+            out.push_str("<tr>");
+            write_blocks(content, out);
+            out.push_str("</tr>");
+        }
+        Node::TableCell { content, attrs } | Node::TableHeader { content, attrs } => {
+            let tag = if matches!(node, Node::TableHeader { .. }) { "th" } else { "td" };
+            out.push('<');
+            out.push_str(tag);
+            if let Some(attrs) = attrs {
+                if attrs.colspan() > 1 {
+                    out.push_str(&format!(" colspan=\"{}\"", attrs.colspan()));
+                }
+                if attrs.rowspan() > 1 {
+                    out.push_str(&format!(" rowspan=\"{}\"", attrs.rowspan()));
+                }
+            }
+            out.push('>');
+            write_blocks(content, out);
+            out.push_str(&format!("</{}>", tag));
+        }
+        Node::TaskList { content, .. } => {
+            out.push_str("<ac:task-list>");
+            for item in content {
+                match item {
+                    Node::TaskItem { content, attrs } => {
+                        let status = if *attrs.state() == TaskState::Done { "complete" } else { "incomplete" };
+                        out.push_str(&format!(
+                            "<ac:task><ac:task-id>{}</ac:task-id><ac:task-status>{}</ac:task-status><ac:task-body>",
+                            escape(attrs.local_id()),
+                            status
+                        ));
+                        write_inline(content, out);
+                        out.push_str("</ac:task-body></ac:task>");
+                    }
+                    // Nested task lists.
+                    other => write_block(other, out),
+                }
+            }
+            out.push_str("</ac:task-list>");
+        }
+        Node::MediaSingle { content, .. } | Node::MediaGroup { content } => {
+            out.push_str("<p>");
+            write_blocks(content, out);
+            out.push_str("</p>");
+        }
+        Node::Media { attrs } => {
+            out.push_str("<ac:image");
+            if let (Some(width), Some(height)) = (attrs.width(), attrs.height()) {
+                out.push_str(&format!(" ac:width=\"{}\" ac:height=\"{}\"", width, height));
+            }
+            match attrs.media_type() {
+                MediaType::External => out.push_str(&format!("><ri:url ri:value=\"{}\" />", escape(attrs.id()))),
+                _ => out.push_str(&format!("><ri:attachment ri:filename=\"{}\" />", escape(attrs.id()))),
+            }
+            out.push_str("</ac:image>");
+        }
+        inline_node => {
+            out.push_str("<p>");
+            write_inline(std::slice::from_ref(inline_node), out);
+            out.push_str("</p>");
+        }
+    }
+}
+
+fn write_items(items: &[Node], out: &mut String) {
+    for item in items {
+        out.push_str("<li>");
+        match item {
+            Node::DecisionItem { content, .. } => write_inline(content, out),
+            // A list item's first paragraph is written bare, as Confluence does.
+            Node::ListItem { content } => match content.split_first() {
+                Some((Node::Paragraph { content: first, .. }, rest)) => {
+                    write_inline(first, out);
+                    write_blocks(rest, out);
+                }
+                _ => write_blocks(content, out),
+            },
+            other => write_block(other, out),
+        }
+        out.push_str("</li>");
+    }
+}
+
+fn write_macro(name: &str, parameters: &[(&str, &str)], content: &[Node], out: &mut String) {
+    out.push_str(&format!(r#"<ac:structured-macro ac:name="{}">"#, name));
+    for (name, value) in parameters {
+        write_parameter(name, value, out);
+    }
+    out.push_str("<ac:rich-text-body>");
+    write_blocks(content, out);
+    out.push_str("</ac:rich-text-body></ac:structured-macro>");
+}
+
+fn write_parameter(name: &str, value: &str, out: &mut String) {
+    out.push_str(&format!(r#"<ac:parameter ac:name="{}">{}</ac:parameter>"#, name, escape(value)));
+}
+
+fn write_inline(content: &[Node], out: &mut String) {
+    for node in content {
+        match node {
+            Node::Text { text, marks } => write_text(text, marks.as_deref().unwrap_or_default(), out),
+            Node::HardBreak { .. } => out.push_str("<br />"),
+            Node::Mention { attrs } => {
+                out.push_str(&format!(r#"<ac:link><ri:user ri:account-id="{}" /></ac:link>"#, escape(attrs.id())))
+            }
+            Node::Emoji { attrs } => {
+                let short_name = escape(attrs.short_name());
+                out.push_str(&format!(r#"<ac:emoticon ac:name="blue-star" ac:emoji-shortname="{}""#, short_name));
+                if let Some(id) = attrs.id() {
+                    out.push_str(&format!(r#" ac:emoji-id="{}""#, escape(id)));
+                }
+                if let Some(text) = attrs.text() {
+                    out.push_str(&format!(r#" ac:emoji-fallback="{}""#, escape(text)));
+                }
+                out.push_str(" />");
+            }
+            Node::Date { attrs } => match attrs.parsed_timestamp() {
+                Ok(timestamp) => {
+                    let date = timestamp.to_date_time(UtcOffset::UTC);
+                    out.push_str(&format!(r#"<time datetime="{:04}-{:02}-{:02}" />"#, date.year, date.month, date.day));
+                }
+                Err(_) => out.push_str(&escape(attrs.timestamp())),
+            },
+            Node::Status { attrs } => {
+                let colour = match attrs.color() {
+                    StatusColor::Purple => "Purple",
+                    StatusColor::Blue => "Blue",
+                    StatusColor::Red => "Red",
+                    StatusColor::Yellow => "Yellow",
+                    StatusColor::Green => "Green",
+                    _ => "Grey",
+                };
+                out.push_str(r#"<ac:structured-macro ac:name="status">"#);
+                write_parameter("title", attrs.text(), out);
+                write_parameter("colour", colour, out);
+                out.push_str("</ac:structured-macro>");
+            }
+            Node::InlineCard { attrs } => {
+                let url = escape(attrs.url().unwrap_or_default());
+                out.push_str(&format!(r#"<a href="{}" data-card-appearance="inline">{}</a>"#, url, url));
+            }
+            other => out.push_str(&escape(&other.text_content())),
+        }
+    }
+}
+
+fn write_text(text: &str, marks: &[Mark], out: &mut String) {
+    // Outermost first: links, then colours, then the plain styles.
+    let mut open: Vec<(String, &str)> = Vec::new();
+    for mark in marks {
+        if let Mark::Link { attrs } = mark {
+            open.push((format!(r#"<a href="{}">"#, escape(attrs.href())), "a"));
+        }
+    }
+    for mark in marks {
+        match mark {
+            Mark::TextColor { attrs } => open.push((format!(r#"<span style="color: {};">"#, attrs.color()), "span")),
+            Mark::BackgroundColor { attrs } => {
+                open.push((format!(r#"<span style="background-color: {};">"#, attrs.color()), "span"))
+            }
+            _ => {}
+        }
+    }
+    for (mark, tag) in [
+        (Mark::Strong, "strong"),
+        (Mark::Em, "em"),
+        (Mark::Underline, "u"),
+        (Mark::Strike, "s"),
+        (Mark::SubSup { attrs: SubSupAttrs::Sub }, "sub"),
+        (Mark::SubSup { attrs: SubSupAttrs::Sup }, "sup"),
+        (Mark::Code, "code"),
+    ] {
+        if marks.contains(&mark) {
+            open.push((format!("<{}>", tag), tag));
+        }
+    }
+    for (tag, _) in &open {
+        out.push_str(tag);
+    }
+    out.push_str(&escape(text));
+    for (_, tag) in open.iter().rev() {
+        out.push_str(&format!("</{}>", tag));
+    }
+}
+
+// Escapes text for use in markup, inside elements or quoted attributes.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ------ Reading -------
+
+struct Storage;
+
+impl Hooks for Storage {
+    fn element(&self, element: &Element, marks: &[Mark], reader: &Reader) -> Option<Custom> {
+        Some(match element.name.as_str() {
+            "ac:structured-macro" | "ac:macro" => self.read_macro(element, reader),
+            "ac:task-list" => Custom::Blocks(vec![task_list(element, reader)]),
+            "ac:link" => match element.child("ri:user") {
+                Some(user) => {
+                    let id = user.attr("ri:account-id").or_else(|| user.attr("ri:userkey")).unwrap_or_default();
+                    Custom::Inline(vec![Node::Mention { attrs: MentionAttrs::new(id) }])
+                }
+                None => {
+                    let marks = match element.child("ri:url").and_then(|url| url.attr("ri:value")) {
+                        Some(href) => match safe_url(href) {
+                            Some(href) => markup::with_mark(marks, Mark::Link { attrs: LinkAttrs::new(href) }),
+                            None => marks.to_vec(),
+                        },
+                        None => marks.to_vec(),
+                    };
+                    let body = element.child("ac:plain-text-link-body").or_else(|| element.child("ac:link-body"));
+                    match body {
+                        Some(body) => Custom::Inline(reader.inline(&body.children, &marks)),
+                        None => {
+                            let text = link_target(element).map(|text| markup::text_node(text, &marks));
+                            Custom::Inline(text.into_iter().collect())
+                        }
+                    }
+                }
+            },
+            "ac:image" => {
+                let (media_type, id) = if let Some(url) = element.child("ri:url") {
+                    (MediaType::External, url.attr("ri:value"))
+                } else {
+                    (MediaType::File, element.child("ri:attachment").and_then(|file| file.attr("ri:filename")))
+                };
+                let Some(id) = id else { return Some(Custom::Skip) };
+                let mut attrs = MediaAttrs::new(media_type, id, "");
+                let size = |name| element.attr(name).and_then(|size| size.trim().parse().ok());
+                if let (Some(width), Some(height)) = (size("ac:width"), size("ac:height")) {
+                    attrs = attrs.with_size(width, height);
+                }
+                Custom::Blocks(vec![Node::MediaSingle {
+                    content: vec![Node::Media { attrs }],
+                    attrs: MediaSingleAttrs::new(MediaSingleLayout::Center),
+                }])
+            }
+            "ac:emoticon" => {
+                let short_name = match element.attr("ac:emoji-shortname") {
+                    Some(short_name) => short_name.to_string(),
+                    None => format!(":{}:", element.attr("ac:name").unwrap_or("blue-star")),
+                };
+                let mut attrs = EmojiAttrs::new(short_name);
+                if let Some(id) = element.attr("ac:emoji-id") {
+                    attrs = attrs.with_id(id);
+                }
+                if let Some(text) = element.attr("ac:emoji-fallback") {
+                    attrs = attrs.with_text(text);
+                }
+                Custom::Inline(vec![Node::Emoji { attrs }])
+            }
+            "time" => {
+                let date = element.attr("datetime").and_then(|datetime| {
+                    let mut parts = datetime.trim().splitn(3, '-');
+                    let year = parts.next()?.parse().ok()?;
+                    let month = parts.next()?.parse().ok()?;
+                    let day = parts.next()?.parse().ok()?;
+                    DateAttrs::from_ymd(year, month, day).ok()
+                });
+                match date {
+                    Some(attrs) => Custom::Inline(vec![Node::Date { attrs }]),
+                    None => return None,
+                }
+            }
+            "a" if element.attr("data-card-appearance").is_some() => {
+                match element.attr("href").and_then(safe_url) {
+                    Some(url) => Custom::Inline(vec![Node::InlineCard { attrs: InlineCardAttrs::from_url(url) }]),
+                    None => return None,
+                }
+            }
+            name if name.starts_with("ac:") || name.starts_with("ri:") => Custom::Skip,
+            _ => return None,
+        })
+    }
+}
+
+impl Storage {
+    fn read_macro(&self, element: &Element, reader: &Reader) -> Custom {
+        let name = element.attr("ac:name").unwrap_or_default();
+        let parameter = |name: &str| {
+            element
+                .elements()
+                .find(|child| child.name == "ac:parameter" && child.attr("ac:name") == Some(name))
+                .map(Element::text)
+        };
+        let body = || element.child("ac:rich-text-body").map_or_else(Vec::new, |body| reader.blocks(&body.children));
+        let block = match name {
+            "info" | "tip" | "note" | "warning" | "panel" => {
+                let panel_type = match name {
+                    "info" => PanelType::Info,
+                    "tip" => PanelType::Success,
+                    "note" => PanelType::Warning,
+                    "warning" => PanelType::Error,
+                    _ => PanelType::Note,
+                };
+                let mut content = Vec::new();
+                if let Some(title) = parameter("title").filter(|title| !title.is_empty()) {
+                    content.push(Node::paragraph(vec![Node::text_with_marks(title, vec![Mark::Strong])]));
+                }
+                content.extend(body());
+                if content.is_empty() {
+                    content.push(Node::paragraph(Vec::new()));
+                }
+                Node::Panel { content, attrs: PanelAttrs::new(panel_type) }
+            }
+            "expand" => Node::Expand {
+                content: body(),
+                attrs: ExpandAttrs::new(parameter("title").unwrap_or_default()),
+                marks: None,
+            },
+            "code" | "noformat" => {
+                let code = element.child("ac:plain-text-body").map(Element::text).unwrap_or_default();
+                let language = parameter("language").filter(|_| name == "code");
+                Node::code_block(language.as_deref(), code)
+            }
+            "status" => {
+                let color = match parameter("colour").unwrap_or_default().to_lowercase().as_str() {
+                    "purple" => StatusColor::Purple,
+                    "blue" => StatusColor::Blue,
+                    "red" => StatusColor::Red,
+                    "yellow" => StatusColor::Yellow,
+                    "green" => StatusColor::Green,
+                    _ => StatusColor::Neutral,
+                };
+                let attrs = StatusAttrs::new(parameter("title").unwrap_or_default(), color);
+                return Custom::Inline(vec![Node::Status { attrs }]);
+            }
+            _ => return Custom::Blocks(body()),
+        };
+        Custom::Blocks(vec![block])
+    }
+}
+
+fn task_list(element: &Element, reader: &Reader) -> Node {
+    let mut content = Vec::new();
+    for child in element.elements() {
+        match child.name.as_str() {
+            "ac:task" => {
+                let text = |name| child.child(name).map(Element::text).unwrap_or_default();
+                let state = if text("ac:task-status").trim() == "complete" { TaskState::Done } else { TaskState::Todo };
+                let body = child.child("ac:task-body").map_or(&[][..], |body| &body.children);
+                content.push(Node::TaskItem {
+                    content: reader.inline(body, &[]),
+                    attrs: TaskItemAttrs::new(text("ac:task-id").trim(), state),
+                });
+            }
+            "ac:task-list" => content.push(task_list(child, reader)),
+            _ => {}
+        }
+    }
+    Node::TaskList { content, attrs: TaskListAttrs::new("") }
+}
+
+// Text for a link to a page, attachment or space with no body of its own.
+fn link_target(element: &Element) -> Option<String> {
+    element.elements().find_map(|target| {
+        let attr = match target.name.as_str() {
+            "ri:page" | "ri:blog-post" => "ri:content-title",
+            "ri:attachment" => "ri:filename",
+            "ri:space" => "ri:space-key",
+            "ri:url" => "ri:value",
+            _ => return None,
+        };
+        target.attr(attr).map(str::to_string)
+    })
+}
+
+#[cfg(test)]
+mod storage_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+
+    #[test]
+    fn should_read_storage_format() {
+        let doc = from_storage(concat!(
+            r#"<ac:structured-macro ac:name="info" ac:schema-version="1">"#,
+            r#"<ac:parameter ac:name="title">Heads up</ac:parameter><ac:rich-text-body>"#,
+            r#"<p>Ask <ac:link><ri:user ri:account-id="5b10a2" /></ac:link> first.</p></ac:rich-text-body>"#,
+            "</ac:structured-macro>\n",
+            "<ac:task-list>\n<ac:task><ac:task-id>7</ac:task-id><ac:task-status>complete</ac:task-status>",
+            "<ac:task-body>Ship <strong>it</strong></ac:task-body></ac:task>\n</ac:task-list>\n",
+            r#"<p><ac:image ac:width="200" ac:height="100"><ri:attachment ri:filename="chart.png" /></ac:image></p>"#,
+            r#"<ac:structured-macro ac:name="expand"><ac:parameter ac:name="title">More</ac:parameter>"#,
+            "<ac:rich-text-body><p>Hidden</p></ac:rich-text-body></ac:structured-macro>",
+            r#"<ac:structured-macro ac:name="code"><ac:parameter ac:name="language">sql</ac:parameter>"#,
+            "<ac:plain-text-body><![CDATA[select 1 < 2;]]></ac:plain-text-body></ac:structured-macro>",
+        ));
+        let Node::Doc { content, .. } = &doc else { unreachable!() };
+
+        assert_eq!(content, &vec![
+            Node::Panel {
+                content: vec![
+                    Node::paragraph(vec![Node::text_with_marks("Heads up", vec![Mark::Strong])]),
+                    Node::paragraph(vec![
+                        Node::text("Ask "),
+                        Node::Mention { attrs: MentionAttrs::new("5b10a2") },
+                        Node::text(" first."),
+                    ]),
+                ],
+                attrs: PanelAttrs::new(PanelType::Info),
+            },
+            Node::TaskList {
+                content: vec![Node::TaskItem {
+                    content: vec![Node::text("Ship "), Node::text_with_marks("it", vec![Mark::Strong])],
+                    attrs: TaskItemAttrs::new("7", TaskState::Done),
+                }],
+                attrs: TaskListAttrs::new(""),
+            },
+            Node::MediaSingle {
+                content: vec![Node::Media {
+                    attrs: MediaAttrs::new(MediaType::File, "chart.png", "").with_size(200, 100),
+                }],
+                attrs: MediaSingleAttrs::new(MediaSingleLayout::Center),
+            },
+            Node::Expand { content: vec![paragraph("Hidden")], attrs: ExpandAttrs::new("More"), marks: None },
+            Node::code_block(Some("sql"), "select 1 < 2;"),
+        ]);
+    }
+
+    #[test]
+    fn should_import_images_as_external_or_placeholder_media() {
+        let doc = from_storage(concat!(
+            r#"<ac:image><ri:url ri:value="https://cdn.example/chart.png" /></ac:image>"#,
+            r#"<ac:image><ri:attachment ri:filename="chart.png" /></ac:image>"#,
+        ));
+        let media = |media_type: MediaType, id: &str| Node::MediaSingle {
+            content: vec![Node::Media { attrs: MediaAttrs::new(media_type, id, "") }],
+            attrs: MediaSingleAttrs::new(MediaSingleLayout::Center),
+        };
+
+        assert_eq!(
+            doc,
+            Node::doc(vec![
+                media(MediaType::External, "https://cdn.example/chart.png"),
+                media(MediaType::File, "chart.png"),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_round_trip_through_storage_format() {
+        let doc = Node::doc(vec![
+            Node::heading(1, vec![Node::text("Plan & scope")]),
+            Node::Panel { content: vec![paragraph("Careful")], attrs: PanelAttrs::new(PanelType::Error) },
+            Node::bullet_list(vec![Node::list_item(vec![
+                Node::paragraph(vec![
+                    Node::text_with_marks("bold link", vec![
+                        Mark::Link { attrs: LinkAttrs::new("https://example.com") },
+                        Mark::Strong,
+                    ]),
+                    Node::text(" and "),
+                    Node::Status { attrs: StatusAttrs::new("DONE", StatusColor::Green) },
+                ]),
+                Node::ordered_list(vec![Node::list_item(vec![paragraph("nested")])]),
+            ])]),
+            Node::table(vec![Node::table_row(vec![
+                Node::table_header(vec![paragraph("Key")]),
+                Node::table_cell(vec![Node::paragraph(vec![Node::Date {
+                    attrs: DateAttrs::from_ymd(2024, 2, 29).unwrap(),
+                }])]),
+            ])]),
+            Node::code_block(None, "a ]]> b"),
+        ]);
+        let xhtml = to_storage(&doc);
+
+        assert!(xhtml.starts_with(concat!(
+            r#"<h1>Plan &amp; scope</h1><ac:structured-macro ac:name="warning"><ac:rich-text-body><p>Careful</p>"#,
+            r#"</ac:rich-text-body></ac:structured-macro><ul><li><a href="https://example.com"><strong>bold link"#,
+        )));
+        assert_eq!(from_storage(&xhtml), doc);
+    }
+
+    #[test]
+    fn should_reject_bad_dates_and_survive_deep_nesting() {
+        let doc = from_storage(r#"<p><time datetime="2024-257-01" /></p>"#);
+        assert_eq!(doc, Node::doc(Vec::new()));
+
+        let doc = from_storage(&format!("{}deep", "<blockquote>".repeat(100_000)));
+        assert_eq!(doc.text_content(), "deep");
+    }
+}
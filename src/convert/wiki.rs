@@ -0,0 +1,823 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::model::{
+    Color, ColorAttrs, InlineCardAttrs, LinkAttrs, Mark, MediaAttrs, MediaSingleAttrs, MediaSingleLayout, MediaType,
+    MentionAttrs, Node, PanelAttrs, PanelType, SubSupAttrs, TaskState,
+};
+
+/// Jira wiki markup for a node.
+///
+/// Nodes the markup has no form for are approximated: expands become titled
+/// panels, task lists become bullet lists with `(/)` marking finished tasks,
+/// and status lozenges, dates and emoji become their text.
+pub fn to_wiki(node: &Node) -> String {
+    block(node).trim_end().to_string()
+}
+
+/// Parses Jira wiki markup into a `doc`. Markup is never rejected: anything
+/// unrecognised is kept as text.
+///
+/// `{info}`, `{tip}`, `{note}` and `{warning}` become info, success, warning
+/// and error panels, and a plain `{panel}` a note panel, with any title as a
+/// bold first paragraph.
+///
+/// An `!image!` of a URL becomes external media. An attachment is only known
+/// by its filename, which the media API cannot look up: it becomes `file`
+/// media with the filename as `id` and an empty `collection`, a placeholder
+/// to map to the uploaded file (e.g. by a
+/// [`MediaResolver`](crate::resolve::MediaResolver) keyed by filename)
+/// before the document is stored.
+pub fn from_wiki(markup: &str) -> Node {
+    Node::doc(parse_blocks(&markup.replace("\r\n", "\n")))
+}
+
+// ------ Writing -------
+
+fn blocks(content: &[Node]) -> String {
+    content.iter().map(block).filter(|text| !text.is_empty()).collect::<Vec<_>>().join("\n\n")
+}
+
+fn block(node: &Node) -> String {
+    match node {
+        Node::Doc { content, .. } | Node::ListItem { content } | Node::TableRow { content } => blocks(content),
+        Node::TableCell { content, .. } | Node::TableHeader { content, .. } => blocks(content),
+        Node::Paragraph { content, .. } => guard_line_starts(&inline(content, false)),
+        Node::Heading { content, attrs } => format!("h{}. {}", attrs.level.clamp(1, 6), inline(content, false)),
+        Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } | Node::DecisionList { .. } => {
+            list_lines(node, "").join("\n")
+        }
+        Node::Blockquote { content } => format!("{{quote}}\n{}\n{{quote}}", blocks(content)),
+        Node::CodeBlock { content, attrs } => {
+            let code: String = content
+                .iter()
+                .flatten()
+                .filter_map(|node| match node {
+                    Node::Text { text, .. } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            // The first closing tag ends the macro whatever surrounds it, so
+            // code holding `{code}` goes in a `{noformat}` block, which has no
+            // language. Code holding both has its closing tags broken up.
+            let code_macro = match attrs.as_ref().filter(|attrs| !attrs.language.is_empty()) {
+                Some(attrs) => format!("code:{}", attrs.language),
+                None => "code".to_string(),
+            };
+            let (open, close) = if !code.contains("{code}") {
+                (code_macro.as_str(), "code")
+            } else if !code.contains("{noformat}") {
+                ("noformat", "noformat")
+            } else {
+                return format!("{{noformat}}\n{}\n{{noformat}}", code.replace("{noformat}", "{\u{200b}noformat}"));
+            };
+            format!("{{{}}}\n{}\n{{{}}}", open, code, close)
+        }
+        Node::Panel { content, attrs } => {
+            let name = match attrs.panel_type {
+                PanelType::Info => "info",
+                PanelType::Success => "tip",
+                PanelType::Warning => "note",
+                PanelType::Error => "warning",
+                _ => "panel",
+            };
+            format!("{{{}}}\n{}\n{{{}}}", name, blocks(content), name)
+        }
+        Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+            let title = attrs.title.replace(['|', '}'], " ");
+            format!("{{panel:title={}}}\n{}\n{{panel}}", title, blocks(content))
+        }
+        Node::Rule => "----".to_string(),
+        Node::Table { content, .. } => content.iter().map(table_row).collect::<Vec<_>>().join("\n"),
+        Node::MediaSingle { content, .. } | Node::MediaGroup { content } => {
+            content.iter().map(block).collect::<Vec<_>>().join(" ")
+        }
+        Node::Media { attrs } => format!("!{}!", attrs.id),
+        inline_node => inline(std::slice::from_ref(inline_node), false),
+    }
+}
+
+fn list_lines(list: &Node, prefix: &str) -> Vec<String> {
+    let marker = if matches!(list, Node::OrderedList { .. }) { '#' } else { '*' };
+    let prefix = format!("{}{}", prefix, marker);
+    let mut lines = Vec::new();
+    for item in list.content().into_iter().flatten() {
+        match item {
+            Node::TaskItem { content, attrs } => {
+                let done = if attrs.state == TaskState::Done { "(/) " } else { "" };
+                lines.push(format!("{} {}{}", prefix, done, inline(content, false)));
+            }
+            Node::DecisionItem { content, .. } => lines.push(format!("{} {}", prefix, inline(content, false))),
+            _ => {
+                let mut text: Option<String> = None;
+                let mut nested = Vec::new();
+                for child in item.content().into_iter().flatten() {
+                    match child {
+                        Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } => {
+                            nested.extend(list_lines(child, &prefix))
+                        }
+                        // Further paragraphs join the item's line with forced breaks.
+                        other => {
+                            let other = block(other).replace('\n', " \\\\ ");
+                            text = Some(match text {
+                                Some(text) => format!("{} \\\\ {}", text, other),
+                                None => other,
+                            });
+                        }
+                    }
+                }
+                lines.push(format!("{} {}", prefix, text.unwrap_or_default()));
+                lines.extend(nested);
+            }
+        }
+    }
+    lines
+}
+
+fn table_row(row: &Node) -> String {
+    let mut line = String::new();
+    let mut separator = "|";
+    for cell in row.content().into_iter().flatten() {
+        separator = if matches!(cell, Node::TableHeader { .. }) { "||" } else { "|" };
+        let content = cell.content().into_iter().flatten();
+        let text: Vec<String> = content
+            .map(|block| match block {
+                Node::Paragraph { content, .. } => inline(content, true),
+                other => block_in_cell(other),
+            })
+            .collect();
+        line.push_str(separator);
+        // An empty cell needs a space, or its separators read as one `||`.
+        line.push_str(&Some(text.join(" \\\\ ")).filter(|text| !text.is_empty()).unwrap_or_else(|| " ".to_string()));
+    }
+    line.push_str(separator);
+    line
+}
+
+fn block_in_cell(node: &Node) -> String {
+    block(node).replace('\n', " \\\\ ")
+}
+
+fn inline(content: &[Node], in_table: bool) -> String {
+    let mut out = String::new();
+    for node in content {
+        match node {
+            Node::Text { text, marks } => {
+                let marks = marks.as_deref().unwrap_or_default();
+                let mut text = if marks.contains(&Mark::Code) {
+                    format!("{{{{{}}}}}", text.replace('}', "\\}"))
+                } else {
+                    escape(text)
+                };
+                // Innermost first, links outermost.
+                for mark in [Mark::SubSup { attrs: SubSupAttrs::Sub }, Mark::SubSup { attrs: SubSupAttrs::Sup }] {
+                    if marks.contains(&mark) {
+                        let delimiter = if mark == (Mark::SubSup { attrs: SubSupAttrs::Sub }) { "~" } else { "^" };
+                        text = format!("{}{}{}", delimiter, text, delimiter);
+                    }
+                }
+                let delimiters = [(Mark::Strike, "-"), (Mark::Underline, "+"), (Mark::Em, "_"), (Mark::Strong, "*")];
+                for (mark, delimiter) in delimiters {
+                    if marks.contains(&mark) {
+                        text = format!("{}{}{}", delimiter, text, delimiter);
+                    }
+                }
+                for mark in marks {
+                    if let Mark::TextColor { attrs } = mark {
+                        text = format!("{{color:{}}}{}{{color}}", attrs.color, text);
+                    }
+                }
+                for mark in marks {
+                    if let Mark::Link { attrs } = mark {
+                        let href = escape_href(&attrs.href);
+                        text = if escape(&attrs.href) == text && href == attrs.href {
+                            format!("[{}]", href)
+                        } else {
+                            format!("[{}|{}]", text, href)
+                        };
+                    }
+                }
+                out.push_str(&text);
+            }
+            Node::HardBreak { .. } => out.push_str(if in_table { " \\\\ " } else { "\n" }),
+            Node::Mention { attrs } => out.push_str(&format!("[~accountid:{}]", attrs.id)),
+            Node::InlineCard { attrs } => {
+                let url = escape_href(attrs.url.as_deref().unwrap_or_default());
+                out.push_str(&format!("[{}|{}|smart-link]", url, url));
+            }
+            other => out.push_str(&escape(&other.text_content())),
+        }
+    }
+    out
+}
+
+// Backslash-escapes characters that would otherwise read as markup. Mark
+// delimiters only count next to a word boundary, so `e-mail` is left alone.
+fn escape(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        let boundary = || {
+            let before = index.checked_sub(1).map(|index| chars[index]);
+            let after = chars.get(index + 1);
+            !before.is_some_and(char::is_alphanumeric) || !after.is_some_and(|c| c.is_alphanumeric())
+        };
+        let special = match c {
+            '\\' | '{' | '}' | '[' | ']' | '|' => true,
+            '*' | '_' | '+' | '-' | '^' | '~' | '?' | '!' => boundary(),
+            _ => false,
+        };
+        if special {
+            out.push('\\');
+        }
+        out.push(*c);
+    }
+    out
+}
+
+// Inside `[text|url]` the first `|` or `]` ends the URL, so brackets and
+// pipes in it are percent-encoded, which leaves its meaning unchanged.
+fn escape_href(href: &str) -> String {
+    href.replace('|', "%7C").replace('[', "%5B").replace(']', "%5D")
+}
+
+// A line of a paragraph starting like a heading, quote or numbered list would
+// be read as one; the backslash keeps it text. Other markers (`*`, `-`, `|`,
+// `{`) are already escaped.
+fn guard_line_starts(text: &str) -> String {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    let marker = MARKER.get_or_init(|| Regex::new(r"(?m)^(\s*)(#|h[1-6]\.|bq\.)").expect("marker pattern is valid"));
+    marker.replace_all(text, "$1\\$2").into_owned()
+}
+
+// ------ Parsing -------
+
+const MACROS: &[&str] = &["code", "noformat", "quote", "panel", "info", "tip", "note", "warning"];
+
+enum Pending<'a> {
+    None,
+    Paragraph(Vec<&'a str>),
+    List(Vec<(&'a str, &'a str)>),
+    Table(Vec<&'a str>),
+}
+
+fn parse_blocks(text: &str) -> Vec<Node> {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    static LIST: OnceLock<Regex> = OnceLock::new();
+    let heading = HEADING.get_or_init(|| Regex::new(r"^h([1-6])\.\s*(.*)$").expect("heading pattern is valid"));
+    let list = LIST.get_or_init(|| Regex::new(r"^([*#]+|-)\s+(.*)$").expect("list pattern is valid"));
+
+    let mut blocks = Vec::new();
+    let mut pending = Pending::None;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (line, next) = rest.split_once('\n').unwrap_or((rest, ""));
+        let trimmed = line.trim();
+
+        if let Some((node, after)) = parse_macro(rest.trim_start_matches([' ', '\t'])) {
+            flush(&mut pending, &mut blocks);
+            blocks.push(node);
+            // Text after the closing tag on the same line starts a new line.
+            rest = after.strip_prefix('\n').unwrap_or(after);
+            continue;
+        }
+        rest = next;
+
+        if trimmed.is_empty() {
+            flush(&mut pending, &mut blocks);
+        } else if let Some(captures) = heading.captures(trimmed) {
+            flush(&mut pending, &mut blocks);
+            let level = captures[1].parse().expect("level is a digit");
+            blocks.push(Node::heading(level, parse_inline(captures.get(2).map_or("", |text| text.as_str()))));
+        } else if let Some(quote) = trimmed.strip_prefix("bq.") {
+            flush(&mut pending, &mut blocks);
+            blocks.push(Node::Blockquote { content: vec![Node::paragraph(parse_inline(quote.trim()))] });
+        } else if trimmed == "----" {
+            flush(&mut pending, &mut blocks);
+            blocks.push(Node::Rule);
+        } else if let Some(captures) = list.captures(trimmed) {
+            let markers = captures.get(1).expect("group 1 always matches").as_str();
+            let item = captures.get(2).expect("group 2 always matches").as_str();
+            match &mut pending {
+                Pending::List(items) => items.push((markers, item)),
+                _ => {
+                    flush(&mut pending, &mut blocks);
+                    pending = Pending::List(vec![(markers, item)]);
+                }
+            }
+        } else if trimmed.starts_with('|') {
+            match &mut pending {
+                Pending::Table(rows) => rows.push(trimmed),
+                _ => {
+                    flush(&mut pending, &mut blocks);
+                    pending = Pending::Table(vec![trimmed]);
+                }
+            }
+        } else {
+            match &mut pending {
+                Pending::Paragraph(lines) => lines.push(line),
+                _ => {
+                    flush(&mut pending, &mut blocks);
+                    pending = Pending::Paragraph(vec![line]);
+                }
+            }
+        }
+    }
+    flush(&mut pending, &mut blocks);
+    blocks
+}
+
+fn flush(pending: &mut Pending, blocks: &mut Vec<Node>) {
+    match std::mem::replace(pending, Pending::None) {
+        Pending::None => {}
+        Pending::Paragraph(lines) => blocks.push(paragraph(&lines.join("\n"))),
+        Pending::List(items) => blocks.extend(build_lists(&items, 0)),
+        Pending::Table(rows) => blocks.push(Node::table(rows.into_iter().map(parse_row).collect())),
+    }
+}
+
+// A paragraph made only of an `!image!` is a media block.
+fn paragraph(text: &str) -> Node {
+    static IMAGE: OnceLock<Regex> = OnceLock::new();
+    let image = IMAGE.get_or_init(|| Regex::new(r"^!([^!|\s]+)(?:\|[^!]*)?!$").expect("image pattern is valid"));
+    let Some(captures) = image.captures(text.trim()) else {
+        return Node::paragraph(parse_inline(text));
+    };
+    let target = &captures[1];
+    let media_type = if target.contains("://") { MediaType::External } else { MediaType::File };
+    Node::MediaSingle {
+        content: vec![Node::Media { attrs: MediaAttrs::new(media_type, target, "") }],
+        attrs: MediaSingleAttrs::new(MediaSingleLayout::Center),
+    }
+}
+
+// A `{macro}...{macro}` block starting the text, and the text after it.
+fn parse_macro(text: &str) -> Option<(Node, &str)> {
+    let tag_end = text.strip_prefix('{')?.find('}')? + 1;
+    let tag = &text[1..tag_end];
+    let (name, params) = tag.split_once(':').unwrap_or((tag, ""));
+    if !MACROS.contains(&name) {
+        return None;
+    }
+    let body = &text[tag_end + 1..];
+    let close = format!("{{{}}}", name);
+    let (body, after) = match body.find(&close) {
+        Some(end) => (&body[..end], &body[end + close.len()..]),
+        None => (body, ""),
+    };
+    let params: Vec<&str> = params.split('|').filter(|param| !param.is_empty()).collect();
+
+    let node = match name {
+        "code" | "noformat" => {
+            let code = body.strip_prefix('\n').unwrap_or(body);
+            let code = code.strip_suffix('\n').unwrap_or(code);
+            let language = params.iter().find(|param| !param.contains('=')).copied().filter(|_| name == "code");
+            Node::code_block(language, code)
+        }
+        "quote" => Node::Blockquote { content: parse_blocks(body) },
+        _ => {
+            let panel_type = match name {
+                "info" => PanelType::Info,
+                "tip" => PanelType::Success,
+                "note" => PanelType::Warning,
+                "warning" => PanelType::Error,
+                _ => PanelType::Note,
+            };
+            let mut content = Vec::new();
+            if let Some(title) = params.iter().find_map(|param| param.strip_prefix("title=")) {
+                content.push(Node::paragraph(vec![Node::text_with_marks(title, vec![Mark::Strong])]));
+            }
+            content.extend(parse_blocks(body));
+            Node::Panel { content, attrs: PanelAttrs::new(panel_type) }
+        }
+    };
+    Some((node, after))
+}
+
+// Lists from `(markers, text)` lines, the markers' characters at `depth`
+// and below having already placed them.
+fn build_lists(items: &[(&str, &str)], depth: usize) -> Vec<Node> {
+    let kind = |markers: &str| markers.as_bytes().get(depth).map(|marker| *marker == b'#');
+    let mut lists = Vec::new();
+    let mut index = 0;
+    while index < items.len() {
+        let ordered = kind(items[index].0);
+        let mut list_items = Vec::new();
+        while index < items.len() && kind(items[index].0) == ordered {
+            let mut content = Vec::new();
+            if items[index].0.len() == depth + 1 {
+                content.push(Node::paragraph(parse_inline(items[index].1)));
+                index += 1;
+            } else {
+                // A deeper line with no item above it.
+                content.push(Node::paragraph(Vec::new()));
+            }
+            let nested = index;
+            while index < items.len() && items[index].0.len() > depth + 1 && kind(items[index].0) == ordered {
+                index += 1;
+            }
+            content.extend(build_lists(&items[nested..index], depth + 1));
+            list_items.push(Node::list_item(content));
+        }
+        lists.push(if ordered == Some(true) { Node::ordered_list(list_items) } else { Node::bullet_list(list_items) });
+    }
+    lists
+}
+
+fn parse_row(line: &str) -> Node {
+    let mut cells = Vec::new();
+    let mut rest = line;
+    while let Some(after) = rest.strip_prefix('|') {
+        let (header, after) = match after.strip_prefix('|') {
+            Some(after) => (true, after),
+            None => (false, after),
+        };
+        let end = cell_end(after);
+        let text = after[..end].trim();
+        rest = &after[end..];
+        if rest.is_empty() && text.is_empty() {
+            break;
+        }
+        let content = vec![Node::paragraph(parse_inline(text))];
+        cells.push(if header { Node::table_header(content) } else { Node::table_cell(content) });
+    }
+    Node::table_row(cells)
+}
+
+// Byte offset of the next `|` separating cells, skipping escaped pipes and
+// those inside links and `{{monospace}}`.
+fn cell_end(text: &str) -> usize {
+    let mut brackets = 0;
+    let mut monospace = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '{' if text[at..].starts_with("{{") => {
+                monospace = true;
+                chars.next();
+            }
+            '}' if monospace && text[at..].starts_with("}}") => {
+                monospace = false;
+                chars.next();
+            }
+            '[' if !monospace => brackets += 1,
+            ']' if !monospace && brackets > 0 => brackets -= 1,
+            '|' if !monospace && brackets == 0 => return at,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn parse_inline(text: &str) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    parse_inline_into(text, &[], &mut nodes);
+    // Merge neighbours with the same marks, left apart by escapes.
+    let mut merged: Vec<Node> = Vec::new();
+    for mut node in nodes {
+        match (merged.last_mut(), &mut node) {
+            (Some(Node::Text { text, marks }), Node::Text { text: next, marks: next_marks }) if marks == next_marks => {
+                text.push_str(next)
+            }
+            _ => merged.push(node),
+        }
+    }
+    merged
+}
+
+fn parse_inline_into(text: &str, marks: &[Mark], out: &mut Vec<Node>) {
+    let mut plain = String::new();
+    let mut at = 0;
+    while let Some(c) = text[at..].chars().next() {
+        let rest = &text[at..];
+        if rest.starts_with("\\\\") || c == '\n' {
+            push_text(&mut plain, marks, out);
+            out.push(Node::HardBreak { attrs: None });
+            at += if c == '\n' { 1 } else { 2 };
+            continue;
+        }
+        if c == '\\' {
+            if let Some(escaped) = rest[1..].chars().next() {
+                plain.push(escaped);
+                at += 1 + escaped.len_utf8();
+                continue;
+            }
+        }
+        if let Some(code) = rest.strip_prefix("{{") {
+            if let Some(end) = code.find("}}") {
+                push_text(&mut plain, marks, out);
+                let code = code[..end].replace("\\}", "}");
+                out.push(marked_text(code, &with_mark(marks, Mark::Code)));
+                at += 4 + end;
+                continue;
+            }
+        }
+        if let Some(color) = rest.strip_prefix("{color:") {
+            if let Some((value, inner, length)) = color.split_once('}').and_then(|(value, inner)| {
+                let end = inner.find("{color}")?;
+                Some((value, &inner[..end], 7 + value.len() + 1 + end + 7))
+            }) {
+                push_text(&mut plain, marks, out);
+                let color = Color::from_str(value).ok().or_else(|| Color::from_palette_name(&value.to_lowercase()));
+                match color {
+                    Some(color) => {
+                        let color = Mark::TextColor { attrs: ColorAttrs::new(color) };
+                        parse_inline_into(inner, &with_mark(marks, color), out)
+                    }
+                    None => parse_inline_into(inner, marks, out),
+                }
+                at += length;
+                continue;
+            }
+        }
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                if let Some(nodes) = parse_link(&rest[1..end], marks) {
+                    push_text(&mut plain, marks, out);
+                    out.extend(nodes);
+                    at += end + 1;
+                    continue;
+                }
+            }
+        }
+        if let Some((mark, delimiter)) = mark_delimiter(rest) {
+            let before = text[..at].chars().next_back();
+            let opens = !before.is_some_and(char::is_alphanumeric)
+                && rest[delimiter.len()..].chars().next().is_some_and(|next| !next.is_whitespace());
+            if let Some(end) = opens.then(|| closing(&rest[delimiter.len()..], delimiter)).flatten() {
+                push_text(&mut plain, marks, out);
+                let inner = &rest[delimiter.len()..delimiter.len() + end];
+                parse_inline_into(inner, &with_mark(marks, mark), out);
+                at += 2 * delimiter.len() + end;
+                continue;
+            }
+        }
+        plain.push(c);
+        at += c.len_utf8();
+    }
+    push_text(&mut plain, marks, out);
+}
+
+fn mark_delimiter(text: &str) -> Option<(Mark, &'static str)> {
+    if text.starts_with("??") {
+        return Some((Mark::Em, "??"));
+    }
+    Some(match text.chars().next()? {
+        '*' => (Mark::Strong, "*"),
+        '_' => (Mark::Em, "_"),
+        '+' => (Mark::Underline, "+"),
+        '-' => (Mark::Strike, "-"),
+        '^' => (Mark::SubSup { attrs: SubSupAttrs::Sup }, "^"),
+        '~' => (Mark::SubSup { attrs: SubSupAttrs::Sub }, "~"),
+        _ => return None,
+    })
+}
+
+// Offset of the delimiter closing a mark: after some text, not after
+// whitespace and not followed by a letter or digit.
+fn closing(text: &str, delimiter: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (at, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        if c == '\\' {
+            escaped = true;
+            continue;
+        }
+        if at > 0
+            && text[at..].starts_with(delimiter)
+            && !text[..at].ends_with(char::is_whitespace)
+            && !text[at + delimiter.len()..].starts_with(char::is_alphanumeric)
+        {
+            return Some(at);
+        }
+    }
+    None
+}
+
+// `[url]`, `[text|url]`, `[url|url|smart-link]` or `[~accountid:id]`.
+fn parse_link(inner: &str, marks: &[Mark]) -> Option<Vec<Node>> {
+    if let Some(user) = inner.strip_prefix('~') {
+        let id = user.strip_prefix("accountid:").unwrap_or(user);
+        return Some(vec![Node::Mention { attrs: MentionAttrs::new(id) }]);
+    }
+    let parts: Vec<&str> = inner.split('|').collect();
+    match parts.as_slice() {
+        [url] if url.contains("://") || url.starts_with("mailto:") => {
+            Some(vec![marked_text(url.to_string(), &with_mark(marks, link(url)))])
+        }
+        [_, url, "smart-link"] => Some(vec![Node::InlineCard { attrs: InlineCardAttrs::from_url(url.trim()) }]),
+        [text, url] => {
+            let mut nodes = Vec::new();
+            parse_inline_into(text, &with_mark(marks, link(url.trim())), &mut nodes);
+            Some(nodes)
+        }
+        _ => None,
+    }
+}
+
+fn link(href: &str) -> Mark {
+    Mark::Link { attrs: LinkAttrs::new(href) }
+}
+
+fn with_mark(marks: &[Mark], mark: Mark) -> Vec<Mark> {
+    let mut marks = marks.to_vec();
+    if !marks.contains(&mark) {
+        marks.push(mark);
+    }
+    marks
+}
+
+fn marked_text(text: String, marks: &[Mark]) -> Node {
+    Node::Text { text, marks: (!marks.is_empty()).then(|| marks.to_vec()) }
+}
+
+fn push_text(plain: &mut String, marks: &[Mark], out: &mut Vec<Node>) {
+    if !plain.is_empty() {
+        out.push(marked_text(std::mem::take(plain), marks));
+    }
+}
+
+#[cfg(test)]
+mod wiki_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+
+    #[test]
+    fn should_parse_inline_marks_links_and_mentions() {
+        let doc = from_wiki(
+            "*Bold _both_* and {{x*y}} in e-mail, {color:#ff5630}red{color} [docs|https://d.example] \\*not\\* \
+             [~accountid:abc]",
+        );
+        let Some(Node::Paragraph { content, .. }) = doc.descendant(&[0]) else { unreachable!() };
+        let red = Mark::TextColor { attrs: ColorAttrs::new(Color::new(0xff, 0x56, 0x30)) };
+
+        assert_eq!(
+            content,
+            &vec![
+                Node::text_with_marks("Bold ", vec![Mark::Strong]),
+                Node::text_with_marks("both", vec![Mark::Strong, Mark::Em]),
+                Node::text(" and "),
+                Node::text_with_marks("x*y", vec![Mark::Code]),
+                Node::text(" in e-mail, "),
+                Node::text_with_marks("red", vec![red]),
+                Node::text(" "),
+                Node::text_with_marks("docs", vec![link("https://d.example")]),
+                Node::text(" *not* "),
+                Node::Mention { attrs: MentionAttrs::new("abc") },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_parse_blocks() {
+        let doc = from_wiki(concat!(
+            "h2. Steps\n",
+            "# Install\n",
+            "#* with cargo\n",
+            "# Run\n",
+            "\n",
+            "||Key||Summary||\n",
+            "|AB-1|[a|b] link|\n",
+            "{code:java|title=Main.java}\n",
+            "int x = 1;\n",
+            "{code}\n",
+            "{warning:title=Careful}\n",
+            "Dragons.\n",
+            "{warning}\n",
+            "----",
+        ));
+        let Node::Doc { content, .. } = &doc else { unreachable!() };
+
+        assert_eq!(content[0], Node::heading(2, vec![Node::text("Steps")]));
+        assert_eq!(
+            content[1],
+            Node::ordered_list(vec![
+                Node::list_item(vec![
+                    paragraph("Install"),
+                    Node::bullet_list(vec![Node::list_item(vec![paragraph("with cargo")])]),
+                ]),
+                Node::list_item(vec![paragraph("Run")]),
+            ])
+        );
+        assert_eq!(
+            content[2],
+            Node::table(vec![
+                Node::table_row(vec![
+                    Node::table_header(vec![paragraph("Key")]),
+                    Node::table_header(vec![paragraph("Summary")]),
+                ]),
+                Node::table_row(vec![
+                    Node::table_cell(vec![paragraph("AB-1")]),
+                    Node::table_cell(vec![Node::paragraph(vec![
+                        Node::text_with_marks("a", vec![link("b")]),
+                        Node::text(" link"),
+                    ])]),
+                ]),
+            ])
+        );
+        assert_eq!(content[3], Node::code_block(Some("java"), "int x = 1;"));
+        assert_eq!(
+            content[4],
+            Node::Panel {
+                content: vec![
+                    Node::paragraph(vec![Node::text_with_marks("Careful", vec![Mark::Strong])]),
+                    paragraph("Dragons."),
+                ],
+                attrs: PanelAttrs::new(PanelType::Error),
+            }
+        );
+        assert_eq!(content[5], Node::Rule);
+    }
+
+    #[test]
+    fn should_import_images_as_external_or_placeholder_media() {
+        let media = |media_type: MediaType, id: &str| Node::MediaSingle {
+            content: vec![Node::Media { attrs: MediaAttrs::new(media_type, id, "") }],
+            attrs: MediaSingleAttrs::new(MediaSingleLayout::Center),
+        };
+
+        assert_eq!(
+            from_wiki("!https://cdn.example/chart.png|width=200!\n\n!chart.png!"),
+            Node::doc(vec![
+                media(MediaType::External, "https://cdn.example/chart.png"),
+                media(MediaType::File, "chart.png"),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_round_trip_through_wiki_markup() {
+        let doc = Node::doc(vec![
+            Node::heading(1, vec![Node::text("Release - notes")]),
+            Node::paragraph(vec![
+                Node::text("Ask "),
+                Node::Mention { attrs: MentionAttrs::new("557058:f5") },
+                Node::text(" about "),
+                Node::text_with_marks("strike", vec![Mark::Strong, Mark::Strike]),
+                Node::HardBreak { attrs: None },
+                Node::text_with_marks("site", vec![link("https://example.com")]),
+            ]),
+            Node::bullet_list(vec![Node::list_item(vec![
+                paragraph("a [b]"),
+                Node::ordered_list(vec![Node::list_item(vec![paragraph("c")])]),
+            ])]),
+            Node::Panel { content: vec![paragraph("Note")], attrs: PanelAttrs::new(PanelType::Info) },
+            Node::code_block(Some("rust"), "fn main() {\n}"),
+            Node::table(vec![Node::table_row(vec![
+                Node::table_header(vec![paragraph("H")]),
+                Node::table_cell(vec![paragraph("1 | 2")]),
+            ])]),
+        ]);
+        let markup = to_wiki(&doc);
+
+        assert_eq!(
+            markup,
+            concat!(
+                "h1. Release \\- notes\n\n",
+                "Ask [~accountid:557058:f5] about *-strike-*\n[site|https://example.com]\n\n",
+                "* a \\[b\\]\n*# c\n\n",
+                "{info}\nNote\n{info}\n\n",
+                "{code:rust}\nfn main() {\n}\n{code}\n\n",
+                "||H|1 \\| 2|",
+            )
+        );
+        assert_eq!(from_wiki(&markup), doc);
+    }
+
+    #[test]
+    fn should_escape_line_starts_code_tags_and_link_urls() {
+        let doc = Node::doc(vec![
+            paragraph("# not a list"),
+            Node::paragraph(vec![
+                Node::text("h1. no heading"),
+                Node::HardBreak { attrs: None },
+                Node::text("bq. no quote"),
+            ]),
+            Node::code_block(Some("text"), "a {code} b"),
+            Node::paragraph(vec![Node::text_with_marks("odd", vec![link("https://a.example/?q=[1]|2")])]),
+        ]);
+        let markup = to_wiki(&doc);
+
+        assert_eq!(
+            markup,
+            concat!(
+                "\\# not a list\n\n",
+                "\\h1. no heading\n\\bq. no quote\n\n",
+                "{noformat}\na {code} b\n{noformat}\n\n",
+                "[odd|https://a.example/?q=%5B1%5D%7C2]",
+            )
+        );
+        let parsed = from_wiki(&markup);
+        let Node::Doc { content, .. } = &parsed else { unreachable!() };
+        assert_eq!(content[..2], doc.content().unwrap()[..2]);
+        assert_eq!(content[2], Node::code_block(None, "a {code} b"));
+    }
+}
@@ -0,0 +1,281 @@
+use std::fmt;
+
+// Seconds since the epoch stop looking plausible around here (year 5138), so
+// larger values are read as the milliseconds some Jira payloads send.
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+// 0000-01-01 and 9999-12-31 in seconds since the epoch.
+const MIN_SECONDS: i64 = -62_167_219_200;
+const MAX_SECONDS: i64 = 253_402_300_799;
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September",
+    "October", "November", "December",
+];
+const WEEKDAYS: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateError {
+    /// The timestamp is not an integer.
+    Malformed(String),
+    /// The timestamp or date lies outside years 0 to 9999.
+    OutOfRange,
+    /// The year, month and day do not name a real day.
+    InvalidDate { year: i32, month: u8, day: u8 },
+    /// UTC offsets are limited to ±18 hours.
+    InvalidOffset(i32),
+}
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateError::Malformed(value) => write!(f, "timestamp {:?} is not an integer", value),
+            DateError::OutOfRange => write!(f, "timestamp is outside years 0 to 9999"),
+            DateError::InvalidDate { year, month, day } => {
+                write!(f, "{:04}-{:02}-{:02} is not a valid date", year, month, day)
+            }
+            DateError::InvalidOffset(minutes) => write!(f, "UTC offset of {} minutes is out of range", minutes),
+        }
+    }
+}
+
+impl std::error::Error for DateError {}
+
+/// A point in time with millisecond precision, as stored by a `date` node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    millis: i64,
+}
+
+impl Timestamp {
+    /// Reads Unix seconds, or milliseconds when the value is too large to be
+    /// a plausible number of seconds.
+    pub fn parse(value: &str) -> Result<Self, DateError> {
+        let number: i64 = value.trim().parse().map_err(|_| DateError::Malformed(value.to_string()))?;
+        let millis = if number.unsigned_abs() >= MILLIS_THRESHOLD.unsigned_abs() {
+            number
+        } else {
+            number.checked_mul(1000).ok_or(DateError::OutOfRange)?
+        };
+        Timestamp::from_millis(millis)
+    }
+
+    pub fn from_millis(millis: i64) -> Result<Self, DateError> {
+        if !(MIN_SECONDS * 1000..=MAX_SECONDS * 1000 + 999).contains(&millis) {
+            return Err(DateError::OutOfRange);
+        }
+        Ok(Timestamp { millis })
+    }
+
+    /// Midnight UTC at the start of the given day.
+    pub fn from_ymd(year: i32, month: u8, day: u8) -> Result<Self, DateError> {
+        let invalid = || DateError::InvalidDate { year, month, day };
+        if !(0..=9999).contains(&year) || !(1..=12).contains(&month) {
+            return Err(invalid());
+        }
+        if day == 0 || day > days_in_month(year, month) {
+            return Err(invalid());
+        }
+        Timestamp::from_millis(days_from_civil(year, month, day) * 86_400_000)
+    }
+
+    pub fn as_millis(self) -> i64 {
+        self.millis
+    }
+
+    pub fn as_seconds(self) -> i64 {
+        self.millis.div_euclid(1000)
+    }
+
+    /// Calendar date and wall clock time at the given offset from UTC.
+    pub fn to_date_time(self, offset: UtcOffset) -> DateTime {
+        let seconds = self.as_seconds() + offset.minutes as i64 * 60;
+        let days = seconds.div_euclid(86_400);
+        let time = seconds.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        DateTime {
+            year,
+            month,
+            day,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+            // 1970-01-01 was a Thursday.
+            weekday: (days + 3).rem_euclid(7) as u8,
+        }
+    }
+}
+
+/// A fixed offset from UTC. Only fixed offsets are supported so that no
+/// timezone database has to be shipped or fetched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct UtcOffset {
+    minutes: i32,
+}
+
+impl UtcOffset {
+    pub const UTC: UtcOffset = UtcOffset { minutes: 0 };
+
+    pub fn from_minutes(minutes: i32) -> Result<Self, DateError> {
+        if minutes.abs() > 18 * 60 {
+            return Err(DateError::InvalidOffset(minutes));
+        }
+        Ok(UtcOffset { minutes })
+    }
+
+    pub fn minutes(self) -> i32 {
+        self.minutes
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// Days since Monday, 0 to 6.
+    pub weekday: u8,
+}
+
+/// How renderers display `date` nodes.
+///
+/// The pattern understands `%Y` (year), `%m` and `%d` (zero padded month and
+/// day), `%e` (unpadded day), `%B` and `%b` (full and abbreviated month name),
+/// `%A` and `%a` (full and abbreviated weekday), `%H`, `%M` and `%S` (time of
+/// day) and `%%`. Anything else is copied through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormat {
+    pub pattern: String,
+    pub offset: UtcOffset,
+}
+
+impl Default for DateFormat {
+    fn default() -> Self {
+        DateFormat { pattern: "%Y-%m-%d".to_string(), offset: UtcOffset::UTC }
+    }
+}
+
+impl DateFormat {
+    pub fn format(&self, timestamp: Timestamp) -> String {
+        let date = timestamp.to_date_time(self.offset);
+        let mut out = String::new();
+        let mut chars = self.pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => out.push_str(&format!("{:04}", date.year)),
+                Some('m') => out.push_str(&format!("{:02}", date.month)),
+                Some('d') => out.push_str(&format!("{:02}", date.day)),
+                Some('e') => out.push_str(&date.day.to_string()),
+                Some('B') => out.push_str(MONTHS[date.month as usize - 1]),
+                Some('b') => out.push_str(&MONTHS[date.month as usize - 1][..3]),
+                Some('A') => out.push_str(WEEKDAYS[date.weekday as usize]),
+                Some('a') => out.push_str(&WEEKDAYS[date.weekday as usize][..3]),
+                Some('H') => out.push_str(&format!("{:02}", date.hour)),
+                Some('M') => out.push_str(&format!("{:02}", date.minute)),
+                Some('S') => out.push_str(&format!("{:02}", date.second)),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+        out
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Howard Hinnant's `days_from_civil` and `civil_from_days`, which count
+// years from March so that the leap day falls at the end.
+fn days_from_civil(year: i32, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year } as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i32, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u8;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u8;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as i32, month, day)
+}
+
+#[cfg(test)]
+mod date_tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_seconds_and_milliseconds() {
+        let seconds = Timestamp::parse("1582152559").unwrap();
+        let millis = Timestamp::parse("1582152559000").unwrap();
+
+        assert_eq!(seconds, millis);
+        assert_eq!(seconds.as_seconds(), 1582152559);
+    }
+
+    #[test]
+    fn should_reject_invalid_timestamps() {
+        assert_eq!(Timestamp::parse("yesterday"), Err(DateError::Malformed("yesterday".to_string())));
+        assert_eq!(Timestamp::parse("999999999999999999"), Err(DateError::OutOfRange));
+        assert_eq!(Timestamp::parse(&i64::MIN.to_string()), Err(DateError::OutOfRange));
+        assert_eq!(Timestamp::parse(&i64::MAX.to_string()), Err(DateError::OutOfRange));
+    }
+
+    #[test]
+    fn should_convert_to_calendar_date() {
+        let date = Timestamp::parse("1582152559").unwrap().to_date_time(UtcOffset::UTC);
+
+        assert_eq!((date.year, date.month, date.day), (2020, 2, 19));
+        assert_eq!((date.hour, date.minute, date.second), (22, 49, 19));
+        assert_eq!(WEEKDAYS[date.weekday as usize], "Wednesday");
+    }
+
+    #[test]
+    fn should_build_timestamps_from_year_month_day() {
+        assert_eq!(Timestamp::from_ymd(2020, 2, 29).unwrap().as_seconds(), 1582934400);
+        assert_eq!(Timestamp::from_ymd(1969, 12, 31).unwrap().as_seconds(), -86400);
+        assert!(Timestamp::from_ymd(2021, 2, 29).is_err());
+        assert!(Timestamp::from_ymd(2021, 13, 1).is_err());
+    }
+
+    #[test]
+    fn should_format_with_pattern_and_offset() {
+        let timestamp = Timestamp::parse("1582152559").unwrap();
+        let format = DateFormat {
+            pattern: "%a %e %b %Y %H:%M, 100%%".to_string(),
+            offset: UtcOffset::from_minutes(120).unwrap(),
+        };
+
+        assert_eq!(format.format(timestamp), "Thu 20 Feb 2020 00:49, 100%");
+        assert_eq!(DateFormat::default().format(timestamp), "2020-02-19");
+    }
+}
@@ -0,0 +1,496 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::model::{EmojiAttrs, Mark, Node};
+
+// Short name without colons, the emoji, and whether it takes a skin tone.
+// Aliases share an emoji; the first name listed for one is its canonical
+// name.
+const EMOJI: &[(&str, &str, bool)] = &[
+    ("grinning", "😀", false),
+    ("smiley", "😃", false),
+    ("smile", "😄", false),
+    ("grin", "😁", false),
+    ("laughing", "😆", false),
+    ("satisfied", "😆", false),
+    ("sweat_smile", "😅", false),
+    ("joy", "😂", false),
+    ("rofl", "🤣", false),
+    ("slight_smile", "🙂", false),
+    ("slightly_smiling_face", "🙂", false),
+    ("upside_down", "🙃", false),
+    ("wink", "😉", false),
+    ("blush", "😊", false),
+    ("innocent", "😇", false),
+    ("heart_eyes", "😍", false),
+    ("kissing_heart", "😘", false),
+    ("yum", "😋", false),
+    ("stuck_out_tongue", "😛", false),
+    ("stuck_out_tongue_winking_eye", "😜", false),
+    ("hugging", "🤗", false),
+    ("thinking", "🤔", false),
+    ("thinking_face", "🤔", false),
+    ("neutral_face", "😐", false),
+    ("expressionless", "😑", false),
+    ("no_mouth", "😶", false),
+    ("smirk", "😏", false),
+    ("unamused", "😒", false),
+    ("rolling_eyes", "🙄", false),
+    ("grimacing", "😬", false),
+    ("relieved", "😌", false),
+    ("pensive", "😔", false),
+    ("sleepy", "😪", false),
+    ("sleeping", "😴", false),
+    ("mask", "😷", false),
+    ("nerd", "🤓", false),
+    ("sunglasses", "😎", false),
+    ("confused", "😕", false),
+    ("worried", "😟", false),
+    ("slight_frown", "🙁", false),
+    ("frowning2", "☹️", false),
+    ("open_mouth", "😮", false),
+    ("astonished", "😲", false),
+    ("flushed", "😳", false),
+    ("fearful", "😨", false),
+    ("cold_sweat", "😰", false),
+    ("cry", "😢", false),
+    ("sob", "😭", false),
+    ("scream", "😱", false),
+    ("confounded", "😖", false),
+    ("persevere", "😣", false),
+    ("disappointed", "😞", false),
+    ("sweat", "😓", false),
+    ("weary", "😩", false),
+    ("tired_face", "😫", false),
+    ("triumph", "😤", false),
+    ("rage", "😡", false),
+    ("angry", "😠", false),
+    ("smiling_imp", "😈", false),
+    ("skull", "💀", false),
+    ("poop", "💩", false),
+    ("clown", "🤡", false),
+    ("ghost", "👻", false),
+    ("alien", "👽", false),
+    ("robot", "🤖", false),
+    ("see_no_evil", "🙈", false),
+    ("wave", "👋", true),
+    ("raised_hand", "✋", true),
+    ("ok_hand", "👌", true),
+    ("v", "✌️", true),
+    ("crossed_fingers", "🤞", true),
+    ("point_up", "☝️", true),
+    ("point_right", "👉", true),
+    ("point_left", "👈", true),
+    ("point_down", "👇", true),
+    ("thumbsup", "👍", true),
+    ("+1", "👍", true),
+    ("thumbsdown", "👎", true),
+    ("-1", "👎", true),
+    ("fist", "✊", true),
+    ("punch", "👊", true),
+    ("clap", "👏", true),
+    ("raised_hands", "🙌", true),
+    ("open_hands", "👐", true),
+    ("pray", "🙏", true),
+    ("handshake", "🤝", false),
+    ("writing_hand", "✍️", true),
+    ("muscle", "💪", true),
+    ("eyes", "👀", false),
+    ("brain", "🧠", false),
+    ("heart", "❤️", false),
+    ("orange_heart", "🧡", false),
+    ("yellow_heart", "💛", false),
+    ("green_heart", "💚", false),
+    ("blue_heart", "💙", false),
+    ("purple_heart", "💜", false),
+    ("black_heart", "🖤", false),
+    ("broken_heart", "💔", false),
+    ("sparkling_heart", "💖", false),
+    ("100", "💯", false),
+    ("boom", "💥", false),
+    ("zzz", "💤", false),
+    ("speech_balloon", "💬", false),
+    ("fire", "🔥", false),
+    ("sparkles", "✨", false),
+    ("star", "⭐", false),
+    ("star2", "🌟", false),
+    ("zap", "⚡", false),
+    ("sunny", "☀️", false),
+    ("cloud", "☁️", false),
+    ("umbrella", "☔", false),
+    ("snowflake", "❄️", false),
+    ("rainbow", "🌈", false),
+    ("earth_americas", "🌎", false),
+    ("tada", "🎉", false),
+    ("confetti_ball", "🎊", false),
+    ("balloon", "🎈", false),
+    ("gift", "🎁", false),
+    ("trophy", "🏆", false),
+    ("medal", "🏅", false),
+    ("dart", "🎯", false),
+    ("rocket", "🚀", false),
+    ("airplane", "✈️", false),
+    ("car", "🚗", false),
+    ("ship", "🚢", false),
+    ("construction", "🚧", false),
+    ("rotating_light", "🚨", false),
+    ("coffee", "☕", false),
+    ("beer", "🍺", false),
+    ("beers", "🍻", false),
+    ("pizza", "🍕", false),
+    ("cake", "🍰", false),
+    ("apple", "🍎", false),
+    ("dog", "🐶", false),
+    ("cat", "🐱", false),
+    ("bug", "🐛", false),
+    ("bee", "🐝", false),
+    ("turtle", "🐢", false),
+    ("snail", "🐌", false),
+    ("unicorn", "🦄", false),
+    ("seedling", "🌱", false),
+    ("four_leaf_clover", "🍀", false),
+    ("bulb", "💡", false),
+    ("mag", "🔍", false),
+    ("lock", "🔒", false),
+    ("unlock", "🔓", false),
+    ("key", "🔑", false),
+    ("bell", "🔔", false),
+    ("link", "🔗", false),
+    ("paperclip", "📎", false),
+    ("pushpin", "📌", false),
+    ("memo", "📝", false),
+    ("pencil2", "✏️", false),
+    ("book", "📖", false),
+    ("books", "📚", false),
+    ("calendar", "📅", false),
+    ("chart_with_upwards_trend", "📈", false),
+    ("chart_with_downwards_trend", "📉", false),
+    ("bar_chart", "📊", false),
+    ("clipboard", "📋", false),
+    ("file_folder", "📁", false),
+    ("package", "📦", false),
+    ("email", "📧", false),
+    ("envelope", "✉️", false),
+    ("inbox_tray", "📥", false),
+    ("outbox_tray", "📤", false),
+    ("phone", "☎️", false),
+    ("computer", "💻", false),
+    ("keyboard", "⌨️", false),
+    ("desktop", "🖥️", false),
+    ("gear", "⚙️", false),
+    ("wrench", "🔧", false),
+    ("hammer", "🔨", false),
+    ("tools", "🛠️", false),
+    ("shield", "🛡️", false),
+    ("hourglass", "⌛", false),
+    ("stopwatch", "⏱️", false),
+    ("alarm_clock", "⏰", false),
+    ("moneybag", "💰", false),
+    ("dollar", "💵", false),
+    ("white_check_mark", "✅", false),
+    ("heavy_check_mark", "✔️", false),
+    ("ballot_box_with_check", "☑️", false),
+    ("x", "❌", false),
+    ("negative_squared_cross_mark", "❎", false),
+    ("heavy_plus_sign", "➕", false),
+    ("heavy_minus_sign", "➖", false),
+    ("question", "❓", false),
+    ("grey_question", "❔", false),
+    ("exclamation", "❗", false),
+    ("bangbang", "‼️", false),
+    ("warning", "⚠️", false),
+    ("no_entry", "⛔", false),
+    ("no_entry_sign", "🚫", false),
+    ("red_circle", "🔴", false),
+    ("large_blue_circle", "🔵", false),
+    ("white_circle", "⚪", false),
+    ("black_circle", "⚫", false),
+    ("arrow_up", "⬆️", false),
+    ("arrow_down", "⬇️", false),
+    ("arrow_left", "⬅️", false),
+    ("arrow_right", "➡️", false),
+    ("arrows_counterclockwise", "🔄", false),
+    ("information_source", "ℹ️", false),
+    ("new", "🆕", false),
+    ("free", "🆓", false),
+    ("ok", "🆗", false),
+    ("sos", "🆘", false),
+    ("checkered_flag", "🏁", false),
+    ("triangular_flag_on_post", "🚩", false),
+];
+
+const SKIN_TONES: [char; 5] = ['\u{1f3fb}', '\u{1f3fc}', '\u{1f3fd}', '\u{1f3fe}', '\u{1f3ff}'];
+
+/// A Unicode emoji and the attributes Atlassian editors give it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmojiInfo {
+    /// Canonical short name with colons, e.g. `:thumbsup::skin-tone-2:`.
+    pub short_name: String,
+    /// Code points in lowercase hex joined by `-`, e.g. `1f44d-1f3fb`.
+    pub id: String,
+    pub text: String,
+}
+
+/// Looks up a short name, with or without its colons. A trailing
+/// `:skin-tone-N:` (2 to 6) applies the matching modifier to emoji that take
+/// one.
+pub fn lookup(short_name: &str) -> Option<EmojiInfo> {
+    let name = short_name.strip_prefix(':').unwrap_or(short_name);
+    let name = name.strip_suffix(':').unwrap_or(name);
+    let (name, tone) = match name.split_once("::skin-tone-") {
+        Some((name, tone)) => match tone.parse::<usize>() {
+            Ok(tone @ 2..=6) => (name, Some(tone)),
+            _ => return None,
+        },
+        None => (name, None),
+    };
+    let &(_, base, takes_tone) = EMOJI.iter().find(|(candidate, _, _)| *candidate == name)?;
+    let canonical = EMOJI.iter().find(|(_, text, _)| *text == base).map(|(name, _, _)| *name).unwrap_or(name);
+
+    let (short_name, text) = match tone {
+        Some(tone) if takes_tone => {
+            // The modifier replaces the emoji presentation selector.
+            let mut text: String = base.chars().filter(|c| *c != '\u{fe0f}').collect();
+            text.push(SKIN_TONES[tone - 2]);
+            (format!(":{}::skin-tone-{}:", canonical, tone), text)
+        }
+        Some(_) => return None,
+        None => (format!(":{}:", canonical), base.to_string()),
+    };
+    let id = text
+        .chars()
+        .filter(|c| *c != '\u{fe0f}')
+        .map(|c| format!("{:x}", c as u32))
+        .collect::<Vec<_>>()
+        .join("-");
+    Some(EmojiInfo { short_name, id, text })
+}
+
+/// The text shown for an emoji: the node's own text, else the Unicode emoji
+/// for its short name, else the short name.
+pub fn display_text(attrs: &EmojiAttrs) -> String {
+    match (&attrs.text, lookup(&attrs.short_name)) {
+        (Some(text), _) => text.clone(),
+        (None, Some(info)) => info.text,
+        (None, None) => attrs.short_name.clone(),
+    }
+}
+
+/// Whether the emoji is a site's custom emoji, which has no Unicode form.
+pub fn is_custom(attrs: &EmojiAttrs) -> bool {
+    attrs.id.as_deref().is_some_and(|id| id.starts_with("atlassian-"))
+}
+
+/// How renderers show custom emoji.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CustomEmojiFallback {
+    /// The short name, such as `:party-parrot:`.
+    #[default]
+    ShortName,
+    /// Fixed text in place of every custom emoji.
+    Text(String),
+    /// An image, with `{id}` in the template replaced by the emoji id.
+    /// Formats that cannot show an image inline use the short name.
+    Image { url_template: String },
+}
+
+impl CustomEmojiFallback {
+    /// The text shown for an emoji: [`display_text`], or for a custom emoji
+    /// its short name or the fixed text.
+    pub fn text(&self, attrs: &EmojiAttrs) -> String {
+        match self {
+            _ if !is_custom(attrs) => display_text(attrs),
+            CustomEmojiFallback::Text(text) => text.clone(),
+            _ => attrs.short_name.clone(),
+        }
+    }
+
+    /// The image to show for a custom emoji, when images are wanted.
+    pub fn image_url(&self, attrs: &EmojiAttrs) -> Option<String> {
+        match self {
+            CustomEmojiFallback::Image { url_template } if is_custom(attrs) => {
+                Some(url_template.replace("{id}", attrs.id.as_deref().unwrap_or_default()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Converts known shortcodes such as `:smile:` in text into `emoji` nodes,
+/// splitting text nodes around them. Code blocks and `code` marked text are
+/// left alone, as are unknown shortcodes. Returns the number converted.
+pub fn convert_shortcodes(root: &mut Node) -> usize {
+    static SHORTCODE: OnceLock<Regex> = OnceLock::new();
+    let shortcode = SHORTCODE.get_or_init(|| {
+        Regex::new(r":[a-z0-9_+\-]+:(?::skin-tone-[2-6]:)?").expect("shortcode pattern is valid")
+    });
+    if matches!(root, Node::CodeBlock { .. }) {
+        return 0;
+    }
+    let Some(content) = root.content_mut() else {
+        return 0;
+    };
+    let mut converted = 0;
+    let mut index = 0;
+    while index < content.len() {
+        let nodes = match &content[index] {
+            Node::Text { text, marks } if !marks.iter().flatten().any(|mark| *mark == Mark::Code) => {
+                split_shortcodes(text, marks, shortcode, &mut converted)
+            }
+            _ => None,
+        };
+        match nodes {
+            Some(nodes) => {
+                let count = nodes.len();
+                content.splice(index..index + 1, nodes);
+                index += count;
+            }
+            None => {
+                converted += convert_shortcodes(&mut content[index]);
+                index += 1;
+            }
+        }
+    }
+    converted
+}
+
+/// Fills in the `id` and `text` of emoji nodes that only have a short name.
+/// Custom and unknown emoji are left as they are. Returns the number of
+/// nodes changed.
+pub fn fill_emoji_attrs(root: &mut Node) -> usize {
+    let mut filled = 0;
+    if let Node::Emoji { attrs } = root {
+        if let Some(info) = lookup(&attrs.short_name).filter(|_| !is_custom(attrs)) {
+            if attrs.id.is_none() || attrs.text.is_none() {
+                attrs.id.get_or_insert(info.id);
+                attrs.text.get_or_insert(info.text);
+                filled += 1;
+            }
+        }
+    }
+    for child in root.content_mut().into_iter().flatten() {
+        filled += fill_emoji_attrs(child);
+    }
+    filled
+}
+
+fn split_shortcodes(
+    text: &str,
+    marks: &Option<Vec<Mark>>,
+    shortcode: &Regex,
+    converted: &mut usize,
+) -> Option<Vec<Node>> {
+    let mut nodes = Vec::new();
+    let mut cursor = 0;
+    let mut from = 0;
+    while let Some(found) = shortcode.find_at(text, from) {
+        let Some(info) = lookup(found.as_str()) else {
+            // The closing colon may open the real shortcode, as in `10:30:smile:`.
+            from = found.end() - 1;
+            continue;
+        };
+        if cursor < found.start() {
+            nodes.push(Node::Text { text: text[cursor..found.start()].to_string(), marks: marks.clone() });
+        }
+        let attrs = EmojiAttrs::new(info.short_name).with_id(info.id).with_text(info.text);
+        nodes.push(Node::Emoji { attrs });
+        *converted += 1;
+        cursor = found.end();
+        from = found.end();
+    }
+    if nodes.is_empty() {
+        return None;
+    }
+    if cursor < text.len() {
+        nodes.push(Node::Text { text: text[cursor..].to_string(), marks: marks.clone() });
+    }
+    Some(nodes)
+}
+
+#[cfg(test)]
+mod emoji_tests {
+    use super::*;
+
+    fn emoji(short_name: &str, id: &str, text: &str) -> Node {
+        Node::Emoji { attrs: EmojiAttrs::new(short_name).with_id(id).with_text(text) }
+    }
+
+    #[test]
+    fn should_look_up_short_names_and_skin_tones() {
+        assert_eq!(
+            lookup(":+1:"),
+            Some(EmojiInfo { short_name: ":thumbsup:".to_string(), id: "1f44d".to_string(), text: "👍".to_string() })
+        );
+        assert_eq!(
+            lookup("thumbsup::skin-tone-2"),
+            Some(EmojiInfo {
+                short_name: ":thumbsup::skin-tone-2:".to_string(),
+                id: "1f44d-1f3fb".to_string(),
+                text: "👍🏻".to_string(),
+            })
+        );
+        assert_eq!(lookup(":v::skin-tone-6:").unwrap().text, "✌\u{1f3ff}");
+        assert_eq!(lookup(":heart:").unwrap().id, "2764");
+        assert_eq!(lookup(":heart::skin-tone-3:"), None);
+        assert_eq!(lookup(":party-parrot:"), None);
+    }
+
+    #[test]
+    fn should_pick_display_text() {
+        assert_eq!(display_text(&EmojiAttrs::new(":smile:").with_text(":)")), ":)");
+        assert_eq!(display_text(&EmojiAttrs::new(":+1:")), "👍");
+        assert_eq!(display_text(&EmojiAttrs::new(":party-parrot:")), ":party-parrot:");
+    }
+
+    #[test]
+    fn should_apply_the_custom_emoji_fallback() {
+        let parrot = EmojiAttrs::new(":parrot:").with_id("atlassian-parrot");
+        let smile = EmojiAttrs::new(":smile:").with_id("1f604");
+        let image = CustomEmojiFallback::Image { url_template: "https://e.example/{id}.png".to_string() };
+
+        assert_eq!(CustomEmojiFallback::ShortName.text(&parrot), ":parrot:");
+        assert_eq!(CustomEmojiFallback::Text("[emoji]".to_string()).text(&parrot), "[emoji]");
+        assert_eq!(CustomEmojiFallback::Text("[emoji]".to_string()).text(&smile), "😄");
+        assert_eq!(image.image_url(&parrot).as_deref(), Some("https://e.example/atlassian-parrot.png"));
+        assert_eq!(image.image_url(&smile), None);
+    }
+
+    #[test]
+    fn should_convert_shortcodes_in_text() {
+        let mut doc = Node::doc(vec![
+            Node::paragraph(vec![Node::text_with_marks(
+                "Shipped :tada: at 10:30:wave::skin-tone-4:",
+                vec![Mark::Strong],
+            )]),
+            Node::paragraph(vec![Node::text_with_marks(":smile:", vec![Mark::Code])]),
+            Node::code_block(None, ":smile:"),
+        ]);
+
+        assert_eq!(convert_shortcodes(&mut doc), 2);
+        assert_eq!(
+            doc.descendant(&[0]),
+            Some(&Node::paragraph(vec![
+                Node::text_with_marks("Shipped ", vec![Mark::Strong]),
+                emoji(":tada:", "1f389", "🎉"),
+                Node::text_with_marks(" at 10:30", vec![Mark::Strong]),
+                emoji(":wave::skin-tone-4:", "1f44b-1f3fd", "👋🏽"),
+            ]))
+        );
+        assert_eq!(doc.descendant(&[1, 0]), Some(&Node::text_with_marks(":smile:", vec![Mark::Code])));
+    }
+
+    #[test]
+    fn should_fill_missing_attrs_except_custom_emoji() {
+        let custom = Node::Emoji { attrs: EmojiAttrs::new(":smile:").with_id("atlassian-smile") };
+        let mut doc = Node::doc(vec![Node::paragraph(vec![
+            Node::Emoji { attrs: EmojiAttrs::new(":smile:") },
+            custom.clone(),
+            Node::Emoji { attrs: EmojiAttrs::new(":nope:") },
+        ])]);
+
+        assert_eq!(fill_emoji_attrs(&mut doc), 1);
+        assert_eq!(doc.descendant(&[0, 0]), Some(&emoji(":smile:", "1f604", "😄")));
+        assert_eq!(doc.descendant(&[0, 1]), Some(&custom));
+    }
+}
@@ -0,0 +1,8 @@
+//! Nodes shared by the unit tests.
+
+use crate::model::Node;
+
+/// A paragraph holding plain text.
+pub(crate) fn paragraph(text: &str) -> Node {
+    Node::paragraph(vec![Node::text(text)])
+}
@@ -1,12 +1,246 @@
+use std::fmt;
+
+use serde_json::Value;
+
 use crate::model;
 
-#[allow(dead_code)]
+/// Upper bounds enforced on a document before it is deserialized into a
+/// [`model::Node`] tree. Untrusted payloads can otherwise nest deeply enough
+/// to exhaust the stack or allocate far more than expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum nesting of nodes, counting the root node as depth 1.
+    pub max_depth: usize,
+    /// Maximum number of nodes in the whole document.
+    pub max_nodes: usize,
+    /// Maximum number of bytes summed over every text node.
+    pub max_text_bytes: usize,
+    /// Maximum number of grid slots (headers included) in a single table,
+    /// counting each cell as the area its `colspan` and `rowspan` cover.
+    pub max_table_cells: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            // serde_json refuses to parse past 128 nested JSON values, and
+            // each node level costs an object and a `content` array.
+            max_depth: 60,
+            max_nodes: 100_000,
+            max_text_bytes: 10 * 1024 * 1024,
+            max_table_cells: 10_000,
+        }
+    }
+}
+
+/// How a document is read: the limits to enforce, and whether attributes
+/// outside the value sets defined by ADF are kept (the default) or rejected
+/// (strict). Colors that do not parse as a [`model::Color`] cannot be kept:
+/// the default drops the color mark or cell background carrying them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub limits: ParseLimits,
+    pub strict: bool,
+}
+
+#[derive(Debug)]
+pub enum LexerError {
+    Json(serde_json::Error),
+    UnknownValue { attr: &'static str, value: String },
+    TooDeep { max: usize },
+    TooManyNodes { max: usize },
+    TextTooLarge { max: usize },
+    TooManyTableCells { max: usize },
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexerError::Json(err) => write!(f, "invalid ADF JSON: {}", err),
+            LexerError::UnknownValue { attr, value } => {
+                write!(f, "unknown value {:?} for attribute `{}`", value, attr)
+            }
+            LexerError::TooDeep { max } => write!(f, "document nests deeper than {} nodes", max),
+            LexerError::TooManyNodes { max } => write!(f, "document has more than {} nodes", max),
+            LexerError::TextTooLarge { max } => write!(f, "document text exceeds {} bytes", max),
+            LexerError::TooManyTableCells { max } => write!(f, "table has more than {} cells", max),
+        }
+    }
+}
+
+impl std::error::Error for LexerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LexerError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for LexerError {
+    fn from(err: serde_json::Error) -> Self {
+        LexerError::Json(err)
+    }
+}
+
+/// Parses an ADF document with the default limits.
+///
+/// # Panics
+///
+/// Panics if the input is not a valid ADF document; use
+/// [`from_str_with_options`] to handle the error instead.
 pub fn from_str<S: Into<String>>(input: S) -> model::Node {
-    serde_json::from_str(&input.into()).expect("Failed to parse JSON")
+    from_str_with_limits(input, &ParseLimits::default()).expect("Failed to parse JSON")
 }
 
+/// Same as [`from_str`] for an already parsed JSON value.
+///
+/// # Panics
+///
+/// Panics if the input is not a valid ADF document.
 pub fn from_value(input: serde_json::Value) -> model::Node {
-    serde_json::from_value(input).expect("Failed to parse JSON")
+    from_value_with_limits(input, &ParseLimits::default()).expect("Failed to parse JSON")
+}
+
+pub fn from_str_with_limits<S: Into<String>>(
+    input: S,
+    limits: &ParseLimits,
+) -> Result<model::Node, LexerError> {
+    from_str_with_options(input, &ParseOptions { limits: *limits, strict: false })
+}
+
+pub fn from_value_with_limits(
+    input: serde_json::Value,
+    limits: &ParseLimits,
+) -> Result<model::Node, LexerError> {
+    from_value_with_options(input, &ParseOptions { limits: *limits, strict: false })
+}
+
+pub fn from_str_with_options<S: Into<String>>(
+    input: S,
+    options: &ParseOptions,
+) -> Result<model::Node, LexerError> {
+    let value: Value = serde_json::from_str(&input.into())?;
+    from_value_with_options(value, options)
+}
+
+pub fn from_value_with_options(
+    mut input: serde_json::Value,
+    options: &ParseOptions,
+) -> Result<model::Node, LexerError> {
+    check_limits(&input, &options.limits)?;
+    drop_unknown_colors(&mut input, options.strict)?;
+    let node = serde_json::from_value(input)?;
+    if options.strict {
+        check_known_values(&node)?;
+    }
+    Ok(node)
+}
+
+fn check_known_values(root: &model::Node) -> Result<(), LexerError> {
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if let Some((attr, value)) = node.unknown_attr() {
+            return Err(LexerError::UnknownValue { attr, value: value.to_string() });
+        }
+        stack.extend(node.content().into_iter().flatten());
+    }
+    Ok(())
+}
+
+/// Removes the color marks and cell backgrounds whose color does not parse,
+/// or rejects them when strict, before serde fails the whole document on them.
+fn drop_unknown_colors(root: &mut Value, strict: bool) -> Result<(), LexerError> {
+    let unparseable = |value: Option<&Value>| {
+        value.and_then(Value::as_str).filter(|color| color.parse::<model::Color>().is_err()).map(str::to_string)
+    };
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        let Some(node) = node.as_object_mut() else {
+            continue;
+        };
+        if let Some(Value::Array(marks)) = node.get_mut("marks") {
+            let mut unknown = None;
+            marks.retain(|mark| {
+                let colored = matches!(mark.get("type").and_then(Value::as_str), Some("textColor" | "backgroundColor"));
+                match unparseable(mark.get("attrs").and_then(|attrs| attrs.get("color"))) {
+                    Some(color) if colored => {
+                        unknown.get_or_insert(color);
+                        false
+                    }
+                    _ => true,
+                }
+            });
+            if let Some(value) = unknown.filter(|_| strict) {
+                return Err(LexerError::UnknownValue { attr: "color", value });
+            }
+        }
+        let cell = matches!(node.get("type").and_then(Value::as_str), Some("tableCell" | "tableHeader"));
+        if let Some(Value::Object(attrs)) = node.get_mut("attrs").filter(|_| cell) {
+            if let Some(value) = unparseable(attrs.get("background")) {
+                if strict {
+                    return Err(LexerError::UnknownValue { attr: "background", value });
+                }
+                attrs.remove("background");
+            }
+        }
+        if let Some(Value::Array(content)) = node.get_mut("content") {
+            stack.extend(content.iter_mut());
+        }
+    }
+    Ok(())
+}
+
+/// Walks the raw JSON with an explicit stack so that the limits are checked
+/// before serde recurses into the tree.
+fn check_limits(root: &Value, limits: &ParseLimits) -> Result<(), LexerError> {
+    let mut stack = vec![(root, 1usize)];
+    let mut nodes = 0usize;
+    let mut text_bytes = 0usize;
+
+    while let Some((node, depth)) = stack.pop() {
+        if depth > limits.max_depth {
+            return Err(LexerError::TooDeep { max: limits.max_depth });
+        }
+        nodes += 1;
+        if nodes > limits.max_nodes {
+            return Err(LexerError::TooManyNodes { max: limits.max_nodes });
+        }
+
+        if let Some(Value::String(text)) = node.get("text") {
+            text_bytes += text.len();
+            if text_bytes > limits.max_text_bytes {
+                return Err(LexerError::TextTooLarge { max: limits.max_text_bytes });
+            }
+        }
+
+        let Some(Value::Array(content)) = node.get("content") else {
+            continue;
+        };
+        if node.get("type").and_then(Value::as_str) == Some("table") {
+            let cells = content
+                .iter()
+                .filter_map(|row| row.get("content").and_then(Value::as_array))
+                .flatten()
+                .map(span_area)
+                .fold(0usize, usize::saturating_add);
+            if cells > limits.max_table_cells {
+                return Err(LexerError::TooManyTableCells { max: limits.max_table_cells });
+            }
+        }
+        stack.extend(content.iter().map(|child| (child, depth + 1)));
+    }
+
+    Ok(())
+}
+
+/// Slots a raw table cell covers, reading its spans the way the grid does.
+fn span_area(cell: &Value) -> usize {
+    let span = |name: &str| {
+        let value = cell.get("attrs").and_then(|attrs| attrs.get(name)).and_then(Value::as_u64);
+        usize::try_from(value.unwrap_or(1).max(1)).unwrap_or(usize::MAX)
+    };
+    span("colspan").saturating_mul(span("rowspan"))
 }
 
 #[cfg(test)]
@@ -38,6 +272,30 @@ mod lexer_tests {
         assert_eq!(from_str_result, from_value_result);
     }
 
+    #[test]
+    fn constructors_should_match_parsed_document() {
+        let input = r#"{
+            "type": "doc",
+            "version": 1,
+            "content": [
+                {
+                    "type": "heading",
+                    "attrs": { "level": 2, "localId": "intro" },
+                    "content": [{ "type": "text", "text": "Hello world" }]
+                }
+            ]
+        }"#;
+
+        let expected = model::Node::Doc {
+            version: 1,
+            content: vec![model::Node::Heading {
+                content: vec![model::Node::text("Hello world")],
+                attrs: HeadingAttrs::new(2).with_local_id("intro"),
+            }],
+        };
+        assert_eq!(from_str(input), expected);
+    }
+
     #[test]
     fn should_read_blockquote() {
         let input = r#"{
@@ -497,5 +755,227 @@ mod lexer_tests {
         );
     }
 
+    fn nested_blockquotes(depth: usize) -> String {
+        let mut input = r#"{"type": "paragraph", "content": []}"#.to_string();
+        for _ in 0..depth {
+            input = format!(r#"{{"type": "blockquote", "content": [{}]}}"#, input);
+        }
+        format!(r#"{{"type": "doc", "version": 1, "content": [{}]}}"#, input)
+    }
+
+    #[test]
+    fn should_reject_documents_nested_past_max_depth() {
+        let limits = ParseLimits { max_depth: 10, ..ParseLimits::default() };
+
+        assert!(from_str_with_limits(nested_blockquotes(8), &limits).is_ok());
+        assert!(matches!(
+            from_str_with_limits(nested_blockquotes(9), &limits),
+            Err(LexerError::TooDeep { max: 10 })
+        ));
+    }
+
+    #[test]
+    fn should_reject_documents_with_too_many_nodes() {
+        let limits = ParseLimits { max_nodes: 3, ..ParseLimits::default() };
+
+        assert!(matches!(
+            from_str_with_limits(nested_blockquotes(2), &limits),
+            Err(LexerError::TooManyNodes { max: 3 })
+        ));
+    }
+
+    #[test]
+    fn should_reject_documents_with_too_much_text() {
+        let input = r#"{
+            "type": "doc",
+            "version": 1,
+            "content": [{
+                "type": "paragraph",
+                "content": [
+                    { "type": "text", "text": "Hello" },
+                    { "type": "text", "text": "world" }
+                ]
+            }]
+        }"#;
+        let limits = ParseLimits { max_text_bytes: 9, ..ParseLimits::default() };
+
+        assert!(matches!(
+            from_str_with_limits(input, &limits),
+            Err(LexerError::TextTooLarge { max: 9 })
+        ));
+    }
+
+    #[test]
+    fn should_reject_tables_with_too_many_cells() {
+        let cell = r#"{"type": "tableCell", "content": []}"#;
+        let row = format!(r#"{{"type": "tableRow", "content": [{cell}, {cell}]}}"#);
+        let input = format!(
+            r#"{{"type": "doc", "version": 1, "content": [{{"type": "table", "content": [{row}, {row}]}}]}}"#
+        );
+        let limits = ParseLimits { max_table_cells: 3, ..ParseLimits::default() };
+
+        assert!(matches!(
+            from_str_with_limits(input, &limits),
+            Err(LexerError::TooManyTableCells { max: 3 })
+        ));
+    }
+
+    #[test]
+    fn should_count_the_area_spanning_cells_cover() {
+        let cell = r#"{"type": "tableCell", "attrs": {"colspan": 65535, "rowspan": 2}, "content": []}"#;
+        let input = format!(
+            r#"{{"type": "doc", "version": 1, "content": [{{"type": "table", "content": [
+                {{"type": "tableRow", "content": [{cell}]}}, {{"type": "tableRow", "content": []}}
+            ]}}]}}"#
+        );
+
+        assert!(matches!(
+            from_str_with_limits(input, &ParseLimits::default()),
+            Err(LexerError::TooManyTableCells { max: 10_000 })
+        ));
+    }
+
+    #[test]
+    fn should_return_json_error_for_malformed_input() {
+        assert!(matches!(
+            from_str_with_limits("{", &ParseLimits::default()),
+            Err(LexerError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn should_drop_deeply_nested_trees_without_overflowing() {
+        let mut node = model::Node::Paragraph { content: vec![], attrs: None };
+        for _ in 0..1_000_000 {
+            node = model::Node::Blockquote { content: vec![node] };
+        }
+        drop(node);
+    }
+
+    fn panel(panel_type: &str) -> String {
+        format!(
+            r#"{{
+                "type": "doc",
+                "version": 1,
+                "content": [{{
+                    "type": "panel",
+                    "attrs": {{ "panelType": "{}" }},
+                    "content": []
+                }}]
+            }}"#,
+            panel_type
+        )
+    }
+
+    #[test]
+    fn should_read_panel_type() {
+        assert_eq!(
+            from_str(panel("warning")),
+            model::Node::Doc {
+                version: 1,
+                content: vec![model::Node::Panel {
+                    content: vec![],
+                    attrs: model::PanelAttrs { panel_type: model::PanelType::Warning },
+                }]
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_unknown_attr_values_when_strict() {
+        let options = ParseOptions { strict: true, ..ParseOptions::default() };
+
+        assert!(matches!(
+            from_str_with_options(panel("danger"), &options),
+            Err(LexerError::UnknownValue { attr: "panelType", value }) if value == "danger"
+        ));
+    }
+
+    #[test]
+    fn should_keep_unknown_attr_values_by_default() {
+        let result = from_str(panel("danger"));
+        assert_eq!(result, from_str_with_limits(panel("danger"), &ParseLimits::default()).unwrap());
+
+        assert_eq!(
+            result,
+            model::Node::Doc {
+                version: 1,
+                content: vec![model::Node::Panel {
+                    content: vec![],
+                    attrs: model::PanelAttrs {
+                        panel_type: model::PanelType::Other("danger".to_string())
+                    },
+                }]
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&result).unwrap()["content"][0]["attrs"]["panelType"],
+            "danger"
+        );
+    }
+
+    #[test]
+    fn should_drop_colors_that_do_not_parse_unless_strict() {
+        let input = r#"{
+            "type": "doc",
+            "version": 1,
+            "content": [{
+                "type": "paragraph",
+                "content": [{
+                    "type": "text",
+                    "text": "tinted",
+                    "marks": [{ "type": "textColor", "attrs": { "color": "var(--ds-text)" } }, { "type": "strong" }]
+                }]
+            }]
+        }"#;
+
+        assert_eq!(
+            from_str(input),
+            model::Node::Doc {
+                version: 1,
+                content: vec![model::Node::paragraph(vec![model::Node::text_with_marks(
+                    "tinted",
+                    vec![model::Mark::Strong]
+                )])]
+            }
+        );
+        let options = ParseOptions { strict: true, ..ParseOptions::default() };
+        assert!(matches!(
+            from_str_with_options(input, &options),
+            Err(LexerError::UnknownValue { attr: "color", value }) if value == "var(--ds-text)"
+        ));
+    }
+
+    #[test]
+    fn should_read_task_list() {
+        let input = r#"{
+            "type": "doc",
+            "version": 1,
+            "content": [{
+                "type": "taskList",
+                "attrs": { "localId": "tasks" },
+                "content": [{
+                    "type": "taskItem",
+                    "attrs": { "localId": "task-1", "state": "DONE" },
+                    "content": [{ "type": "text", "text": "Write tests" }]
+                }]
+            }]
+        }"#;
+
+        assert_eq!(
+            from_str(input),
+            model::Node::Doc {
+                version: 1,
+                content: vec![model::Node::TaskList {
+                    content: vec![model::Node::TaskItem {
+                        content: vec![model::Node::text("Write tests")],
+                        attrs: model::TaskItemAttrs::new("task-1", model::TaskState::Done),
+                    }],
+                    attrs: model::TaskListAttrs::new("tasks"),
+                }]
+            }
+        );
+    }
+
     // TODO: left off here: https://developer.atlassian.com/cloud/jira/platform/apis/document/nodes/inlineCard/
 }
\ No newline at end of file
@@ -0,0 +1,46 @@
+//! Reading, inspecting and rendering documents in the Atlassian Document
+//! Format (ADF) used by Jira and Confluence.
+//!
+//! [`lexer`] turns ADF JSON into the [`model::Node`] tree, which serializes
+//! back to ADF with serde. Renderers and converters each sit behind a cargo
+//! feature, all enabled by default; disable default features to pick them
+//! individually.
+//!
+//! Renderers:
+//!
+//! - `ansi`: [`render::ansi`], styled text for terminals
+//! - `asciidoc`: [`render::asciidoc`]
+//! - `html`: [`render::html`]
+//! - `email`: [`render::email`], inline-styled HTML and plain text for
+//!   notification emails
+//! - `latex`: [`render::latex`], LaTeX for printable reports, with the packages it needs
+//! - `rst`: [`render::rst`], reStructuredText for Sphinx
+//! - `slack`: [`render::slack`], `mrkdwn` text and Block Kit JSON for Slack
+//! - `typst`: [`render::typst`], Typst markup for printable reports
+//!
+//! Converters between ADF and other formats, in [`convert`]:
+//!
+//! - `csv`: [`convert::csv`], tables to and from CSV or TSV
+//! - `html-import`: [`convert::html`], HTML pasted from web pages, sanitized
+//! - `storage`: [`convert::storage`], Confluence storage format (XHTML)
+//! - `wiki`: [`convert::wiki`], Jira wiki markup in both directions
+
+pub mod color;
+pub mod convert;
+pub mod date;
+pub mod emoji;
+pub mod lexer;
+pub mod linkify;
+pub mod local_id;
+pub mod model;
+pub mod outline;
+pub mod render;
+pub mod resolve;
+pub mod search;
+pub mod section;
+pub mod table;
+#[cfg(any(feature = "html", feature = "html-import", feature = "storage"))]
+mod url;
+
+#[cfg(test)]
+mod fixtures;
@@ -0,0 +1,332 @@
+use std::fmt;
+
+use regex::Regex;
+
+use crate::model::{InlineCardAttrs, LinkAttrs, Mark, Node};
+
+const URL_PATTERN: &str = r#"\b(?:https?://|www\.)[^\s<>"]+"#;
+const EMAIL_PATTERN: &str = r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b";
+const ISSUE_KEY_PATTERN: &str = r"\b[A-Z][A-Z0-9_]+-[1-9][0-9]*\b";
+// Names in prose that are written like issue keys, as in `UTF-8`.
+const COMMON_ACRONYMS: &[&str] =
+    &["AES", "COVID", "CRC", "CVE", "ECMA", "HTTP", "ISO", "MD", "PEP", "RFC", "SHA", "TLS", "UTF"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkifyOptions {
+    pub urls: bool,
+    pub emails: bool,
+    pub issue_keys: Option<IssueKeys>,
+}
+
+impl Default for LinkifyOptions {
+    fn default() -> Self {
+        LinkifyOptions { urls: true, emails: true, issue_keys: None }
+    }
+}
+
+/// How Jira issue keys such as `PROJ-123` are recognised and linked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueKeys {
+    /// Regular expression matching a key.
+    pub pattern: String,
+    /// Target URL, with `{key}` replaced by the matched key, e.g.
+    /// `https://example.atlassian.net/browse/{key}`.
+    pub url_template: String,
+    pub style: IssueKeyStyle,
+    /// Projects whose keys are left as text.
+    pub ignored_projects: Vec<String>,
+}
+
+impl IssueKeys {
+    /// Matches keys of any project, like `PROJ-123`, except acronyms such as
+    /// `UTF-8` and `SHA-256`. [`IssueKeys::for_projects`] avoids guessing.
+    pub fn new<S: Into<String>>(url_template: S, style: IssueKeyStyle) -> Self {
+        IssueKeys {
+            pattern: ISSUE_KEY_PATTERN.to_string(),
+            url_template: url_template.into(),
+            style,
+            ignored_projects: COMMON_ACRONYMS.iter().map(|acronym| acronym.to_string()).collect(),
+        }
+    }
+
+    /// Matches keys of the given projects only.
+    pub fn for_projects<S: Into<String>>(projects: &[&str], url_template: S, style: IssueKeyStyle) -> Self {
+        let projects: Vec<_> = projects.iter().map(|project| regex::escape(project)).collect();
+        IssueKeys {
+            pattern: format!(r"\b(?:{})-[1-9][0-9]*\b", projects.join("|")),
+            url_template: url_template.into(),
+            style,
+            ignored_projects: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKeyStyle {
+    /// Replace the key with an `inlineCard` smart link.
+    InlineCard,
+    /// Keep the key as text with a `link` mark.
+    Link,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkifyError(regex::Error);
+
+impl fmt::Display for LinkifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid issue key pattern: {}", self.0)
+    }
+}
+
+impl std::error::Error for LinkifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Turns bare URLs, email addresses and issue keys in text into links.
+/// Code blocks, `code` marked text and text that already has a link are left
+/// alone.
+#[derive(Debug, Clone)]
+pub struct Linkifier {
+    urls: Option<Regex>,
+    emails: Option<Regex>,
+    issue_keys: Option<(Regex, IssueKeys)>,
+}
+
+// A piece of text to link, as a byte range of the text node.
+struct Found {
+    start: usize,
+    end: usize,
+    target: Target,
+}
+
+enum Target {
+    Link(String),
+    Card(String),
+}
+
+impl Linkifier {
+    pub fn new(options: &LinkifyOptions) -> Result<Self, LinkifyError> {
+        let compile = |pattern: &str| Regex::new(pattern).map_err(LinkifyError);
+        Ok(Linkifier {
+            urls: options.urls.then(|| compile(URL_PATTERN)).transpose()?,
+            emails: options.emails.then(|| compile(EMAIL_PATTERN)).transpose()?,
+            issue_keys: match &options.issue_keys {
+                Some(issue_keys) => Some((compile(&issue_keys.pattern)?, issue_keys.clone())),
+                None => None,
+            },
+        })
+    }
+
+    /// Links everything found in the subtree, splitting text nodes around
+    /// each link. Returns the number of links created.
+    pub fn apply(&self, root: &mut Node) -> usize {
+        if matches!(root, Node::CodeBlock { .. }) {
+            return 0;
+        }
+        let Some(content) = root.content_mut() else {
+            return 0;
+        };
+        let mut linked = 0;
+        let mut index = 0;
+        while index < content.len() {
+            let found = match &content[index] {
+                Node::Text { text, marks } if !has_code_or_link(marks.as_deref()) => self.find(text),
+                _ => Vec::new(),
+            };
+            if found.is_empty() {
+                linked += self.apply(&mut content[index]);
+                index += 1;
+                continue;
+            }
+            linked += found.len();
+            let nodes = split(&content[index], &found);
+            let count = nodes.len();
+            content.splice(index..index + 1, nodes);
+            index += count;
+        }
+        linked
+    }
+
+    fn find(&self, text: &str) -> Vec<Found> {
+        let mut found = Vec::new();
+        if let Some(urls) = &self.urls {
+            for url in urls.find_iter(text) {
+                let end = url.start() + trim_url(url.as_str()).len();
+                let href = &text[url.start()..end];
+                let href = if href.starts_with("www.") { format!("https://{}", href) } else { href.to_string() };
+                found.push(Found { start: url.start(), end, target: Target::Link(href) });
+            }
+        }
+        if let Some(emails) = &self.emails {
+            for email in emails.find_iter(text) {
+                let target = Target::Link(format!("mailto:{}", email.as_str()));
+                found.push(Found { start: email.start(), end: email.end(), target });
+            }
+        }
+        if let Some((pattern, issue_keys)) = &self.issue_keys {
+            for key in pattern.find_iter(text) {
+                let project = key.as_str().rsplit_once('-').map_or("", |(project, _)| project);
+                if issue_keys.ignored_projects.iter().any(|ignored| ignored == project) {
+                    continue;
+                }
+                let url = issue_keys.url_template.replace("{key}", key.as_str());
+                let target = match issue_keys.style {
+                    IssueKeyStyle::InlineCard => Target::Card(url),
+                    IssueKeyStyle::Link => Target::Link(url),
+                };
+                found.push(Found { start: key.start(), end: key.end(), target });
+            }
+        }
+
+        // Earliest first, longest first on ties, dropping anything that
+        // overlaps a match already kept (such as a key inside a URL).
+        found.sort_by_key(|found| (found.start, std::cmp::Reverse(found.end)));
+        let mut kept: Vec<Found> = Vec::new();
+        for candidate in found {
+            if kept.last().is_none_or(|last| candidate.start >= last.end) {
+                kept.push(candidate);
+            }
+        }
+        kept
+    }
+}
+
+fn has_code_or_link(marks: Option<&[Mark]>) -> bool {
+    marks.unwrap_or_default().iter().any(|mark| matches!(mark, Mark::Code | Mark::Link { .. }))
+}
+
+// Drops trailing punctuation that ends the sentence rather than the URL,
+// keeping closing parentheses that balance one inside the URL.
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let Some(last) = url.chars().last() else {
+            return url;
+        };
+        let unbalanced = last == ')' && url.matches('(').count() < url.matches(')').count();
+        if matches!(last, '.' | ',' | ';' | ':' | '!' | '?' | '\'' | ']' | '}') || unbalanced {
+            url = &url[..url.len() - last.len_utf8()];
+        } else {
+            return url;
+        }
+    }
+}
+
+fn split(node: &Node, found: &[Found]) -> Vec<Node> {
+    let Node::Text { text, marks } = node else {
+        unreachable!("only text nodes are split");
+    };
+    let mut nodes = Vec::new();
+    let mut cursor = 0;
+    for found in found {
+        if cursor < found.start {
+            nodes.push(Node::Text { text: text[cursor..found.start].to_string(), marks: marks.clone() });
+        }
+        let matched = &text[found.start..found.end];
+        nodes.push(match &found.target {
+            Target::Link(href) => {
+                let mut marks = marks.clone().unwrap_or_default();
+                marks.push(Mark::Link { attrs: LinkAttrs::new(href.clone()) });
+                Node::text_with_marks(matched, marks)
+            }
+            Target::Card(url) => Node::InlineCard { attrs: InlineCardAttrs::from_url(url.clone()) },
+        });
+        cursor = found.end;
+    }
+    if cursor < text.len() {
+        nodes.push(Node::Text { text: text[cursor..].to_string(), marks: marks.clone() });
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod linkify_tests {
+    use super::*;
+
+    fn link(text: &str, href: &str) -> Node {
+        Node::text_with_marks(text, vec![Mark::Link { attrs: LinkAttrs::new(href) }])
+    }
+
+    #[test]
+    fn should_link_urls_and_emails() {
+        let mut doc = Node::doc(vec![Node::paragraph(vec![Node::text(
+            "See https://example.com/a_(b)). Or www.example.org, mail ops@example.co.uk!",
+        )])]);
+
+        assert_eq!(Linkifier::new(&LinkifyOptions::default()).unwrap().apply(&mut doc), 3);
+        assert_eq!(
+            doc.descendant(&[0]),
+            Some(&Node::paragraph(vec![
+                Node::text("See "),
+                link("https://example.com/a_(b)", "https://example.com/a_(b)"),
+                Node::text("). Or "),
+                link("www.example.org", "https://www.example.org"),
+                Node::text(", mail "),
+                link("ops@example.co.uk", "mailto:ops@example.co.uk"),
+                Node::text("!"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn should_turn_issue_keys_into_cards_or_links() {
+        let text = "Fixed PROJ-12 and OTHER-3, see https://jira.example.com/browse/PROJ-9";
+        let mut doc = Node::doc(vec![Node::paragraph(vec![Node::text_with_marks(text, vec![Mark::Strong])])]);
+        let options = LinkifyOptions {
+            issue_keys: Some(IssueKeys::for_projects(
+                &["PROJ"],
+                "https://jira.example.com/browse/{key}",
+                IssueKeyStyle::InlineCard,
+            )),
+            ..LinkifyOptions::default()
+        };
+
+        assert_eq!(Linkifier::new(&options).unwrap().apply(&mut doc), 2);
+        let Some(Node::Paragraph { content, .. }) = doc.descendant(&[0]) else { unreachable!() };
+        assert_eq!(content[0], Node::text_with_marks("Fixed ", vec![Mark::Strong]));
+        assert_eq!(
+            content[1],
+            Node::InlineCard { attrs: InlineCardAttrs::from_url("https://jira.example.com/browse/PROJ-12") }
+        );
+        assert_eq!(content[2], Node::text_with_marks(" and OTHER-3, see ", vec![Mark::Strong]));
+        assert_eq!(content.len(), 4);
+
+        let options = LinkifyOptions {
+            issue_keys: Some(IssueKeys::new("https://jira/{key}", IssueKeyStyle::Link)),
+            ..LinkifyOptions::default()
+        };
+        let mut doc = Node::doc(vec![Node::paragraph(vec![Node::text("OTHER-3")])]);
+        Linkifier::new(&options).unwrap().apply(&mut doc);
+        assert_eq!(doc.descendant(&[0, 0]), Some(&link("OTHER-3", "https://jira/OTHER-3")));
+    }
+
+    #[test]
+    fn should_leave_acronyms_that_look_like_issue_keys() {
+        let options = LinkifyOptions {
+            issue_keys: Some(IssueKeys::new("https://jira/{key}", IssueKeyStyle::Link)),
+            ..LinkifyOptions::default()
+        };
+        let mut doc = Node::doc(vec![Node::paragraph(vec![Node::text("UTF-8 and SHA-256 for AB-1")])]);
+
+        assert_eq!(Linkifier::new(&options).unwrap().apply(&mut doc), 1);
+        assert_eq!(doc.descendant(&[0, 0]), Some(&Node::text("UTF-8 and SHA-256 for ")));
+        assert_eq!(doc.descendant(&[0, 1]), Some(&link("AB-1", "https://jira/AB-1")));
+    }
+
+    #[test]
+    fn should_skip_code_and_existing_links() {
+        let original = Node::doc(vec![
+            Node::code_block(Some("sh"), "curl https://example.com"),
+            Node::paragraph(vec![
+                Node::text_with_marks("https://example.com", vec![Mark::Code]),
+                link("docs at https://example.com", "https://docs.example.com"),
+            ]),
+        ]);
+        let mut doc = original.clone();
+
+        assert_eq!(Linkifier::new(&LinkifyOptions::default()).unwrap().apply(&mut doc), 0);
+        assert_eq!(doc, original);
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
+
+use crate::model::Node;
+
+/// Source of new `localId` values. When it keeps returning empty or used
+/// ids, the last one is made unique with a `-2`, `-3`, ... suffix.
+pub trait LocalIdGenerator {
+    fn generate(&mut self) -> String;
+}
+
+impl<F: FnMut() -> String> LocalIdGenerator for F {
+    fn generate(&mut self) -> String {
+        self()
+    }
+}
+
+/// Generates random version 4 UUIDs, the format the Atlassian editors use.
+/// A seeded generator yields the same sequence every run, which keeps
+/// generated documents stable in tests and diffs.
+#[derive(Debug, Clone)]
+pub struct UuidGenerator {
+    state: u64,
+}
+
+impl UuidGenerator {
+    /// Seeded from the per-process randomness std uses for `HashMap`.
+    pub fn new() -> Self {
+        UuidGenerator::seeded(RandomState::new().build_hasher().finish())
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        UuidGenerator { state: seed }
+    }
+
+    // SplitMix64.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl Default for UuidGenerator {
+    fn default() -> Self {
+        UuidGenerator::new()
+    }
+}
+
+impl LocalIdGenerator for UuidGenerator {
+    fn generate(&mut self) -> String {
+        let high = self.next_u64();
+        let low = self.next_u64();
+        // Version 4 in the high nibble of the third group, RFC 4122 variant
+        // in the top bits of the fourth.
+        let high = (high & 0xffff_ffff_ffff_0fff) | 0x0000_0000_0000_4000;
+        let low = (low & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xffff,
+            high & 0xffff,
+            low >> 48,
+            low & 0xffff_ffff_ffff
+        )
+    }
+}
+
+/// A `localId` used by more than one node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateLocalId {
+    pub local_id: String,
+    /// Paths of every node using the id, in document order.
+    pub paths: Vec<Vec<usize>>,
+}
+
+/// Gives every node that supports a `localId` but lacks one a new id, never
+/// reusing one already in the document. Returns how many were assigned.
+pub fn assign_local_ids(root: &mut Node, generator: &mut dyn LocalIdGenerator) -> usize {
+    let mut used = HashSet::new();
+    collect_local_ids(root, &mut Vec::new(), &mut |local_id, _| {
+        used.insert(local_id.to_string());
+    });
+    let mut assigned = 0;
+    visit_mut(root, &mut |node| {
+        if node.local_id().is_none() && supports_local_id(node) {
+            node.set_local_id(fresh_id(generator, &mut used));
+            assigned += 1;
+        }
+    });
+    assigned
+}
+
+/// Gives a new id to every node repeating an id seen earlier in document
+/// order, as happens when blocks are copied between documents. Returns how
+/// many were replaced.
+pub fn reassign_duplicate_local_ids(root: &mut Node, generator: &mut dyn LocalIdGenerator) -> usize {
+    let mut used = HashSet::new();
+    collect_local_ids(root, &mut Vec::new(), &mut |local_id, _| {
+        used.insert(local_id.to_string());
+    });
+    let mut seen = HashSet::new();
+    let mut replaced = 0;
+    visit_mut(root, &mut |node| {
+        let Some(local_id) = node.local_id() else {
+            return;
+        };
+        if !seen.insert(local_id.to_string()) {
+            node.set_local_id(fresh_id(generator, &mut used));
+            replaced += 1;
+        }
+    });
+    replaced
+}
+
+/// Every `localId` used more than once, in order of first use.
+pub fn duplicate_local_ids(root: &Node) -> Vec<DuplicateLocalId> {
+    let mut order = Vec::new();
+    let mut paths: HashMap<String, Vec<Vec<usize>>> = HashMap::new();
+    collect_local_ids(root, &mut Vec::new(), &mut |local_id, path| {
+        let entry = paths.entry(local_id.to_string()).or_default();
+        if entry.is_empty() {
+            order.push(local_id.to_string());
+        }
+        entry.push(path.to_vec());
+    });
+    order
+        .into_iter()
+        .filter_map(|local_id| {
+            let paths = paths.remove(&local_id).filter(|paths| paths.len() > 1)?;
+            Some(DuplicateLocalId { local_id, paths })
+        })
+        .collect()
+}
+
+fn supports_local_id(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Heading { .. }
+            | Node::Paragraph { .. }
+            | Node::Status { .. }
+            | Node::TaskList { .. }
+            | Node::TaskItem { .. }
+            | Node::DecisionList { .. }
+            | Node::DecisionItem { .. }
+    )
+}
+
+// Generator calls before a repeating or empty id is made unique with a
+// counter suffix instead, so a poor generator cannot loop forever.
+const MAX_ATTEMPTS: usize = 16;
+
+fn fresh_id(generator: &mut dyn LocalIdGenerator, used: &mut HashSet<String>) -> String {
+    let mut base = String::new();
+    for _ in 0..MAX_ATTEMPTS {
+        let local_id = generator.generate();
+        if !local_id.is_empty() && used.insert(local_id.clone()) {
+            return local_id;
+        }
+        if !local_id.is_empty() {
+            base = local_id;
+        }
+    }
+    if base.is_empty() {
+        base.push_str("id");
+    }
+    (2..)
+        .map(|suffix| format!("{}-{}", base, suffix))
+        .find(|local_id| used.insert(local_id.clone()))
+        .expect("an unused suffix")
+}
+
+fn collect_local_ids(node: &Node, path: &mut Vec<usize>, f: &mut dyn FnMut(&str, &[usize])) {
+    if let Some(local_id) = node.local_id() {
+        f(local_id, path);
+    }
+    for (index, child) in node.content().into_iter().flatten().enumerate() {
+        path.push(index);
+        collect_local_ids(child, path, f);
+        path.pop();
+    }
+}
+
+fn visit_mut(node: &mut Node, f: &mut dyn FnMut(&mut Node)) {
+    f(node);
+    for child in node.content_mut().into_iter().flatten() {
+        visit_mut(child, f);
+    }
+}
+
+#[cfg(test)]
+mod local_id_tests {
+    use super::*;
+    use crate::model::{HeadingAttrs, StatusAttrs, StatusColor, TaskItemAttrs, TaskListAttrs, TaskState};
+
+    fn task_list(local_id: &str, items: &[&str]) -> Node {
+        Node::TaskList {
+            content: items
+                .iter()
+                .map(|local_id| Node::TaskItem {
+                    content: vec![Node::text("Ship it")],
+                    attrs: TaskItemAttrs::new(*local_id, TaskState::Todo),
+                })
+                .collect(),
+            attrs: TaskListAttrs::new(local_id),
+        }
+    }
+
+    #[test]
+    fn should_generate_seeded_uuids() {
+        let mut first = UuidGenerator::seeded(7);
+        let mut second = UuidGenerator::seeded(7);
+        let id = first.generate();
+
+        assert_eq!(id, second.generate());
+        assert_ne!(id, first.generate());
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert!(matches!(&id[19..20], "8" | "9" | "a" | "b"));
+    }
+
+    #[test]
+    fn should_assign_missing_local_ids_without_collisions() {
+        let mut doc = Node::doc(vec![
+            Node::Heading { content: vec![], attrs: HeadingAttrs::new(1).with_local_id("id-1") },
+            Node::paragraph(vec![Node::Status { attrs: StatusAttrs::new("DONE", StatusColor::Green) }]),
+            task_list("", &[""]),
+        ]);
+        let mut counter = 0;
+        let mut generator = || {
+            counter += 1;
+            format!("id-{}", counter)
+        };
+
+        assert_eq!(assign_local_ids(&mut doc, &mut generator), 4);
+        assert_eq!(doc.descendant(&[1]).unwrap().local_id(), Some("id-2"));
+        assert_eq!(doc.descendant(&[1, 0]).unwrap().local_id(), Some("id-3"));
+        assert_eq!(doc.descendant(&[2, 0]).unwrap().local_id(), Some("id-5"));
+        assert!(duplicate_local_ids(&doc).is_empty());
+    }
+
+    #[test]
+    fn should_report_and_fix_duplicate_local_ids() {
+        let mut doc = Node::doc(vec![task_list("list", &["a", "b"]), task_list("list", &["a"])]);
+
+        assert_eq!(
+            duplicate_local_ids(&doc),
+            vec![
+                DuplicateLocalId { local_id: "list".to_string(), paths: vec![vec![0], vec![1]] },
+                DuplicateLocalId { local_id: "a".to_string(), paths: vec![vec![0, 0], vec![1, 0]] },
+            ]
+        );
+
+        let mut generator = UuidGenerator::seeded(1);
+        assert_eq!(reassign_duplicate_local_ids(&mut doc, &mut generator), 2);
+        assert!(duplicate_local_ids(&doc).is_empty());
+        assert_eq!(doc.descendant(&[0, 0]).unwrap().local_id(), Some("a"));
+    }
+
+    #[test]
+    fn should_suffix_ids_when_the_generator_keeps_repeating() {
+        let mut doc = Node::doc(vec![Node::paragraph(vec![]), Node::paragraph(vec![]), Node::paragraph(vec![])]);
+        let mut constant = || "same".to_string();
+
+        assert_eq!(assign_local_ids(&mut doc, &mut constant), 3);
+        let ids: Vec<_> = (0..3).map(|index| doc.descendant(&[index]).unwrap().local_id()).collect();
+        assert_eq!(ids, [Some("same"), Some("same-2"), Some("same-3")]);
+
+        let mut empty = String::new;
+        let mut doc = Node::doc(vec![Node::paragraph(vec![])]);
+        assert_eq!(assign_local_ids(&mut doc, &mut empty), 1);
+        assert_eq!(doc.descendant(&[0]).unwrap().local_id(), Some("id-2"));
+    }
+}
@@ -1,5 +1,4 @@
-mod model;
-mod lexer;
+use adf_rs::lexer;
 use serde_json::json;
 
 fn main() {
@@ -22,8 +21,9 @@ fn main() {
     // Check root node
     let root = lexer::from_value(example);
     println!("{:#?}", root);
+    #[cfg(feature = "html")]
+    println!("{}", adf_rs::render::html::to_html(&root, &Default::default()));
 
     // TODO: Make a parser to verify the ADF doc
-    // TODO: Make a renderer to convert ADF doc to HTML
     // TODO: Make a renderer to convert ADF doc to Markdown
-}
\ No newline at end of file
+}
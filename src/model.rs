@@ -1,6 +1,9 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub use crate::color::Color;
+use crate::date::{DateError, Timestamp};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum Node {
@@ -17,6 +20,14 @@ pub enum Node {
     Date {
         attrs: DateAttrs,
     },
+    DecisionItem {
+        content: Vec<Node>,
+        attrs: DecisionItemAttrs,
+    },
+    DecisionList {
+        content: Vec<Node>,
+        attrs: DecisionListAttrs,
+    },
     Doc {
         version: i8,
         content: Vec<Node>,
@@ -90,13 +101,282 @@ pub enum Node {
     TableRow {
         content: Vec<Node>,
     },
+    TaskItem {
+        content: Vec<Node>,
+        attrs: TaskItemAttrs,
+    },
+    TaskList {
+        content: Vec<Node>,
+        attrs: TaskListAttrs,
+    },
     Text {
         text: String,
         marks: Option<Vec<Mark>>,
     },
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl Node {
+    pub fn doc(content: Vec<Node>) -> Self {
+        Node::Doc { version: 1, content }
+    }
+
+    pub fn paragraph(content: Vec<Node>) -> Self {
+        Node::Paragraph { content, attrs: None }
+    }
+
+    pub fn heading(level: i8, content: Vec<Node>) -> Self {
+        Node::Heading { content, attrs: HeadingAttrs::new(level) }
+    }
+
+    pub fn text<S: Into<String>>(text: S) -> Self {
+        Node::Text { text: text.into(), marks: None }
+    }
+
+    pub fn text_with_marks<S: Into<String>>(text: S, marks: Vec<Mark>) -> Self {
+        Node::Text { text: text.into(), marks: Some(marks) }
+    }
+
+    pub fn bullet_list(items: Vec<Node>) -> Self {
+        Node::BulletList { content: items }
+    }
+
+    pub fn ordered_list(items: Vec<Node>) -> Self {
+        Node::OrderedList { content: items, attrs: None }
+    }
+
+    pub fn list_item(content: Vec<Node>) -> Self {
+        Node::ListItem { content }
+    }
+
+    pub fn code_block<S: Into<String>>(language: Option<&str>, code: S) -> Self {
+        Node::CodeBlock {
+            content: Some(vec![Node::text(code)]),
+            attrs: language.map(CodeBlockAttrs::new),
+        }
+    }
+
+    pub fn table(rows: Vec<Node>) -> Self {
+        Node::Table { content: rows, attrs: None }
+    }
+
+    pub fn table_row(cells: Vec<Node>) -> Self {
+        Node::TableRow { content: cells }
+    }
+
+    pub fn table_cell(content: Vec<Node>) -> Self {
+        Node::TableCell { content, attrs: None }
+    }
+
+    pub fn table_header(content: Vec<Node>) -> Self {
+        Node::TableHeader { content, attrs: None }
+    }
+
+    /// Whether the node sits inside paragraphs and headings rather than
+    /// between blocks.
+    pub fn is_inline(&self) -> bool {
+        matches!(
+            self,
+            Node::Date { .. }
+                | Node::Emoji { .. }
+                | Node::HardBreak { .. }
+                | Node::InlineCard { .. }
+                | Node::Mention { .. }
+                | Node::Status { .. }
+                | Node::Text { .. }
+        )
+    }
+
+    /// Text of the node and its descendants, with a newline between blocks
+    /// and for hard breaks.
+    pub fn text_content(&self) -> String {
+        let mut out = String::new();
+        self.push_text_content(&mut out);
+        out
+    }
+
+    fn push_text_content(&self, out: &mut String) {
+        match self {
+            Node::Text { text, .. } => out.push_str(text),
+            Node::HardBreak { .. } => out.push('\n'),
+            Node::Mention { attrs } => match &attrs.text {
+                Some(text) => out.push_str(text),
+                None => out.push_str(&format!("@{}", attrs.id)),
+            },
+            Node::Emoji { attrs } => out.push_str(attrs.text.as_deref().unwrap_or(&attrs.short_name)),
+            Node::Status { attrs } => out.push_str(&attrs.text),
+            Node::InlineCard { attrs } => out.push_str(attrs.url.as_deref().unwrap_or_default()),
+            Node::Date { attrs } => out.push_str(&attrs.timestamp),
+            _ => {
+                let mut previous_block = false;
+                for child in self.content().into_iter().flatten() {
+                    let block = !child.is_inline();
+                    if block && previous_block {
+                        out.push('\n');
+                    }
+                    child.push_text_content(out);
+                    previous_block = block;
+                }
+            }
+        }
+    }
+
+    /// The `localId` of nodes that carry one, `None` when unset or for nodes
+    /// without the attribute.
+    pub fn local_id(&self) -> Option<&str> {
+        let local_id = match self {
+            Node::Heading { attrs, .. } => attrs.local_id.as_deref(),
+            Node::Paragraph { attrs, .. } => attrs.as_ref().map(|attrs| attrs.local_id.as_str()),
+            Node::Status { attrs } => attrs.local_id.as_deref(),
+            Node::TaskList { attrs, .. } => Some(attrs.local_id.as_str()),
+            Node::TaskItem { attrs, .. } => Some(attrs.local_id.as_str()),
+            Node::DecisionList { attrs, .. } => Some(attrs.local_id.as_str()),
+            Node::DecisionItem { attrs, .. } => Some(attrs.local_id.as_str()),
+            _ => None,
+        };
+        local_id.filter(|local_id| !local_id.is_empty())
+    }
+
+    /// Sets the `localId`, returning `false` for nodes without the attribute.
+    pub fn set_local_id<S: Into<String>>(&mut self, local_id: S) -> bool {
+        let local_id = local_id.into();
+        match self {
+            Node::Heading { attrs, .. } => attrs.local_id = Some(local_id),
+            Node::Paragraph { attrs, .. } => *attrs = Some(ParagraphAttrs::new(local_id)),
+            Node::Status { attrs } => attrs.local_id = Some(local_id),
+            Node::TaskList { attrs, .. } => attrs.local_id = local_id,
+            Node::TaskItem { attrs, .. } => attrs.local_id = local_id,
+            Node::DecisionList { attrs, .. } => attrs.local_id = local_id,
+            Node::DecisionItem { attrs, .. } => attrs.local_id = local_id,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Child nodes of container nodes, `None` for leaves.
+    pub fn content(&self) -> Option<&Vec<Node>> {
+        match self {
+            Node::Blockquote { content }
+            | Node::BulletList { content }
+            | Node::Doc { content, .. }
+            | Node::Expand { content, .. }
+            | Node::Heading { content, .. }
+            | Node::ListItem { content }
+            | Node::MediaGroup { content }
+            | Node::MediaSingle { content, .. }
+            | Node::NestedExpand { content, .. }
+            | Node::OrderedList { content, .. }
+            | Node::Panel { content, .. }
+            | Node::Paragraph { content, .. }
+            | Node::Table { content, .. }
+            | Node::TableCell { content, .. }
+            | Node::TableHeader { content, .. }
+            | Node::TableRow { content }
+            | Node::TaskItem { content, .. }
+            | Node::TaskList { content, .. }
+            | Node::DecisionItem { content, .. }
+            | Node::DecisionList { content, .. } => Some(content),
+            Node::CodeBlock { content, .. } => content.as_ref(),
+            Node::Date { .. }
+            | Node::Emoji { .. }
+            | Node::HardBreak { .. }
+            | Node::InlineCard { .. }
+            | Node::Media { .. }
+            | Node::Mention { .. }
+            | Node::Rule
+            | Node::Status { .. }
+            | Node::Text { .. } => None,
+        }
+    }
+
+    /// The node reached by following child indices, such as the `path` of
+    /// an outline entry.
+    pub fn descendant(&self, path: &[usize]) -> Option<&Node> {
+        path.iter().try_fold(self, |node, index| node.content()?.get(*index))
+    }
+
+    pub fn descendant_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        path.iter().try_fold(self, |node, index| node.content_mut()?.get_mut(*index))
+    }
+
+    pub fn content_mut(&mut self) -> Option<&mut Vec<Node>> {
+        match self {
+            Node::Blockquote { content }
+            | Node::BulletList { content }
+            | Node::Doc { content, .. }
+            | Node::Expand { content, .. }
+            | Node::Heading { content, .. }
+            | Node::ListItem { content }
+            | Node::MediaGroup { content }
+            | Node::MediaSingle { content, .. }
+            | Node::NestedExpand { content, .. }
+            | Node::OrderedList { content, .. }
+            | Node::Panel { content, .. }
+            | Node::Paragraph { content, .. }
+            | Node::Table { content, .. }
+            | Node::TableCell { content, .. }
+            | Node::TableHeader { content, .. }
+            | Node::TableRow { content }
+            | Node::TaskItem { content, .. }
+            | Node::TaskList { content, .. }
+            | Node::DecisionItem { content, .. }
+            | Node::DecisionList { content, .. } => Some(content),
+            Node::CodeBlock { content, .. } => content.as_mut(),
+            Node::Date { .. }
+            | Node::Emoji { .. }
+            | Node::HardBreak { .. }
+            | Node::InlineCard { .. }
+            | Node::Media { .. }
+            | Node::Mention { .. }
+            | Node::Rule
+            | Node::Status { .. }
+            | Node::Text { .. } => None,
+        }
+    }
+
+    /// First attribute on this node holding a value outside the set defined
+    /// by ADF, as `(attribute name, value)`.
+    pub(crate) fn unknown_attr(&self) -> Option<(&'static str, &str)> {
+        match self {
+            Node::Media { attrs } => attrs.type_.other().map(|value| ("type", value)),
+            Node::MediaSingle { attrs, .. } => attrs
+                .layout
+                .other()
+                .map(|value| ("layout", value))
+                .or_else(|| attrs.width_type.as_ref()?.other().map(|value| ("widthType", value))),
+            Node::Mention { attrs } => attrs.user_type.as_ref()?.other().map(|value| ("userType", value)),
+            Node::Panel { attrs, .. } => attrs.panel_type.other().map(|value| ("panelType", value)),
+            Node::Status { attrs } => attrs.color.other().map(|value| ("color", value)),
+            Node::TaskItem { attrs, .. } => attrs.state.other().map(|value| ("state", value)),
+            Node::DecisionItem { attrs, .. } => attrs.state.other().map(|value| ("state", value)),
+            Node::Table { attrs: Some(attrs), .. } => attrs
+                .display_mode
+                .as_ref()
+                .and_then(TableDisplayMode::other)
+                .map(|value| ("displayMode", value))
+                .or_else(|| attrs.layout.as_ref()?.other().map(|value| ("layout", value))),
+            _ => None,
+        }
+    }
+}
+
+// The derived drop glue recurses once per nesting level, so a deep enough
+// tree overflows the stack while being freed. Detach children onto a heap
+// stack instead so that every node is dropped with no children left.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack = match self.content_mut() {
+            Some(content) if !content.is_empty() => std::mem::take(content),
+            _ => return,
+        };
+        while let Some(mut node) = stack.pop() {
+            if let Some(content) = node.content_mut() {
+                stack.append(content);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum Mark {
@@ -121,24 +401,102 @@ pub enum Mark {
 
 // ------ Attrs -------
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ColorAttrs {
-    pub(crate) color: String, // defined in HTML hexadecimal format (e.g. #daa520)
+    pub(crate) color: Color,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl ColorAttrs {
+    pub fn new(color: Color) -> Self {
+        ColorAttrs { color }
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CodeBlockAttrs {
     pub(crate) language: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl CodeBlockAttrs {
+    pub fn new<S: Into<String>>(language: S) -> Self {
+        CodeBlockAttrs { language: language.into() }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DateAttrs {
     // Example: 1582152559
     // Unix timestamp in seconds
     pub(crate) timestamp: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl DateAttrs {
+    pub fn new<S: Into<String>>(timestamp: S) -> Self {
+        DateAttrs { timestamp: timestamp.into() }
+    }
+
+    /// Midnight UTC at the start of the given day, stored in seconds.
+    pub fn from_ymd(year: i32, month: u8, day: u8) -> Result<Self, DateError> {
+        let timestamp = Timestamp::from_ymd(year, month, day)?;
+        Ok(DateAttrs { timestamp: timestamp.as_seconds().to_string() })
+    }
+
+    /// The timestamp exactly as stored in the document.
+    pub fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    pub fn parsed_timestamp(&self) -> Result<Timestamp, DateError> {
+        Timestamp::parse(&self.timestamp)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionItemAttrs {
+    pub(crate) local_id: String,
+    pub(crate) state: DecisionState,
+}
+
+impl DecisionItemAttrs {
+    pub fn new<S: Into<String>>(local_id: S) -> Self {
+        DecisionItemAttrs { local_id: local_id.into(), state: DecisionState::Decided }
+    }
+
+    pub fn local_id(&self) -> &str {
+        &self.local_id
+    }
+
+    pub fn state(&self) -> &DecisionState {
+        &self.state
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionListAttrs {
+    pub(crate) local_id: String,
+}
+
+impl DecisionListAttrs {
+    pub fn new<S: Into<String>>(local_id: S) -> Self {
+        DecisionListAttrs { local_id: local_id.into() }
+    }
+
+    pub fn local_id(&self) -> &str {
+        &self.local_id
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct EmojiAttrs {
     pub(crate) short_name: String,
@@ -146,30 +504,115 @@ pub struct EmojiAttrs {
     pub(crate) text: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl EmojiAttrs {
+    pub fn new<S: Into<String>>(short_name: S) -> Self {
+        EmojiAttrs { short_name: short_name.into(), id: None, text: None }
+    }
+
+    pub fn with_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn with_text<S: Into<String>>(mut self, text: S) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn short_name(&self) -> &str {
+        &self.short_name
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ExpandAttrs {
     pub(crate) title: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl ExpandAttrs {
+    pub fn new<S: Into<String>>(title: S) -> Self {
+        ExpandAttrs { title: title.into() }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct HardBreakAttrs {
     pub(crate) text: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl HardBreakAttrs {
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        HardBreakAttrs { text: text.into() }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct HeadingAttrs {
     pub(crate) level: i8,
     pub(crate) local_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl HeadingAttrs {
+    pub fn new(level: i8) -> Self {
+        HeadingAttrs { level, local_id: None }
+    }
+
+    pub fn with_local_id<S: Into<String>>(mut self, local_id: S) -> Self {
+        self.local_id = Some(local_id.into());
+        self
+    }
+
+    pub fn level(&self) -> i8 {
+        self.level
+    }
+
+    pub fn local_id(&self) -> Option<&str> {
+        self.local_id.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct InlineCardAttrs {
     pub(crate) data: Option<String>, // TODO: uses https://json-ld.org/ for structured data
     pub(crate) url: Option<String>, // TODO: according to website this is an object type?
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl InlineCardAttrs {
+    pub fn from_url<S: Into<String>>(url: S) -> Self {
+        InlineCardAttrs { data: None, url: Some(url.into()) }
+    }
+
+    pub fn from_data<S: Into<String>>(data: S) -> Self {
+        InlineCardAttrs { data: Some(data.into()), url: None }
+    }
+
+    pub fn data(&self) -> Option<&str> {
+        self.data.as_deref()
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct LinkAttrs {
     pub(crate) collection: Option<String>,
@@ -179,11 +622,57 @@ pub struct LinkAttrs {
     pub(crate) title: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl LinkAttrs {
+    pub fn new<S: Into<String>>(href: S) -> Self {
+        LinkAttrs { collection: None, href: href.into(), id: None, occurrence_key: None, title: None }
+    }
+
+    pub fn with_collection<S: Into<String>>(mut self, collection: S) -> Self {
+        self.collection = Some(collection.into());
+        self
+    }
+
+    pub fn with_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn with_occurrence_key<S: Into<String>>(mut self, occurrence_key: S) -> Self {
+        self.occurrence_key = Some(occurrence_key.into());
+        self
+    }
+
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn collection(&self) -> Option<&str> {
+        self.collection.as_deref()
+    }
+
+    pub fn href(&self) -> &str {
+        &self.href
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn occurrence_key(&self) -> Option<&str> {
+        self.occurrence_key.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct MediaAttrs {
     #[serde(rename = "type")]
-    pub(crate) type_: String,
+    pub(crate) type_: MediaType,
     pub(crate) width: Option<u32>,
     pub(crate) height: Option<u32>,
     pub(crate) id: String,
@@ -191,66 +680,334 @@ pub struct MediaAttrs {
     pub(crate) occurrence_key: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl MediaAttrs {
+    pub fn new<I: Into<String>, C: Into<String>>(type_: MediaType, id: I, collection: C) -> Self {
+        MediaAttrs {
+            type_,
+            width: None,
+            height: None,
+            id: id.into(),
+            collection: collection.into(),
+            occurrence_key: None,
+        }
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = Some(width);
+        self.height = Some(height);
+        self
+    }
+
+    pub fn with_occurrence_key<S: Into<String>>(mut self, occurrence_key: S) -> Self {
+        self.occurrence_key = Some(occurrence_key.into());
+        self
+    }
+
+    pub fn media_type(&self) -> &MediaType {
+        &self.type_
+    }
+
+    pub fn width(&self) -> Option<u32> {
+        self.width
+    }
+
+    pub fn height(&self) -> Option<u32> {
+        self.height
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    pub fn occurrence_key(&self) -> Option<&str> {
+        self.occurrence_key.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct MediaSingleAttrs {
-    pub(crate) layout: String,
+    pub(crate) layout: MediaSingleLayout,
     pub(crate) width: Option<f32>, // float between 0 and 100
-    pub(crate) width_type: Option<String>, // TODO: ENUM either pixel or percentage
+    pub(crate) width_type: Option<WidthType>,
+}
+
+impl MediaSingleAttrs {
+    pub fn new(layout: MediaSingleLayout) -> Self {
+        MediaSingleAttrs { layout, width: None, width_type: None }
+    }
+
+    pub fn with_width(mut self, width: f32, width_type: WidthType) -> Self {
+        self.width = Some(width);
+        self.width_type = Some(width_type);
+        self
+    }
+
+    pub fn layout(&self) -> &MediaSingleLayout {
+        &self.layout
+    }
+
+    pub fn width(&self) -> Option<f32> {
+        self.width
+    }
+
+    pub fn width_type(&self) -> Option<&WidthType> {
+        self.width_type.as_ref()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct MentionAttrs {
     pub(crate) id: String,
     pub(crate) text: Option<String>,
-    pub(crate) user_type: Option<String>,
+    pub(crate) user_type: Option<MentionUserType>,
+}
+
+impl MentionAttrs {
+    pub fn new<S: Into<String>>(id: S) -> Self {
+        MentionAttrs { id: id.into(), text: None, user_type: None }
+    }
+
+    pub fn with_text<S: Into<String>>(mut self, text: S) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn with_user_type(mut self, user_type: MentionUserType) -> Self {
+        self.user_type = Some(user_type);
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    pub fn user_type(&self) -> Option<&MentionUserType> {
+        self.user_type.as_ref()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct OrderedListAttrs {
     pub(crate) order: u16,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl OrderedListAttrs {
+    pub fn new(order: u16) -> Self {
+        OrderedListAttrs { order }
+    }
+
+    pub fn order(&self) -> u16 {
+        self.order
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct PanelAttrs {
-    pub(crate) panel_type: String,
+    pub(crate) panel_type: PanelType,
+}
+
+impl PanelAttrs {
+    pub fn new(panel_type: PanelType) -> Self {
+        PanelAttrs { panel_type }
+    }
+
+    pub fn panel_type(&self) -> &PanelType {
+        &self.panel_type
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ParagraphAttrs {
     pub(crate) local_id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl ParagraphAttrs {
+    pub fn new<S: Into<String>>(local_id: S) -> Self {
+        ParagraphAttrs { local_id: local_id.into() }
+    }
+
+    pub fn local_id(&self) -> &str {
+        &self.local_id
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct StatusAttrs {
     pub(crate) local_id: Option<String>,
     pub(crate) text: String,
-    pub(crate) color: String,
+    pub(crate) color: StatusColor,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl StatusAttrs {
+    pub fn new<S: Into<String>>(text: S, color: StatusColor) -> Self {
+        StatusAttrs { local_id: None, text: text.into(), color }
+    }
+
+    pub fn with_local_id<S: Into<String>>(mut self, local_id: S) -> Self {
+        self.local_id = Some(local_id.into());
+        self
+    }
+
+    pub fn local_id(&self) -> Option<&str> {
+        self.local_id.as_deref()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn color(&self) -> &StatusColor {
+        &self.color
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TableAttrs {
-    pub(crate) display_mode: Option<String>,
+    pub(crate) display_mode: Option<TableDisplayMode>,
     pub(crate) is_number_column_enabled: Option<bool>,
-    pub(crate) layout: Option<String>,
+    pub(crate) layout: Option<TableLayout>,
     pub(crate) width: Option<u16>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl TableAttrs {
+    pub fn with_display_mode(mut self, display_mode: TableDisplayMode) -> Self {
+        self.display_mode = Some(display_mode);
+        self
+    }
+
+    pub fn with_number_column(mut self, enabled: bool) -> Self {
+        self.is_number_column_enabled = Some(enabled);
+        self
+    }
+
+    pub fn with_layout(mut self, layout: TableLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    pub fn with_width(mut self, width: u16) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn display_mode(&self) -> Option<&TableDisplayMode> {
+        self.display_mode.as_ref()
+    }
+
+    pub fn is_number_column_enabled(&self) -> bool {
+        self.is_number_column_enabled.unwrap_or(false)
+    }
+
+    pub fn layout(&self) -> Option<&TableLayout> {
+        self.layout.as_ref()
+    }
+
+    pub fn width(&self) -> Option<u16> {
+        self.width
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TableCellAttrs {
-    pub(crate) background: Option<String>,
+    pub(crate) background: Option<Color>,
     pub(crate) colspan: Option<u16>,
     pub(crate) colwidth: Option<Vec<u16>>,
     pub(crate) rowspan: Option<u16>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+impl TableCellAttrs {
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    pub fn with_colspan(mut self, colspan: u16) -> Self {
+        self.colspan = Some(colspan);
+        self
+    }
+
+    pub fn with_colwidth(mut self, colwidth: Vec<u16>) -> Self {
+        self.colwidth = Some(colwidth);
+        self
+    }
+
+    pub fn with_rowspan(mut self, rowspan: u16) -> Self {
+        self.rowspan = Some(rowspan);
+        self
+    }
+
+    pub fn background(&self) -> Option<Color> {
+        self.background
+    }
+
+    /// Number of columns the cell covers, 1 when unset.
+    pub fn colspan(&self) -> u16 {
+        self.colspan.unwrap_or(1)
+    }
+
+    pub fn colwidth(&self) -> Option<&[u16]> {
+        self.colwidth.as_deref()
+    }
+
+    /// Number of rows the cell covers, 1 when unset.
+    pub fn rowspan(&self) -> u16 {
+        self.rowspan.unwrap_or(1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskItemAttrs {
+    pub(crate) local_id: String,
+    pub(crate) state: TaskState,
+}
+
+impl TaskItemAttrs {
+    pub fn new<S: Into<String>>(local_id: S, state: TaskState) -> Self {
+        TaskItemAttrs { local_id: local_id.into(), state }
+    }
+
+    pub fn local_id(&self) -> &str {
+        &self.local_id
+    }
+
+    pub fn state(&self) -> &TaskState {
+        &self.state
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskListAttrs {
+    pub(crate) local_id: String,
+}
+
+impl TaskListAttrs {
+    pub fn new<S: Into<String>>(local_id: S) -> Self {
+        TaskListAttrs { local_id: local_id.into() }
+    }
+
+    pub fn local_id(&self) -> &str {
+        &self.local_id
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum SubSupAttrs {
@@ -258,4 +1015,134 @@ pub enum SubSupAttrs {
     Sub,
 }
 
-// ------ Attrs -------
\ No newline at end of file
+// ------ Attrs -------
+
+// ------ Enums -------
+
+// Generates a closed set of string values with an `Other` variant, so that
+// unknown values survive a lenient parse and strict parsing can reject them.
+macro_rules! string_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal,)+ }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)+
+            Other(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)+
+                    $name::Other(value) => value,
+                }
+            }
+
+            /// The raw value when it is not one defined by ADF.
+            pub fn other(&self) -> Option<&str> {
+                match self {
+                    $name::Other(value) => Some(value),
+                    _ => None,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => $name::$variant,)+
+                    other => $name::Other(other.to_string()),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Ok($name::from(value.as_str()))
+            }
+        }
+    };
+}
+
+string_enum!(MediaType {
+    File => "file",
+    Link => "link",
+    External => "external",
+});
+
+string_enum!(MediaSingleLayout {
+    WrapLeft => "wrap-left",
+    Center => "center",
+    WrapRight => "wrap-right",
+    Wide => "wide",
+    FullWidth => "full-width",
+    AlignStart => "align-start",
+    AlignEnd => "align-end",
+});
+
+string_enum!(WidthType {
+    Pixel => "pixel",
+    Percentage => "percentage",
+});
+
+string_enum!(MentionUserType {
+    Default => "DEFAULT",
+    Special => "SPECIAL",
+    App => "APP",
+});
+
+string_enum!(PanelType {
+    Info => "info",
+    Note => "note",
+    Warning => "warning",
+    Success => "success",
+    Error => "error",
+    Custom => "custom",
+});
+
+string_enum!(StatusColor {
+    Neutral => "neutral",
+    Purple => "purple",
+    Blue => "blue",
+    Red => "red",
+    Yellow => "yellow",
+    Green => "green",
+});
+
+string_enum!(TaskState {
+    Todo => "TODO",
+    Done => "DONE",
+});
+
+string_enum!(DecisionState {
+    Decided => "DECIDED",
+});
+
+string_enum!(TableDisplayMode {
+    Default => "default",
+    Fixed => "fixed",
+});
+
+string_enum!(TableLayout {
+    Default => "default",
+    Center => "center",
+    Wide => "wide",
+    FullWidth => "full-width",
+    AlignStart => "align-start",
+    AlignEnd => "align-end",
+});
+
+// ------ Enums -------
@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::mem;
+
+use crate::model::{LinkAttrs, Mark, Node, ParagraphAttrs};
+
+/// A heading in the document outline, with the headings under it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineEntry {
+    pub level: i8,
+    pub text: String,
+    pub local_id: Option<String>,
+    /// Link target for the heading: its `localId`, or a slug of its text
+    /// when it has none. A slug already taken by a `localId` or an earlier
+    /// heading gets `-2`, `-3` and so on, in document order.
+    pub anchor: String,
+    /// Child indices leading from the root to the heading.
+    pub path: Vec<usize>,
+    pub children: Vec<OutlineEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocOptions {
+    /// Deepest heading level listed in the table of contents.
+    pub max_level: i8,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        TocOptions { max_level: 6 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutlineError {
+    NotADoc,
+    IndexOutOfBounds { index: usize },
+}
+
+impl fmt::Display for OutlineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutlineError::NotADoc => write!(f, "node is not a doc"),
+            OutlineError::IndexOutOfBounds { index } => write!(f, "index {} is past the end of the doc", index),
+        }
+    }
+}
+
+impl std::error::Error for OutlineError {}
+
+/// Headings of the document nested by level. A heading becomes a child of
+/// the closest preceding heading with a lower level, so skipped levels
+/// (an `h3` straight under an `h1`) nest one step rather than leaving gaps.
+pub fn outline(root: &Node) -> Vec<OutlineEntry> {
+    let mut roots: Vec<OutlineEntry> = Vec::new();
+    // Entries still open for children, from the outermost in.
+    let mut open: Vec<OutlineEntry> = Vec::new();
+    for entry in headings(root) {
+        while open.last().is_some_and(|parent| parent.level >= entry.level) {
+            close(&mut open, &mut roots);
+        }
+        open.push(entry);
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+    roots
+}
+
+/// Inserts a table of contents, a nested bullet list linking to every
+/// heading, as the block at index `at` of the doc. Headings without a
+/// `localId` are given their anchor as one, so that the links resolve in
+/// every output format.
+pub fn insert_table_of_contents(doc: &mut Node, at: usize, options: &TocOptions) -> Result<(), OutlineError> {
+    let Node::Doc { content, .. } = doc else {
+        return Err(OutlineError::NotADoc);
+    };
+    if at > content.len() {
+        return Err(OutlineError::IndexOutOfBounds { index: at });
+    }
+    let entries = outline(doc);
+    for entry in headings(doc).into_iter().filter(|entry| entry.local_id.is_none()) {
+        if let Some(Node::Heading { attrs, .. }) = doc.descendant_mut(&entry.path) {
+            attrs.local_id = Some(entry.anchor);
+        }
+    }
+    if let (Some(list), Node::Doc { content, .. }) = (toc_list(&entries, options), doc) {
+        content.insert(at, list);
+    }
+    Ok(())
+}
+
+/// What [`shift_headings`] does with a heading shifted outside levels 1–6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutOfRange {
+    /// Keep it a heading at level 1 or 6.
+    Clamp,
+    /// Turn it into a paragraph with its text in bold.
+    BoldParagraph,
+}
+
+/// Moves every heading in the subtree by `by` levels, positive to demote
+/// (`h1` to `h2`) and negative to promote. To shift part of a document only,
+/// pass the subtree, e.g. from [`Node::descendant_mut`].
+pub fn shift_headings(root: &mut Node, by: i8, out_of_range: OutOfRange) {
+    if let Node::Heading { attrs, .. } = root {
+        let level = attrs.level.saturating_add(by);
+        if (1..=6).contains(&level) || out_of_range == OutOfRange::Clamp {
+            attrs.level = level.clamp(1, 6);
+        } else {
+            let local_id = attrs.local_id.take();
+            let mut content = root.content_mut().map(mem::take).unwrap_or_default();
+            for node in &mut content {
+                embolden(node);
+            }
+            *root = Node::Paragraph { content, attrs: local_id.map(ParagraphAttrs::new) };
+        }
+        return;
+    }
+    for child in root.content_mut().into_iter().flatten() {
+        shift_headings(child, by, out_of_range);
+    }
+}
+
+fn embolden(node: &mut Node) {
+    if let Node::Text { marks, .. } = node {
+        let marks = marks.get_or_insert_with(Vec::new);
+        if !marks.contains(&Mark::Strong) {
+            marks.push(Mark::Strong);
+        }
+    }
+}
+
+/// A copy of the tree in which headings whose anchor is not their plain slug
+/// have it as `localId`, so that ids taken from heading text are unique in
+/// the document, or `None` when no heading needs it.
+#[cfg(feature = "html")]
+pub(crate) fn with_heading_anchors(root: &Node) -> Option<Node> {
+    let mut headings = headings(root);
+    headings.retain(|entry| entry.local_id.is_none() && entry.anchor != slug(&entry.text));
+    if headings.is_empty() {
+        return None;
+    }
+    let mut anchored = root.clone();
+    for entry in headings {
+        if let Some(Node::Heading { attrs, .. }) = anchored.descendant_mut(&entry.path) {
+            attrs.local_id = Some(entry.anchor);
+        }
+    }
+    Some(anchored)
+}
+
+// Anchors handed out so far in a document.
+#[derive(Default)]
+struct Anchors {
+    taken: HashSet<String>,
+    // Suffix to try next for each base, so repeats are not counted up from 2
+    // each time.
+    next: HashMap<String, usize>,
+}
+
+impl Anchors {
+    fn unique(&mut self, base: String) -> String {
+        if self.taken.insert(base.clone()) {
+            return base;
+        }
+        let next = self.next.entry(base.clone()).or_insert(2);
+        loop {
+            let anchor = format!("{}-{}", base, next);
+            *next += 1;
+            if self.taken.insert(anchor.clone()) {
+                return anchor;
+            }
+        }
+    }
+}
+
+// Every heading in document order, flat, with its anchor. `localId`s are
+// reserved first so that a slug never takes one from a later heading.
+fn headings(root: &Node) -> Vec<OutlineEntry> {
+    let mut headings = Vec::new();
+    collect_headings(root, &mut Vec::new(), &mut headings);
+    let mut anchors = Anchors::default();
+    anchors.taken.extend(headings.iter().filter_map(|entry| entry.local_id.clone()));
+    for entry in &mut headings {
+        entry.anchor = match &entry.local_id {
+            Some(local_id) => local_id.clone(),
+            None => anchors.unique(slug(&entry.text)),
+        };
+    }
+    headings
+}
+
+fn collect_headings(node: &Node, path: &mut Vec<usize>, out: &mut Vec<OutlineEntry>) {
+    if let Node::Heading { attrs, .. } = node {
+        out.push(OutlineEntry {
+            level: attrs.level,
+            text: node.text_content(),
+            local_id: attrs.local_id.clone(),
+            anchor: String::new(),
+            path: path.clone(),
+            children: Vec::new(),
+        });
+        return;
+    }
+    for (index, child) in node.content().into_iter().flatten().enumerate() {
+        path.push(index);
+        collect_headings(child, path, out);
+        path.pop();
+    }
+}
+
+fn close(open: &mut Vec<OutlineEntry>, roots: &mut Vec<OutlineEntry>) {
+    let entry = open.pop().expect("closing an open entry");
+    match open.last_mut() {
+        Some(parent) => parent.children.push(entry),
+        None => roots.push(entry),
+    }
+}
+
+fn toc_list(entries: &[OutlineEntry], options: &TocOptions) -> Option<Node> {
+    let items: Vec<Node> = entries
+        .iter()
+        .filter(|entry| entry.level <= options.max_level)
+        .map(|entry| {
+            let link = Mark::Link { attrs: LinkAttrs::new(format!("#{}", entry.anchor)) };
+            let mut content = vec![Node::paragraph(vec![Node::text_with_marks(entry.text.clone(), vec![link])])];
+            content.extend(toc_list(&entry.children, options));
+            Node::list_item(content)
+        })
+        .collect();
+    (!items.is_empty()).then(|| Node::bullet_list(items))
+}
+
+/// Lowercase words joined by hyphens, dropping punctuation; the anchor of
+/// headings without a `localId`.
+pub(crate) fn slug(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod outline_tests {
+    use super::*;
+    use crate::model::{ExpandAttrs, HeadingAttrs, PanelAttrs, PanelType};
+
+    fn heading(level: i8, text: &str) -> Node {
+        Node::heading(level, vec![Node::text(text)])
+    }
+
+    fn titles(entries: &[OutlineEntry]) -> Vec<(String, Vec<String>)> {
+        entries
+            .iter()
+            .map(|entry| (entry.text.clone(), entry.children.iter().map(|child| child.text.clone()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn should_nest_headings_tolerating_skipped_levels() {
+        let doc = Node::doc(vec![
+            heading(2, "Intro"),
+            heading(1, "Setup"),
+            heading(3, "Install"),
+            Node::Panel {
+                content: vec![heading(2, "Configure")],
+                attrs: PanelAttrs::new(PanelType::Info),
+            },
+            heading(1, "Usage"),
+        ]);
+        let entries = outline(&doc);
+
+        assert_eq!(
+            titles(&entries),
+            vec![
+                ("Intro".to_string(), vec![]),
+                ("Setup".to_string(), vec!["Install".to_string(), "Configure".to_string()]),
+                ("Usage".to_string(), vec![]),
+            ]
+        );
+        assert_eq!(entries[1].children[1].path, vec![3, 0]);
+    }
+
+    #[test]
+    fn should_shift_headings_clamping_or_converting() {
+        let mut doc = Node::doc(vec![
+            heading(1, "Report"),
+            Node::Panel { content: vec![heading(5, "Detail")], attrs: PanelAttrs::new(PanelType::Note) },
+            heading(6, "Fine print"),
+        ]);
+
+        shift_headings(&mut doc, 1, OutOfRange::BoldParagraph);
+        let mut clamped = doc.clone();
+        shift_headings(&mut clamped, -3, OutOfRange::Clamp);
+
+        assert_eq!(doc.descendant(&[0]), Some(&heading(2, "Report")));
+        assert_eq!(doc.descendant(&[1, 0]), Some(&heading(6, "Detail")));
+        assert_eq!(
+            doc.descendant(&[2]),
+            Some(&Node::paragraph(vec![Node::text_with_marks("Fine print", vec![Mark::Strong])]))
+        );
+        assert_eq!(clamped.descendant(&[0]), Some(&heading(1, "Report")));
+        assert_eq!(clamped.descendant(&[1, 0]), Some(&heading(3, "Detail")));
+    }
+
+    #[test]
+    fn should_shift_headings_in_a_subtree_only() {
+        let mut doc = Node::doc(vec![
+            heading(1, "Parent"),
+            Node::Expand {
+                content: vec![heading(1, "Embedded")],
+                attrs: ExpandAttrs::new("Sub-report"),
+                marks: None,
+            },
+        ]);
+
+        shift_headings(doc.descendant_mut(&[1]).unwrap(), 2, OutOfRange::Clamp);
+
+        assert_eq!(doc.descendant(&[0]), Some(&heading(1, "Parent")));
+        assert_eq!(doc.descendant(&[1, 0]), Some(&heading(3, "Embedded")));
+    }
+
+    #[test]
+    fn should_insert_table_of_contents() {
+        let mut doc = Node::doc(vec![
+            Node::Heading {
+                content: vec![Node::text("Set up")],
+                attrs: HeadingAttrs::new(1).with_local_id("setup"),
+            },
+            heading(2, "Install & run!"),
+            heading(3, "Too deep"),
+        ]);
+        insert_table_of_contents(&mut doc, 0, &TocOptions { max_level: 2 }).unwrap();
+
+        let link = |href: &str, text: &str| {
+            Node::paragraph(vec![Node::text_with_marks(text, vec![Mark::Link { attrs: LinkAttrs::new(href) }])])
+        };
+        let Node::Doc { content, .. } = &doc else { unreachable!() };
+        assert_eq!(
+            content[0],
+            Node::bullet_list(vec![Node::list_item(vec![
+                link("#setup", "Set up"),
+                Node::bullet_list(vec![Node::list_item(vec![link("#install-run", "Install & run!")])]),
+            ])])
+        );
+        let local_id = |index: usize| match &content[index] {
+            Node::Heading { attrs, .. } => attrs.local_id().map(str::to_string),
+            _ => None,
+        };
+        assert_eq!([local_id(1), local_id(2)], [Some("setup".to_string()), Some("install-run".to_string())]);
+        assert_eq!(
+            insert_table_of_contents(&mut doc, 9, &TocOptions::default()),
+            Err(OutlineError::IndexOutOfBounds { index: 9 })
+        );
+    }
+
+    #[test]
+    fn should_number_repeated_anchors() {
+        let doc = Node::doc(vec![
+            heading(1, "Notes"),
+            heading(2, "Notes"),
+            heading(2, "Notes 2"),
+            Node::Heading { content: vec![Node::text("Later")], attrs: HeadingAttrs::new(1).with_local_id("notes") },
+        ]);
+        let entries = outline(&doc);
+
+        let anchors = [&entries[0], &entries[0].children[0], &entries[0].children[1], &entries[1]];
+        assert_eq!(anchors.map(|entry| entry.anchor.as_str()), ["notes-2", "notes-3", "notes-2-2", "notes"]);
+    }
+}
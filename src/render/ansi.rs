@@ -0,0 +1,703 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::date::DateFormat;
+use crate::emoji::CustomEmojiFallback;
+use crate::model::{Color, Mark, Node, PanelType, StatusColor, TaskState};
+use crate::resolve::{self, Resolvers};
+use crate::table::TableGrid;
+
+#[derive(Debug, Clone)]
+pub struct AnsiOptions {
+    /// Columns to wrap to, usually the terminal width.
+    pub width: usize,
+    /// Emit links as OSC 8 hyperlinks. When off, the URL is written out
+    /// after the link text, for terminals that show the escape codes.
+    pub hyperlinks: bool,
+    /// Format and timezone used to display `date` nodes.
+    pub date_format: DateFormat,
+    /// How custom emoji appear. An image fallback links the short name to
+    /// the image.
+    pub custom_emoji: CustomEmojiFallback,
+    /// Looks up user names, media URLs and link titles.
+    pub resolvers: Resolvers,
+}
+
+impl Default for AnsiOptions {
+    fn default() -> Self {
+        AnsiOptions {
+            width: 80,
+            hyperlinks: true,
+            date_format: DateFormat::default(),
+            custom_emoji: CustomEmojiFallback::default(),
+            resolvers: Resolvers::default(),
+        }
+    }
+}
+
+pub fn to_ansi(node: &Node, options: &AnsiOptions) -> String {
+    let renderer = Renderer { options };
+    let mut out = renderer.block(node, options.width.max(1)).join("\n");
+    out.push('\n');
+    out
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    strike: bool,
+    // SGR parameters, such as `36` or `38;2;255;86;48`.
+    fg: Option<String>,
+    bg: Option<String>,
+    link: Option<String>,
+}
+
+impl Style {
+    fn bold() -> Self {
+        Style { bold: true, ..Style::default() }
+    }
+
+    fn dim() -> Self {
+        Style { dim: true, ..Style::default() }
+    }
+
+    fn fg(sgr: &str) -> Self {
+        Style { fg: Some(sgr.to_string()), ..Style::default() }
+    }
+}
+
+// Text in one style. A `\n` piece is a hard line break.
+#[derive(Debug, Clone)]
+struct Piece {
+    text: String,
+    style: Style,
+}
+
+struct Renderer<'a> {
+    options: &'a AnsiOptions,
+}
+
+impl Renderer<'_> {
+    fn block(&self, node: &Node, width: usize) -> Vec<String> {
+        match node {
+            Node::Doc { content, .. } => self.blocks(content, width, false),
+            Node::Paragraph { content, .. } => self.paragraph(content, &Style::default(), width),
+            Node::Heading { content, attrs } => {
+                let style = Style { bold: true, underline: attrs.level <= 1, ..Style::default() };
+                self.paragraph(content, &style, width)
+            }
+            Node::BulletList { content } => self.list(content, width, |_| "• ".to_string()),
+            Node::OrderedList { content, attrs } => {
+                let start = attrs.as_ref().map_or(1, |attrs| attrs.order as usize);
+                let digits = (start + content.len().saturating_sub(1)).to_string().len();
+                self.list(content, width, |index| format!("{:>digits$}. ", start + index, digits = digits))
+            }
+            Node::TaskList { content, .. } | Node::DecisionList { content, .. } => {
+                content.iter().flat_map(|item| self.block(item, width)).collect()
+            }
+            Node::ListItem { content } => self.blocks(content, width, true),
+            Node::TaskItem { content, attrs } => {
+                let marker = if attrs.state == TaskState::Done { "☑ " } else { "☐ " };
+                prefixed(marker, "  ", self.paragraph(content, &Style::default(), width.saturating_sub(2)))
+            }
+            Node::DecisionItem { content, .. } => {
+                let marker = paint("◆ ", &Style::fg("32"), self.options);
+                prefixed(&marker, "  ", self.paragraph(content, &Style::default(), width.saturating_sub(2)))
+            }
+            Node::Blockquote { content } => {
+                let bar = paint("│ ", &Style::dim(), self.options);
+                prefixed(&bar, &bar, self.blocks(content, width.saturating_sub(2), false))
+            }
+            Node::Panel { content, attrs } => {
+                let color = panel_color(&attrs.panel_type);
+                let bar = paint("┃ ", &Style::fg(color), self.options);
+                let label = Style { bold: true, ..Style::fg(color) };
+                let title = strip_controls(&attrs.panel_type.as_str().to_uppercase());
+                let mut lines = vec![paint(&title, &label, self.options)];
+                lines.extend(self.blocks(content, width.saturating_sub(2), false));
+                prefixed(&bar, &bar, lines)
+            }
+            Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+                let mut lines = self.paragraph(&[Node::text(format!("▾ {}", attrs.title))], &Style::bold(), width);
+                lines.extend(prefixed("  ", "  ", self.blocks(content, width.saturating_sub(2), false)));
+                lines
+            }
+            Node::CodeBlock { content, .. } => self.code_block(content.as_deref().unwrap_or_default()),
+            Node::Rule => vec![paint(&"─".repeat(width.min(self.options.width)), &Style::dim(), self.options)],
+            Node::Table { .. } => match TableGrid::new(node) {
+                Ok(grid) => self.table(&grid, width),
+                // Not a grid: show the cells one after another.
+                Err(_) => {
+                    let rows = node.content().into_iter().flatten();
+                    let cells = rows.flat_map(|row| row.content().into_iter().flatten());
+                    cells.flat_map(|cell| self.block(cell, width)).collect()
+                }
+            },
+            Node::TableRow { content }
+            | Node::TableCell { content, .. }
+            | Node::TableHeader { content, .. }
+            | Node::MediaSingle { content, .. }
+            | Node::MediaGroup { content } => self.blocks(content, width, true),
+            Node::Media { attrs } => {
+                let (text, link) = match self.options.resolvers.media(attrs) {
+                    Some(media) => (format!("[{}]", media.alt.unwrap_or_else(|| "image".to_string())), Some(media.url)),
+                    None => ("[attachment]".to_string(), None),
+                };
+                let style = Style { link, ..Style::dim() };
+                self.wrap(&[Piece { text, style }], width)
+            }
+            inline => self.paragraph(std::slice::from_ref(inline), &Style::default(), width),
+        }
+    }
+
+    // Blocks separated by blank lines, or not when tight.
+    fn blocks(&self, content: &[Node], width: usize, tight: bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (index, node) in content.iter().enumerate() {
+            if index > 0 && !tight {
+                lines.push(String::new());
+            }
+            lines.extend(self.block(node, width));
+        }
+        lines
+    }
+
+    fn paragraph(&self, content: &[Node], style: &Style, width: usize) -> Vec<String> {
+        let mut pieces = Vec::new();
+        for node in content {
+            self.inline(node, style, &mut pieces);
+        }
+        self.wrap(&pieces, width)
+    }
+
+    fn list(&self, items: &[Node], width: usize, marker: impl Fn(usize) -> String) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            let marker = marker(index);
+            let indent = " ".repeat(marker.width());
+            lines.extend(prefixed(&marker, &indent, self.block(item, width.saturating_sub(marker.width()))));
+        }
+        lines
+    }
+
+    fn code_block(&self, content: &[Node]) -> Vec<String> {
+        let code: String = content
+            .iter()
+            .filter_map(|node| match node {
+                Node::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        let lines: Vec<&str> = code.lines().collect();
+        let digits = lines.len().max(1).to_string().len();
+        lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let gutter = format!("{:>digits$} │ ", index + 1, digits = digits);
+                format!("{}{}", paint(&gutter, &Style::dim(), self.options), strip_controls(line))
+            })
+            .collect()
+    }
+
+    fn inline(&self, node: &Node, style: &Style, out: &mut Vec<Piece>) {
+        match node {
+            Node::Text { text, marks } => {
+                let mut style = style.clone();
+                let mut href = None;
+                for mark in marks.iter().flatten() {
+                    match mark {
+                        Mark::Strong => style.bold = true,
+                        Mark::Em => style.italic = true,
+                        Mark::Underline => style.underline = true,
+                        Mark::Strike => style.strike = true,
+                        Mark::Code => style.fg = style.fg.take().or_else(|| Some("36".to_string())),
+                        Mark::TextColor { attrs } => style.fg = Some(rgb(38, attrs.color)),
+                        Mark::BackgroundColor { attrs } => style.bg = Some(rgb(48, attrs.color)),
+                        Mark::Link { attrs } => href = Some(attrs.href.clone()),
+                        Mark::SubSup { .. } => {}
+                    }
+                }
+                self.push_linked(text, style, href, out);
+            }
+            Node::HardBreak { .. } => out.push(Piece { text: "\n".to_string(), style: style.clone() }),
+            Node::Mention { attrs } => {
+                let user = self.options.resolvers.user(attrs);
+                let text = resolve::mention_name(user.as_ref(), attrs);
+                let style = Style { bold: true, fg: Some("34".to_string()), ..style.clone() };
+                self.push_linked(&text, style, user.and_then(|user| user.profile_url), out);
+            }
+            Node::Emoji { attrs } => {
+                let fallback = &self.options.custom_emoji;
+                self.push_linked(&fallback.text(attrs), style.clone(), fallback.image_url(attrs), out)
+            }
+            Node::Date { attrs } => {
+                let text = match attrs.parsed_timestamp() {
+                    Ok(timestamp) => self.options.date_format.format(timestamp),
+                    Err(_) => attrs.timestamp.clone(),
+                };
+                out.push(Piece { text, style: style.clone() });
+            }
+            Node::Status { attrs } => {
+                let (background, text) = status_colors(&attrs.color);
+                // Non-breaking spaces keep the lozenge on one line.
+                let lozenge = format!("\u{a0}{}\u{a0}", attrs.text.to_uppercase().replace(' ', "\u{a0}"));
+                let style =
+                    Style { bold: true, fg: Some(rgb(38, text)), bg: Some(rgb(48, background)), ..Style::default() };
+                out.push(Piece { text: lozenge, style });
+            }
+            Node::InlineCard { attrs } => {
+                let url = attrs.url.clone().unwrap_or_default();
+                let text = self.options.resolvers.card(&url).map_or_else(|| url.clone(), |card| card.title);
+                let style = Style { fg: Some("34".to_string()), ..style.clone() };
+                self.push_linked(&text, style, Some(url).filter(|url| !url.is_empty()), out);
+            }
+            node => out.push(Piece { text: node.text_content(), style: style.clone() }),
+        }
+    }
+
+    fn push_linked(&self, text: &str, mut style: Style, href: Option<String>, out: &mut Vec<Piece>) {
+        let Some(href) = href.filter(|href| is_printable(href)) else {
+            out.push(Piece { text: text.to_string(), style });
+            return;
+        };
+        style.underline = true;
+        if self.options.hyperlinks {
+            style.link = Some(href);
+            out.push(Piece { text: text.to_string(), style });
+            return;
+        }
+        out.push(Piece { text: text.to_string(), style });
+        if href != text {
+            out.push(Piece { text: format!(" <{}>", href), style: Style::dim() });
+        }
+    }
+
+    fn table(&self, grid: &TableGrid, width: usize) -> Vec<String> {
+        let columns = grid.columns();
+        if columns == 0 {
+            return Vec::new();
+        }
+        // Natural width of each column: its widest line unwrapped, from
+        // cells that do not span columns.
+        let mut natural = vec![1; columns];
+        for cell in grid.cells().filter(|cell| cell.colspan == 1) {
+            let lines = self.blocks(cell.content(), usize::MAX, true);
+            let widest = lines.iter().map(|line| visible_width(line)).max().unwrap_or(0);
+            natural[cell.column] = natural[cell.column].max(widest);
+        }
+        let widths = fit_columns(&natural, width.saturating_sub(3 * columns + 1));
+
+        let header_rows = grid.header_rows();
+        let boundaries = |row: usize| -> Vec<bool> {
+            (0..columns).map(|column| grid.cell(row, column).is_some_and(|cell| cell.column == column)).collect()
+        };
+        let mut lines = vec![self.border(&widths, None, Some(&boundaries(0)), ('┌', '┐'))];
+        for row in 0..grid.rows() {
+            let cells = grid.row(row);
+            let mut rendered: Vec<(usize, Vec<String>)> = Vec::new();
+            for cell in &cells {
+                let spanned = &widths[cell.column..cell.column + cell.colspan];
+                let span_width = spanned.iter().sum::<usize>() + 3 * (cell.colspan - 1);
+                let content = if cell.row == row {
+                    let mut content = self.blocks(cell.content(), span_width, true);
+                    if cell.is_header() {
+                        let bold = |line: String| paint(&strip_ansi(&line), &Style::bold(), self.options);
+                        content = content.into_iter().map(bold).collect();
+                    }
+                    content
+                } else {
+                    Vec::new()
+                };
+                rendered.push((span_width, content));
+            }
+            let height = rendered.iter().map(|(_, content)| content.len()).max().unwrap_or(0).max(1);
+            for line in 0..height {
+                let mut out = String::from("│");
+                for (span_width, content) in &rendered {
+                    let text = content.get(line).map(String::as_str).unwrap_or_default();
+                    out.push(' ');
+                    out.push_str(text);
+                    out.push_str(&" ".repeat(span_width.saturating_sub(visible_width(text))));
+                    out.push_str(" │");
+                }
+                lines.push(out);
+            }
+            if row + 1 == header_rows && row + 1 < grid.rows() {
+                lines.push(self.border(&widths, Some(&boundaries(row)), Some(&boundaries(row + 1)), ('├', '┤')));
+            }
+        }
+        lines.push(self.border(&widths, Some(&boundaries(grid.rows() - 1)), None, ('└', '┘')));
+        lines
+    }
+
+    // A horizontal rule of the table, joining the column boundaries of the
+    // rows above and below.
+    fn border(&self, widths: &[usize], above: Option<&[bool]>, below: Option<&[bool]>, ends: (char, char)) -> String {
+        let mut out = String::new();
+        out.push(ends.0);
+        for (column, width) in widths.iter().enumerate() {
+            if column > 0 {
+                let up = above.is_some_and(|above| above[column]);
+                let down = below.is_some_and(|below| below[column]);
+                out.push(match (up, down) {
+                    (true, true) => '┼',
+                    (true, false) => '┴',
+                    (false, true) => '┬',
+                    (false, false) => '─',
+                });
+            }
+            out.push_str(&"─".repeat(width + 2));
+        }
+        out.push(ends.1);
+        out
+    }
+
+    fn wrap(&self, pieces: &[Piece], width: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line: Vec<Piece> = Vec::new();
+        let mut line_width = 0;
+        let mut space: Option<Piece> = None;
+        for token in tokens(pieces) {
+            match token {
+                Token::Break => {
+                    lines.push(self.line(&line));
+                    line.clear();
+                    line_width = 0;
+                    space = None;
+                }
+                Token::Space(piece) => space = Some(piece),
+                Token::Word(word) => {
+                    let word_width: usize = word.iter().map(|piece| piece.text.width()).sum();
+                    let space_width = if line.is_empty() { 0 } else { space.as_ref().map_or(0, |_| 1) };
+                    if !line.is_empty() && line_width + space_width + word_width > width {
+                        lines.push(self.line(&line));
+                        line.clear();
+                        line_width = 0;
+                    } else if let Some(space) = space.filter(|_| !line.is_empty()) {
+                        line.push(space);
+                        line_width += 1;
+                    }
+                    space = None;
+                    // Words wider than the line break wherever they reach the edge.
+                    for piece in word {
+                        for c in piece.text.chars() {
+                            let c_width = c.width().unwrap_or(0);
+                            if line_width + c_width > width && !line.is_empty() {
+                                lines.push(self.line(&line));
+                                line.clear();
+                                line_width = 0;
+                            }
+                            line_width += c_width;
+                            line.push(Piece { text: c.to_string(), style: piece.style.clone() });
+                        }
+                    }
+                }
+            }
+        }
+        if !line.is_empty() || lines.is_empty() {
+            lines.push(self.line(&line));
+        }
+        lines
+    }
+
+    fn line(&self, pieces: &[Piece]) -> String {
+        // Adjacent pieces in the same style share one escape sequence.
+        let mut merged: Vec<Piece> = Vec::new();
+        for piece in pieces {
+            match merged.last_mut() {
+                Some(last) if last.style == piece.style => last.text.push_str(&piece.text),
+                _ => merged.push(piece.clone()),
+            }
+        }
+        merged.iter().map(|piece| paint(&piece.text, &piece.style, self.options)).collect()
+    }
+}
+
+enum Token {
+    Word(Vec<Piece>),
+    Space(Piece),
+    Break,
+}
+
+// Words run across pieces, so `**bold**text` stays together when wrapping.
+fn tokens(pieces: &[Piece]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word: Vec<Piece> = Vec::new();
+    for piece in pieces {
+        for c in piece.text.chars() {
+            if c.is_control() && c != '\n' && c != '\t' {
+                continue;
+            }
+            if c == '\n' || c == ' ' || c == '\t' {
+                if !word.is_empty() {
+                    tokens.push(Token::Word(std::mem::take(&mut word)));
+                }
+                tokens.push(match c {
+                    '\n' => Token::Break,
+                    _ => Token::Space(Piece { text: " ".to_string(), style: piece.style.clone() }),
+                });
+                continue;
+            }
+            match word.last_mut() {
+                Some(last) if last.style == piece.style => last.text.push(c),
+                _ => word.push(Piece { text: c.to_string(), style: piece.style.clone() }),
+            }
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(Token::Word(word));
+    }
+    tokens
+}
+
+// Column widths shrunk to fit the space available. Columns narrower than
+// an even share keep their width and the rest is split among the others.
+fn fit_columns(natural: &[usize], available: usize) -> Vec<usize> {
+    if natural.iter().sum::<usize>() <= available {
+        return natural.to_vec();
+    }
+    let mut widths = vec![0; natural.len()];
+    let mut remaining = available;
+    let mut open: Vec<usize> = (0..natural.len()).collect();
+    loop {
+        let share = remaining / open.len().max(1);
+        let (fits, wide): (Vec<usize>, Vec<usize>) = open.iter().partition(|column| natural[**column] <= share);
+        if fits.is_empty() {
+            let extra = remaining - share * wide.len();
+            for (index, column) in wide.iter().enumerate() {
+                widths[*column] = (share + usize::from(index < extra)).max(1);
+            }
+            return widths;
+        }
+        for column in fits {
+            widths[column] = natural[column];
+            remaining -= natural[column];
+        }
+        open = wide;
+        if open.is_empty() {
+            return widths;
+        }
+    }
+}
+
+fn prefixed(first: &str, rest: &str, lines: Vec<String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let prefix = if index == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect()
+}
+
+fn paint(text: &str, style: &Style, options: &AnsiOptions) -> String {
+    let mut codes = Vec::new();
+    for (on, code) in
+        [(style.bold, "1"), (style.dim, "2"), (style.italic, "3"), (style.underline, "4"), (style.strike, "9")]
+    {
+        if on {
+            codes.push(code.to_string());
+        }
+    }
+    codes.extend(style.fg.clone());
+    codes.extend(style.bg.clone());
+    let mut out = if codes.is_empty() {
+        text.to_string()
+    } else {
+        format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+    };
+    if let Some(link) = style.link.as_ref().filter(|link| options.hyperlinks && is_printable(link)) {
+        out = format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", link, out);
+    }
+    out
+}
+
+fn rgb(base: u8, color: Color) -> String {
+    format!("{};2;{};{};{}", base, color.r, color.g, color.b)
+}
+
+fn panel_color(panel_type: &PanelType) -> &'static str {
+    match panel_type {
+        PanelType::Info => "34",
+        PanelType::Note => "35",
+        PanelType::Success => "32",
+        PanelType::Warning => "33",
+        PanelType::Error => "31",
+        PanelType::Custom | PanelType::Other(_) => "90",
+    }
+}
+
+fn status_colors(color: &StatusColor) -> (Color, Color) {
+    match color {
+        StatusColor::Purple => (Color::new(0xea, 0xe6, 0xff), Color::new(0x40, 0x32, 0x94)),
+        StatusColor::Blue => (Color::new(0xde, 0xeb, 0xff), Color::new(0x07, 0x47, 0xa6)),
+        StatusColor::Red => (Color::new(0xff, 0xeb, 0xe6), Color::new(0xbf, 0x26, 0x00)),
+        StatusColor::Yellow => (Color::new(0xff, 0xf0, 0xb3), Color::new(0x17, 0x2b, 0x4d)),
+        StatusColor::Green => (Color::new(0xe3, 0xfc, 0xef), Color::new(0x00, 0x66, 0x44)),
+        StatusColor::Neutral | StatusColor::Other(_) => (Color::new(0xdf, 0xe1, 0xe6), Color::new(0x42, 0x52, 0x6e)),
+    }
+}
+
+// Control characters in content could start escape sequences of their own.
+fn strip_controls(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control() || *c == '\t').collect()
+}
+
+fn is_printable(text: &str) -> bool {
+    !text.chars().any(char::is_control)
+}
+
+// Removes SGR and OSC 8 sequences.
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn visible_width(text: &str) -> usize {
+    strip_ansi(text).width()
+}
+
+#[cfg(test)]
+mod ansi_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+    use crate::model::{LinkAttrs, PanelAttrs, StatusAttrs};
+
+    fn plain(width: usize) -> AnsiOptions {
+        AnsiOptions { width, hyperlinks: false, ..AnsiOptions::default() }
+    }
+
+    #[test]
+    fn should_style_marks_links_and_status() {
+        let link = Mark::Link { attrs: LinkAttrs::new("https://example.com") };
+        let doc = Node::doc(vec![Node::paragraph(vec![
+            Node::text_with_marks("Bold", vec![Mark::Strong, Mark::Em]),
+            Node::text(" "),
+            Node::text_with_marks("site", vec![link]),
+            Node::text(" "),
+            Node::Status { attrs: StatusAttrs::new("in progress", StatusColor::Blue) },
+        ])]);
+
+        assert_eq!(
+            to_ansi(&doc, &AnsiOptions::default()),
+            concat!(
+                "\x1b[1;3mBold\x1b[0m ",
+                "\x1b]8;;https://example.com\x1b\\\x1b[4msite\x1b[0m\x1b]8;;\x1b\\ ",
+                "\x1b[1;38;2;7;71;166;48;2;222;235;255m\u{a0}IN\u{a0}PROGRESS\u{a0}\x1b[0m\n",
+            )
+        );
+        assert_eq!(
+            strip_ansi(&to_ansi(&doc, &plain(80))),
+            "Bold site <https://example.com> \u{a0}IN\u{a0}PROGRESS\u{a0}\n"
+        );
+    }
+
+    #[test]
+    fn should_wrap_lists_quotes_and_code() {
+        let doc = Node::doc(vec![
+            Node::bullet_list(vec![Node::list_item(vec![
+                paragraph("one two three four"),
+                Node::ordered_list(vec![Node::list_item(vec![paragraph("five six")])]),
+            ])]),
+            Node::Blockquote { content: vec![paragraph("quoted words here")] },
+            Node::code_block(Some("rust"), "fn main() {}\n"),
+            Node::Panel { content: vec![paragraph("Careful")], attrs: PanelAttrs::new(PanelType::Warning) },
+        ]);
+
+        assert_eq!(
+            strip_ansi(&to_ansi(&doc, &plain(12))),
+            concat!(
+                "• one two\n",
+                "  three four\n",
+                "  1. five\n",
+                "     six\n",
+                "\n",
+                "│ quoted\n",
+                "│ words here\n",
+                "\n",
+                "1 │ fn main() {}\n",
+                "\n",
+                "┃ WARNING\n",
+                "┃ Careful\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_draw_tables_fitting_columns() {
+        let cell = |text: &str| Node::table_cell(vec![paragraph(text)]);
+        let doc = Node::table(vec![
+            Node::table_row(vec![
+                Node::table_header(vec![paragraph("Key")]),
+                Node::table_header(vec![paragraph("Summary")]),
+            ]),
+            Node::table_row(vec![cell("AB-1"), cell("Fix the login redirect loop")]),
+        ]);
+
+        assert_eq!(
+            strip_ansi(&to_ansi(&doc, &plain(24))),
+            concat!(
+                "┌──────┬───────────────┐\n",
+                "│ Key  │ Summary       │\n",
+                "├──────┼───────────────┤\n",
+                "│ AB-1 │ Fix the login │\n",
+                "│      │ redirect loop │\n",
+                "└──────┴───────────────┘\n",
+            )
+        );
+        assert_eq!(fit_columns(&[4, 30, 30], 20), vec![4, 8, 8]);
+    }
+
+    #[test]
+    fn should_strip_control_characters_from_text_code_and_links() {
+        let link = Mark::Link { attrs: LinkAttrs::new("https://example.com\x1b\\\x1b]8;;https://evil.test") };
+        let doc = Node::doc(vec![
+            Node::paragraph(vec![
+                Node::text("title\x1b]0;pwned\x07\x1b[2J\u{9b}31m"),
+                Node::text(" "),
+                Node::text_with_marks("site", vec![link]),
+            ]),
+            Node::code_block(None, "let a = 1;\x1b[2J\n"),
+        ]);
+        let options = AnsiOptions { width: 80, hyperlinks: true, ..AnsiOptions::default() };
+
+        let out = to_ansi(&doc, &options);
+        assert!(!out.contains("\x1b]0") && !out.contains("\x1b[2J"));
+        assert!(!out.contains('\x07') && !out.contains('\u{9b}'));
+        assert!(!out.contains("evil"));
+        assert_eq!(strip_ansi(&out), "title]0;pwned[2J31m site\n\n1 │ let a = 1;[2J\n");
+    }
+}
@@ -0,0 +1,491 @@
+use crate::date::DateFormat;
+use crate::emoji::CustomEmojiFallback;
+use crate::model::{Mark, Node, PanelType, SubSupAttrs, TaskState};
+use crate::resolve::{self, Resolvers};
+use crate::table::TableGrid;
+
+#[derive(Debug, Clone, Default)]
+pub struct AsciidocOptions {
+    /// Format and timezone used to display `date` nodes.
+    pub date_format: DateFormat,
+    /// How custom emoji appear; an image fallback becomes an inline `image:`
+    /// macro.
+    pub custom_emoji: CustomEmojiFallback,
+    /// Looks up user names, media URLs and link titles.
+    pub resolvers: Resolvers,
+}
+
+/// AsciiDoc for a node.
+///
+/// A heading of level N gets N + 1 `=` signs, leaving the single `=` of the
+/// document title to the caller. Panels become admonition blocks (`NOTE`,
+/// `TIP`, `WARNING` or `CAUTION`), expands collapsible example blocks, and
+/// code blocks `source` listings. Table cells keep their spans, and cells
+/// holding more than a paragraph are written as AsciiDoc cells (`a|`).
+pub fn to_asciidoc(node: &Node, options: &AsciidocOptions) -> String {
+    let mut out = Writer { options }.block(node);
+    out.push('\n');
+    out
+}
+
+struct Writer<'a> {
+    options: &'a AsciidocOptions,
+}
+
+impl Writer<'_> {
+    fn blocks(&self, content: &[Node]) -> String {
+        content.iter().map(|node| self.block(node)).filter(|text| !text.is_empty()).collect::<Vec<_>>().join("\n\n")
+    }
+
+    fn block(&self, node: &Node) -> String {
+        match node {
+            Node::Doc { content, .. } | Node::ListItem { content } => self.blocks(content),
+            Node::Paragraph { content, .. } => guard_line_start(self.inline(content)),
+            Node::Heading { content, attrs } => {
+                format!("{} {}", "=".repeat(attrs.level().clamp(1, 5) as usize + 1), self.inline(content))
+            }
+            Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } | Node::DecisionList { .. } => {
+                self.list(node, 1).join("\n")
+            }
+            Node::Blockquote { content } => delimited('_', &self.blocks(content)),
+            Node::CodeBlock { content, attrs } => {
+                let code: String = content.iter().flatten().map(Node::text_content).collect();
+                let language = attrs.as_ref().map(|attrs| attrs.language()).filter(|language| !language.is_empty());
+                match language {
+                    Some(language) => format!("[source,{}]\n{}", language, delimited('-', &code)),
+                    None => delimited('-', &code),
+                }
+            }
+            Node::Panel { content, attrs } => {
+                let label = match attrs.panel_type() {
+                    PanelType::Success => "TIP",
+                    PanelType::Warning => "WARNING",
+                    PanelType::Error => "CAUTION",
+                    _ => "NOTE",
+                };
+                format!("[{}]\n{}", label, delimited('=', &self.blocks(content)))
+            }
+            Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+                let title = match attrs.title().trim() {
+                    "" => String::new(),
+                    title => format!(".{}\n", escape(title)),
+                };
+                format!("{}[%collapsible]\n{}", title, delimited('=', &self.blocks(content)))
+            }
+            Node::Rule => "'''".to_string(),
+            Node::Table { .. } => self.table(node),
+            Node::MediaSingle { content, .. } | Node::MediaGroup { content } => self.blocks(content),
+            Node::Media { attrs } => match self.options.resolvers.media(attrs) {
+                Some(info) => {
+                    let mut macro_attrs = vec![info.alt.unwrap_or_default().replace([',', ']'], " ")];
+                    if let (Some(width), Some(height)) = (info.width, info.height) {
+                        macro_attrs.extend([width.to_string(), height.to_string()]);
+                    }
+                    format!("image::{}[{}]", escape_url(&info.url), macro_attrs.join(","))
+                }
+                None => format!("image::{}[]", attrs.id()),
+            },
+            inline_node => self.inline(std::slice::from_ref(inline_node)),
+        }
+    }
+
+    fn list(&self, list: &Node, depth: usize) -> Vec<String> {
+        let marker = match list {
+            Node::OrderedList { .. } => ".".repeat(depth),
+            _ => "*".repeat(depth),
+        };
+        let mut lines = Vec::new();
+        // A list that does not count from 1 says where it starts.
+        if let Node::OrderedList { attrs: Some(attrs), .. } = list {
+            if attrs.order() != 1 {
+                lines.push(format!("[start={}]", attrs.order()));
+            }
+        }
+        for item in list.content().into_iter().flatten() {
+            match item {
+                Node::TaskItem { content, attrs } => {
+                    let check = if *attrs.state() == TaskState::Done { "[x]" } else { "[ ]" };
+                    lines.push(format!("{} {} {}", marker, check, self.inline(content)));
+                }
+                Node::DecisionItem { content, .. } => lines.push(format!("{} {}", marker, self.inline(content))),
+                Node::TaskList { .. } => lines.extend(self.list(item, depth + 1)),
+                _ => {
+                    let mut first = true;
+                    for child in item.content().into_iter().flatten() {
+                        match child {
+                            Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } => {
+                                lines.extend(self.list(child, depth + 1))
+                            }
+                            Node::Paragraph { content, .. } if first => {
+                                lines.push(format!("{} {}", marker, self.inline(content)))
+                            }
+                            // Further blocks are attached to the item with a list continuation.
+                            other => {
+                                if first {
+                                    lines.push(format!("{} {{empty}}", marker));
+                                }
+                                lines.push("+".to_string());
+                                lines.push(self.block(other));
+                            }
+                        }
+                        first = false;
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    fn table(&self, table: &Node) -> String {
+        let Ok(grid) = TableGrid::new(table) else {
+            return self.blocks(table.content().map(Vec::as_slice).unwrap_or_default());
+        };
+        let header = grid.header_rows() > 0 && grid.header_rows() < grid.rows();
+        let mut out = format!("[{}cols=\"{}*\"]\n|===", if header { "%header," } else { "" }, grid.columns().max(1));
+        for row in 0..grid.rows() {
+            out.push('\n');
+            let cells: Vec<String> = grid
+                .row(row)
+                .into_iter()
+                .filter(|cell| cell.row == row)
+                .map(|cell| {
+                    let mut spec = String::new();
+                    if cell.colspan > 1 {
+                        spec.push_str(&cell.colspan.to_string());
+                    }
+                    if cell.rowspan > 1 {
+                        spec.push_str(&format!(".{}", cell.rowspan));
+                    }
+                    if !spec.is_empty() {
+                        spec.push('+');
+                    }
+                    let content = cell.content();
+                    match content {
+                        [] => format!("{}|", spec),
+                        [Node::Paragraph { content, .. }] => {
+                            if cell.is_header() && !(header && row < grid.header_rows()) {
+                                spec.push('h');
+                            }
+                            format!("{}|{}", spec, self.inline(content).replace('|', "\\|"))
+                        }
+                        blocks => format!("{}a|{}", spec, self.blocks(blocks).replace('|', "\\|")),
+                    }
+                })
+                .collect();
+            out.push_str(&cells.join(" "));
+            if header && row + 1 == grid.header_rows() {
+                out.push('\n');
+            }
+        }
+        out.push_str("\n|===");
+        out
+    }
+
+    fn inline(&self, content: &[Node]) -> String {
+        let mut out = String::new();
+        for node in content {
+            match node {
+                Node::Text { text, marks } => {
+                    out.push_str(&text_with_marks(text, marks.as_deref().unwrap_or_default()))
+                }
+                Node::HardBreak { .. } => out.push_str(" +\n"),
+                Node::Mention { attrs } => {
+                    let user = self.options.resolvers.user(attrs);
+                    let name = resolve::mention_name(user.as_ref(), attrs);
+                    match user.and_then(|user| user.profile_url) {
+                        Some(url) => {
+                            out.push_str(&format!("link:{}[{}]", escape_url(&url), escape_link_text(&name)))
+                        }
+                        None => out.push_str(&escape(&name)),
+                    }
+                }
+                Node::Emoji { attrs } => {
+                    let fallback = &self.options.custom_emoji;
+                    let text = fallback.text(attrs);
+                    match fallback.image_url(attrs) {
+                        Some(url) => out.push_str(&format!("image:{}[{}]", escape_url(&url), escape_link_text(&text))),
+                        None => out.push_str(&escape(&text)),
+                    }
+                }
+                Node::Date { attrs } => match attrs.parsed_timestamp() {
+                    Ok(timestamp) => out.push_str(&escape(&self.options.date_format.format(timestamp))),
+                    Err(_) => out.push_str(&escape(attrs.timestamp())),
+                },
+                Node::Status { attrs } => out.push_str(&format!("`+{}+`", attrs.text().to_uppercase())),
+                Node::InlineCard { attrs } => {
+                    let url = attrs.url().unwrap_or_default();
+                    let href = escape_url(url);
+                    match self.options.resolvers.card(url) {
+                        Some(card) => out.push_str(&format!("link:{}[{}]", href, escape_link_text(&card.title))),
+                        None => out.push_str(&format!("link:{}[]", href)),
+                    }
+                }
+                other => out.push_str(&escape(&other.text_content())),
+            }
+        }
+        out
+    }
+}
+
+// Unconstrained (doubled) delimiters are used throughout so that marks also
+// apply inside words.
+fn text_with_marks(text: &str, marks: &[Mark]) -> String {
+    let mut out = if marks.contains(&Mark::Code) { format!("`+{}+`", text) } else { escape(text) };
+    for mark in marks {
+        out = match mark {
+            Mark::SubSup { attrs: SubSupAttrs::Sub } => format!("~{}~", out),
+            Mark::SubSup { attrs: SubSupAttrs::Sup } => format!("^{}^", out),
+            _ => continue,
+        };
+    }
+    for (mark, open, close) in [
+        (Mark::Strike, "[.line-through]##", "##"),
+        (Mark::Underline, "[.underline]##", "##"),
+        (Mark::Em, "__", "__"),
+        (Mark::Strong, "**", "**"),
+    ] {
+        if marks.contains(&mark) {
+            out = format!("{}{}{}", open, out, close);
+        }
+    }
+    for mark in marks {
+        if let Mark::Link { attrs } = mark {
+            out = format!("link:{}[{}]", escape_url(attrs.href()), out.replace(']', "\\]"));
+        }
+    }
+    out
+}
+
+// Characters that would end the target of a link or image macro.
+fn escape_url(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('[', "%5B")
+        .replace(']', "%5D")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+        .replace('`', "%60")
+}
+
+// Asciidoctor keeps a backslash that does not precede markup, so characters
+// that could open markup are written as the built-in attributes naming them.
+// `_` and `#` only do so at the edge of a word, and are left alone inside one.
+fn escape(text: &str) -> String {
+    let word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    let mut out = String::with_capacity(text.len());
+    for (at, c) in text.char_indices() {
+        match c {
+            '*' => out.push_str("{asterisk}"),
+            '`' => out.push_str("{backtick}"),
+            '^' => out.push_str("{caret}"),
+            '~' => out.push_str("{tilde}"),
+            '+' => out.push_str("{plus}"),
+            '[' => out.push_str("{startsb}"),
+            ']' => out.push_str("{endsb}"),
+            '_' | '#' if !(word(text[..at].chars().next_back()) && word(text[at + 1..].chars().next())) => {
+                out.push_str(&format!("pass:[{}]", c))
+            }
+            // Only a brace opening an attribute reference needs escaping.
+            '{' if attribute_reference(&text[at + 1..]) => out.push_str("\\{"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn attribute_reference(text: &str) -> bool {
+    text.split_once('}')
+        .is_some_and(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-'))
+}
+
+fn escape_link_text(text: &str) -> String {
+    escape(text).replace("{endsb}", "\\]")
+}
+
+// A block between delimiter lines. The delimiter is longer than any line of
+// the same character inside, so a nested block or a line of code cannot
+// close it early.
+fn delimited(c: char, body: &str) -> String {
+    let inner = body
+        .lines()
+        .filter(|line| !line.is_empty() && line.chars().all(|l| l == c))
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let delimiter = c.to_string().repeat(inner.max(3) + 1);
+    format!("{}\n{}\n{}", delimiter, body, delimiter)
+}
+
+// A paragraph starting like a list item, heading, block title, admonition,
+// comment, break or block delimiter would be read as one.
+fn guard_line_start(text: String) -> String {
+    let list_marker = text.split_once(' ').is_some_and(|(first, _)| {
+        first.chars().all(|c| c == '=' || c == '-' || c == '.')
+            || first.strip_suffix('.').is_some_and(|number| number.chars().all(|c| c.is_ascii_digit()))
+    });
+    let line = text.lines().next().unwrap_or_default();
+    let delimiter = line.len() >= 2
+        && line.starts_with(['-', '=', '_', '*', '+', '/', '\'', '<'])
+        && line.chars().all(|c| line.starts_with(c));
+    let admonition = ["NOTE:", "TIP:", "IMPORTANT:", "WARNING:", "CAUTION:"]
+        .iter()
+        .any(|label| line.strip_prefix(label).is_some_and(|rest| rest.starts_with(' ')));
+    if list_marker
+        || delimiter
+        || admonition
+        || text.starts_with('.')
+        || text.starts_with(':')
+        || text.starts_with("//")
+        || text.starts_with("> ")
+    {
+        format!("{{empty}}{}", text)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod asciidoc_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+    use crate::model::{EmojiAttrs, ExpandAttrs, InlineCardAttrs, LinkAttrs, PanelAttrs, TableCellAttrs};
+
+    #[test]
+    fn should_render_blocks_and_marks() {
+        let doc = Node::doc(vec![
+            Node::heading(1, vec![Node::text("Design")]),
+            Node::paragraph(vec![
+                Node::text("Use "),
+                Node::text_with_marks("snake_case", vec![Mark::Code]),
+                Node::text(" and "),
+                Node::text_with_marks("bold", vec![
+                    Mark::Strong,
+                    Mark::Link { attrs: LinkAttrs::new("https://a.example") },
+                ]),
+                Node::text(" * stars_ {name}"),
+            ]),
+            Node::paragraph(vec![Node::text("- not a list")]),
+            Node::ordered_list(vec![Node::list_item(vec![
+                paragraph("first"),
+                Node::code_block(Some("rust"), "let x = 1;"),
+                Node::bullet_list(vec![Node::list_item(vec![paragraph("inner")])]),
+            ])]),
+            Node::Panel { content: vec![paragraph("Careful")], attrs: PanelAttrs::new(PanelType::Error) },
+        ]);
+
+        assert_eq!(
+            to_asciidoc(&doc, &AsciidocOptions::default()),
+            concat!(
+                "== Design\n\n",
+                "Use `+snake_case+` and link:https://a.example[**bold**] {asterisk} starspass:[_] \\{name}\n\n",
+                "{empty}- not a list\n\n",
+                ". first\n+\n[source,rust]\n----\nlet x = 1;\n----\n** inner\n\n",
+                "[CAUTION]\n====\nCareful\n====\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_lengthen_delimiters_of_nested_blocks() {
+        let doc = Node::Panel {
+            content: vec![Node::Expand {
+                content: vec![
+                    Node::Blockquote { content: vec![Node::Blockquote { content: vec![paragraph("deep")] }] },
+                    Node::code_block(None, "----"),
+                ],
+                attrs: ExpandAttrs::new("Details"),
+                marks: None,
+            }],
+            attrs: PanelAttrs::new(PanelType::Info),
+        };
+
+        assert_eq!(
+            to_asciidoc(&doc, &AsciidocOptions::default()),
+            concat!(
+                "[NOTE]\n=====\n",
+                ".Details\n[%collapsible]\n====\n",
+                "_____\n____\ndeep\n____\n_____\n\n",
+                "-----\n----\n-----\n",
+                "====\n=====\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_guard_paragraphs_read_as_markup() {
+        let texts = ["----", "====", "____", "****", "'''", "// not a comment", "NOTE: not an admonition", "TIP:x"];
+        let doc = Node::doc(texts.iter().map(|text| paragraph(text)).collect());
+
+        assert_eq!(
+            to_asciidoc(&doc, &AsciidocOptions::default()),
+            concat!(
+                "{empty}----\n\n",
+                "{empty}====\n\n",
+                "pass:[_]pass:[_]pass:[_]pass:[_]\n\n",
+                "{asterisk}{asterisk}{asterisk}{asterisk}\n\n",
+                "{empty}'''\n\n",
+                "{empty}// not a comment\n\n",
+                "{empty}NOTE: not an admonition\n\n",
+                "TIP:x\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_encode_characters_ending_link_targets() {
+        let link = Mark::Link { attrs: LinkAttrs::new("https://a.example/x[1] y`>") };
+        let doc = Node::paragraph(vec![
+            Node::text_with_marks("odd", vec![link]),
+            Node::text(" "),
+            Node::InlineCard { attrs: InlineCardAttrs::from_url("https://b.example/[z]") },
+        ]);
+
+        assert_eq!(
+            to_asciidoc(&doc, &AsciidocOptions::default()),
+            "link:https://a.example/x%5B1%5D%20y%60%3E[odd] link:https://b.example/%5Bz%5D[]\n"
+        );
+    }
+
+    #[test]
+    fn should_show_custom_emoji_as_configured() {
+        let doc = Node::paragraph(vec![Node::Emoji {
+            attrs: EmojiAttrs::new(":parrot:").with_id("atlassian-parrot"),
+        }]);
+        let options = AsciidocOptions {
+            custom_emoji: CustomEmojiFallback::Image { url_template: "https://e.example/{id}.png".to_string() },
+            ..AsciidocOptions::default()
+        };
+
+        assert_eq!(to_asciidoc(&doc, &AsciidocOptions::default()), "{empty}:parrot:\n");
+        assert_eq!(to_asciidoc(&doc, &options), "image:https://e.example/atlassian-parrot.png[:parrot:]\n");
+    }
+
+    #[test]
+    fn should_render_tables_with_spans() {
+        let table = Node::table(vec![
+            Node::table_row(vec![
+                Node::table_header(vec![paragraph("A")]),
+                Node::table_header(vec![paragraph("B")]),
+                Node::table_header(vec![paragraph("C")]),
+            ]),
+            Node::table_row(vec![
+                Node::TableCell {
+                    content: vec![paragraph("wide | tall")],
+                    attrs: Some(TableCellAttrs::default().with_colspan(2).with_rowspan(2)),
+                },
+                Node::table_cell(vec![paragraph("c1")]),
+            ]),
+            Node::table_row(vec![Node::table_cell(vec![paragraph("c2"), paragraph("more")])]),
+        ]);
+
+        assert_eq!(
+            to_asciidoc(&table, &AsciidocOptions::default()),
+            concat!(
+                "[%header,cols=\"3*\"]\n|===\n",
+                "|A |B |C\n\n",
+                "2.2+|wide \\| tall |c1\n",
+                "a|c2\n\nmore\n",
+                "|===\n",
+            )
+        );
+    }
+}
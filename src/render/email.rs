@@ -0,0 +1,468 @@
+use crate::model::{
+    CodeBlockAttrs, DateAttrs, DecisionItemAttrs, DecisionListAttrs, ExpandAttrs, Mark, MentionAttrs, Node,
+    PanelAttrs, PanelType, StatusAttrs, StatusColor, TableAttrs, TableCellAttrs, TaskItemAttrs, TaskListAttrs,
+    TaskState,
+};
+use crate::render::html::{self, push_attr, push_escaped, HtmlOptions, HtmlRenderer};
+use crate::url::safe_url;
+
+const FONT: &str = "font-family: -apple-system, 'Segoe UI', Roboto, Helvetica, Arial, sans-serif; \
+                    font-size: 14px; line-height: 1.5; color: #172b4d";
+const MONOSPACE: &str = "font-family: SFMono-Regular, Consolas, Menlo, monospace";
+
+/// Both parts of a notification email.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub html: String,
+    /// Plain-text alternative for clients that do not show HTML.
+    pub text: String,
+}
+
+pub fn to_email(node: &Node, options: &HtmlOptions) -> Email {
+    Email { html: to_email_html(node, options), text: to_plain_text(node, options) }
+}
+
+/// HTML for email clients, which strip `<style>` blocks and classes and
+/// have poor CSS support: all styling is inline, boxes are single-cell
+/// tables and expands are shown open.
+pub fn to_email_html(node: &Node, options: &HtmlOptions) -> String {
+    let mut out = String::new();
+    out.push_str("<div");
+    push_attr(&mut out, "style", FONT);
+    out.push('>');
+    EmailRenderer(options).render_node(node, &mut out);
+    out.push_str("</div>");
+    out
+}
+
+/// Readable plain text: blank lines between blocks, `-` and `1.` list
+/// markers, links written out after their text.
+pub fn to_plain_text(node: &Node, options: &HtmlOptions) -> String {
+    let mut out = String::new();
+    write_block(node, options, &mut out);
+    out.trim_end().to_string()
+}
+
+struct EmailRenderer<'a>(&'a HtmlOptions);
+
+impl HtmlRenderer for EmailRenderer<'_> {
+    fn options(&self) -> &HtmlOptions {
+        self.0
+    }
+
+    fn render_code_block(&self, content: &[Node], _: Option<&CodeBlockAttrs>, out: &mut String) {
+        out.push_str("<pre");
+        let style = format!(
+            "{}; font-size: 12px; background-color: #f4f5f7; padding: 8px 12px; border-radius: 3px; \
+             white-space: pre-wrap; margin: 8px 0",
+            MONOSPACE
+        );
+        push_attr(out, "style", &style);
+        out.push('>');
+        for node in content {
+            if let Node::Text { text, .. } = node {
+                push_escaped(out, text);
+            }
+        }
+        out.push_str("</pre>");
+    }
+
+    fn render_date(&self, attrs: &DateAttrs, out: &mut String) {
+        out.push_str(r#"<span style="background-color: #ebecf0; border-radius: 3px; padding: 0 4px">"#);
+        push_escaped(out, &format_date(attrs, self.0));
+        out.push_str("</span>");
+    }
+
+    fn render_decision_list(&self, content: &[Node], _: &DecisionListAttrs, out: &mut String) {
+        render_plain_list(self, content, out)
+    }
+
+    fn render_decision_item(&self, content: &[Node], _: &DecisionItemAttrs, out: &mut String) {
+        out.push_str(r#"<li style="margin: 4px 0"><span style="color: #00875a">&#9670;</span> "#);
+        self.render_children(content, out);
+        out.push_str("</li>");
+    }
+
+    fn render_expand(&self, content: &[Node], attrs: &ExpandAttrs, out: &mut String) {
+        open_box(out, "border: 1px solid #dfe1e6; padding: 8px 12px");
+        out.push_str(r#"<div style="font-weight: bold; margin-bottom: 4px">"#);
+        push_escaped(out, &attrs.title);
+        out.push_str("</div>");
+        self.render_children(content, out);
+        close_box(out);
+    }
+
+    fn render_mention(&self, attrs: &MentionAttrs, out: &mut String) {
+        let user = self.0.resolvers.user(attrs);
+        let profile_url = user.as_ref().and_then(|user| user.profile_url.as_deref()).and_then(safe_url);
+        let tag = if profile_url.is_some() { "a" } else { "span" };
+        out.push_str(&format!("<{}", tag));
+        if let Some(profile_url) = profile_url {
+            push_attr(out, "href", profile_url);
+        }
+        let style = "background-color: #ebecf0; border-radius: 20px; padding: 0 4px; color: #42526e; \
+                     text-decoration: none";
+        push_attr(out, "style", style);
+        out.push('>');
+        push_escaped(out, &self.0.resolvers.mention_name(attrs));
+        out.push_str(&format!("</{}>", tag));
+    }
+
+    fn render_panel(&self, content: &[Node], attrs: &PanelAttrs, out: &mut String) {
+        let (background, border) = panel_colors(&attrs.panel_type);
+        let style = format!("background-color: {}; border-left: 4px solid {}; padding: 8px 12px", background, border);
+        open_box(out, &style);
+        self.render_children(content, out);
+        close_box(out);
+    }
+
+    fn render_status(&self, attrs: &StatusAttrs, out: &mut String) {
+        let (background, text) = status_colors(&attrs.color);
+        let style = format!(
+            "display: inline-block; background-color: {}; color: {}; border-radius: 3px; padding: 0 4px; \
+             font-size: 11px; font-weight: bold; text-transform: uppercase",
+            background, text
+        );
+        out.push_str("<span");
+        push_attr(out, "style", &style);
+        out.push('>');
+        push_escaped(out, &attrs.text);
+        out.push_str("</span>");
+    }
+
+    fn render_table(&self, content: &[Node], _: Option<&TableAttrs>, out: &mut String) {
+        out.push_str(r#"<table cellpadding="0" cellspacing="0" style="border-collapse: collapse; margin: 8px 0">"#);
+        self.render_children(content, out);
+        out.push_str("</table>");
+    }
+
+    fn render_table_cell(&self, content: &[Node], attrs: Option<&TableCellAttrs>, out: &mut String) {
+        render_cell(self, "td", content, attrs, out)
+    }
+
+    fn render_table_header(&self, content: &[Node], attrs: Option<&TableCellAttrs>, out: &mut String) {
+        render_cell(self, "th", content, attrs, out)
+    }
+
+    fn render_task_list(&self, content: &[Node], _: &TaskListAttrs, out: &mut String) {
+        render_plain_list(self, content, out)
+    }
+
+    fn render_task_item(&self, content: &[Node], attrs: &TaskItemAttrs, out: &mut String) {
+        out.push_str(r#"<li style="margin: 4px 0">"#);
+        out.push_str(if attrs.state == TaskState::Done { "&#9745; " } else { "&#9744; " });
+        self.render_children(content, out);
+        out.push_str("</li>");
+    }
+
+    fn open_mark(&self, mark: &Mark, out: &mut String) {
+        match mark {
+            Mark::Code => {
+                out.push_str("<code");
+                let style = format!("{}; font-size: 12px; background-color: #f4f5f7; padding: 0 2px", MONOSPACE);
+                push_attr(out, "style", &style);
+                out.push('>');
+            }
+            _ => html::open_mark(self, mark, out),
+        }
+    }
+}
+
+// A single-cell table, the box most email clients lay out reliably.
+fn open_box(out: &mut String, style: &str) {
+    out.push_str(r#"<table role="presentation" width="100%" cellpadding="0" cellspacing="0""#);
+    out.push_str(r#" style="border-collapse: collapse; margin: 8px 0"><tr><td"#);
+    push_attr(out, "style", style);
+    out.push('>');
+}
+
+fn close_box(out: &mut String) {
+    out.push_str("</td></tr></table>");
+}
+
+fn render_plain_list(r: &EmailRenderer, content: &[Node], out: &mut String) {
+    out.push_str(r#"<ul style="list-style: none; padding-left: 0; margin: 8px 0">"#);
+    r.render_children(content, out);
+    out.push_str("</ul>");
+}
+
+fn render_cell(r: &EmailRenderer, tag: &str, content: &[Node], attrs: Option<&TableCellAttrs>, out: &mut String) {
+    out.push_str(&format!("<{}", tag));
+    let mut style = "border: 1px solid #c1c7d0; padding: 4px 8px; vertical-align: top; text-align: left".to_string();
+    if tag == "th" {
+        style.push_str("; background-color: #f4f5f7");
+    }
+    if let Some(attrs) = attrs {
+        if let Some(colspan) = attrs.colspan.filter(|span| *span > 1) {
+            push_attr(out, "colspan", &colspan.to_string());
+        }
+        if let Some(rowspan) = attrs.rowspan.filter(|span| *span > 1) {
+            push_attr(out, "rowspan", &rowspan.to_string());
+        }
+        if let Some(background) = &attrs.background {
+            let background = if r.0.dark_mode { background.to_dark_mode() } else { *background };
+            style.push_str(&format!("; background-color: {}", background));
+        }
+    }
+    push_attr(out, "style", &style);
+    out.push('>');
+    r.render_children(content, out);
+    out.push_str(&format!("</{}>", tag));
+}
+
+fn panel_colors(panel_type: &PanelType) -> (&'static str, &'static str) {
+    match panel_type {
+        PanelType::Info => ("#deebff", "#0052cc"),
+        PanelType::Note => ("#eae6ff", "#5243aa"),
+        PanelType::Success => ("#e3fcef", "#00875a"),
+        PanelType::Warning => ("#fffae6", "#ff8b00"),
+        PanelType::Error => ("#ffebe6", "#de350b"),
+        PanelType::Custom | PanelType::Other(_) => ("#f4f5f7", "#6b778c"),
+    }
+}
+
+fn status_colors(color: &StatusColor) -> (&'static str, &'static str) {
+    match color {
+        StatusColor::Purple => ("#eae6ff", "#403294"),
+        StatusColor::Blue => ("#deebff", "#0747a6"),
+        StatusColor::Red => ("#ffebe6", "#bf2600"),
+        StatusColor::Yellow => ("#fff0b3", "#172b4d"),
+        StatusColor::Green => ("#e3fcef", "#006644"),
+        StatusColor::Neutral | StatusColor::Other(_) => ("#dfe1e6", "#42526e"),
+    }
+}
+
+fn format_date(attrs: &DateAttrs, options: &HtmlOptions) -> String {
+    match attrs.parsed_timestamp() {
+        Ok(timestamp) => options.date_format.format(timestamp),
+        Err(_) => attrs.timestamp.clone(),
+    }
+}
+
+// Appends the block followed by a blank line.
+fn write_block(node: &Node, options: &HtmlOptions, out: &mut String) {
+    let text = block_text(node, options);
+    if !text.is_empty() {
+        out.push_str(&text);
+        out.push_str("\n\n");
+    }
+}
+
+fn blocks_text(content: &[Node], options: &HtmlOptions) -> String {
+    let mut out = String::new();
+    for node in content {
+        write_block(node, options, &mut out);
+    }
+    out.trim_end().to_string()
+}
+
+fn block_text(node: &Node, options: &HtmlOptions) -> String {
+    match node {
+        Node::Doc { content, .. } | Node::MediaSingle { content, .. } => blocks_text(content, options),
+        // Tight lists: a nested list follows its item's text directly.
+        Node::ListItem { content } => {
+            content.iter().map(|block| block_text(block, options)).collect::<Vec<_>>().join("\n")
+        }
+        Node::Paragraph { content, .. } => inline_text(content, options),
+        Node::Heading { content, attrs } => {
+            let text = inline_text(content, options);
+            let underline = if attrs.level <= 1 { "=" } else { "-" };
+            format!("{}\n{}", text, underline.repeat(text.chars().count()))
+        }
+        Node::BulletList { content } => list_text(content, options, |_| "- ".to_string()),
+        Node::OrderedList { content, attrs } => {
+            let start = attrs.as_ref().map_or(1, |attrs| attrs.order as usize);
+            list_text(content, options, |index| format!("{}. ", start + index))
+        }
+        Node::TaskList { content, .. } | Node::DecisionList { content, .. } => {
+            content.iter().map(|item| block_text(item, options)).collect::<Vec<_>>().join("\n")
+        }
+        Node::TaskItem { content, attrs } => {
+            let marker = if attrs.state == TaskState::Done { "[x] " } else { "[ ] " };
+            indent(&format!("{}{}", marker, inline_text(content, options)), "    ")
+        }
+        Node::DecisionItem { content, .. } => indent(&format!("<> {}", inline_text(content, options)), "   "),
+        Node::Blockquote { content } => prefix_lines(&blocks_text(content, options), "> "),
+        Node::Panel { content, attrs } => {
+            let label = attrs.panel_type.as_str().to_uppercase();
+            format!("[{}]\n{}", label, blocks_text(content, options))
+        }
+        Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+            format!("{}\n{}", attrs.title, blocks_text(content, options))
+        }
+        Node::CodeBlock { content, .. } => {
+            let code: String = content
+                .iter()
+                .flatten()
+                .filter_map(|node| match node {
+                    Node::Text { text, .. } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            prefix_lines(&code, "    ")
+        }
+        Node::Rule => "----------".to_string(),
+        Node::Table { content, .. } => content
+            .iter()
+            .map(|row| {
+                let cells = row.content().into_iter().flatten();
+                cells.map(|cell| cell.text_content().replace('\n', " ")).collect::<Vec<_>>().join(" | ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Node::MediaGroup { content } => {
+            content.iter().map(|media| block_text(media, options)).collect::<Vec<_>>().join("\n")
+        }
+        Node::Media { attrs } => match options.resolvers.media(attrs) {
+            Some(media) => match media.alt {
+                Some(alt) => format!("[{}: {}]", alt, media.url),
+                None => format!("[{}]", media.url),
+            },
+            None => "[attachment]".to_string(),
+        },
+        inline => inline_text(std::slice::from_ref(inline), options),
+    }
+}
+
+fn inline_text(content: &[Node], options: &HtmlOptions) -> String {
+    let mut out = String::new();
+    for node in content {
+        match node {
+            Node::Text { text, marks } => {
+                out.push_str(text);
+                let href = marks.iter().flatten().find_map(|mark| match mark {
+                    Mark::Link { attrs } => Some(&attrs.href),
+                    _ => None,
+                });
+                if let Some(href) = href.filter(|href| **href != *text && !href.starts_with('#')) {
+                    out.push_str(&format!(" <{}>", href.strip_prefix("mailto:").unwrap_or(href)));
+                }
+            }
+            Node::HardBreak { .. } => out.push('\n'),
+            Node::Mention { attrs } => out.push_str(&options.resolvers.mention_name(attrs)),
+            Node::Emoji { attrs } => out.push_str(&options.custom_emoji.text(attrs)),
+            Node::Date { attrs } => out.push_str(&format_date(attrs, options)),
+            Node::Status { attrs } => out.push_str(&format!("[{}]", attrs.text.to_uppercase())),
+            Node::InlineCard { attrs } => {
+                let url = attrs.url.as_deref().unwrap_or_default();
+                match options.resolvers.card(url) {
+                    Some(card) => out.push_str(&format!("{} <{}>", card.title, url)),
+                    None => out.push_str(url),
+                }
+            }
+            node => out.push_str(&node.text_content()),
+        }
+    }
+    out
+}
+
+fn list_text(items: &[Node], options: &HtmlOptions, marker: impl Fn(usize) -> String) -> String {
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let marker = marker(index);
+            let text = indent(&block_text(item, options), &" ".repeat(marker.len()));
+            format!("{}{}", marker, text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Indents every non-blank line but the first, so it lines up after a list
+// marker.
+fn indent(text: &str, by: &str) -> String {
+    let mut lines = text.split('\n');
+    let mut out = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        out.push('\n');
+        if !line.is_empty() {
+            out.push_str(by);
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+fn prefix_lines(text: &str, prefix: &str) -> String {
+    text.lines().map(|line| format!("{}{}", prefix, line).trim_end().to_string()).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod email_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+    use crate::model::{ExpandAttrs, LinkAttrs, OrderedListAttrs};
+    use crate::resolve::{InMemoryResolver, Resolvers, User};
+    use std::sync::Arc;
+
+    #[test]
+    fn should_inline_styles_for_panels_status_and_expands() {
+        let doc = Node::doc(vec![
+            Node::Panel {
+                content: vec![Node::paragraph(vec![
+                    Node::text_with_marks("f()", vec![Mark::Code]),
+                    Node::Status { attrs: StatusAttrs::new("Done", StatusColor::Green) },
+                ])],
+                attrs: PanelAttrs::new(PanelType::Warning),
+            },
+            Node::Expand { content: vec![paragraph("Hidden")], attrs: ExpandAttrs::new("More"), marks: None },
+        ]);
+        let html = to_email_html(&doc, &HtmlOptions::default());
+
+        assert!(!html.contains("class="));
+        assert!(!html.contains("<details"));
+        assert!(html.contains(
+            r#"<td style="background-color: #fffae6; border-left: 4px solid #ff8b00; padding: 8px 12px">"#
+        ));
+        assert!(html.contains(r#"background-color: #e3fcef; color: #006644"#));
+        assert!(html.contains(r#"<code style="font-family: SFMono-Regular"#));
+        assert!(html.contains(r#"<div style="font-weight: bold; margin-bottom: 4px">More</div><p>Hidden</p>"#));
+    }
+
+    #[test]
+    fn should_render_plain_text_alternative() {
+        let link = Mark::Link { attrs: LinkAttrs::new("https://example.com") };
+        let doc = Node::doc(vec![
+            Node::heading(1, vec![Node::text("Release")]),
+            Node::paragraph(vec![Node::text("See "), Node::text_with_marks("notes", vec![link])]),
+            Node::OrderedList {
+                content: vec![
+                    Node::list_item(vec![
+                        paragraph("One"),
+                        Node::bullet_list(vec![Node::list_item(vec![paragraph("a")])]),
+                    ]),
+                    Node::list_item(vec![paragraph("Two")]),
+                ],
+                attrs: Some(OrderedListAttrs::new(9)),
+            },
+            Node::Panel { content: vec![paragraph("Careful")], attrs: PanelAttrs::new(PanelType::Note) },
+            Node::code_block(None, "let x = 1;\nx"),
+        ]);
+
+        assert_eq!(
+            to_plain_text(&doc, &HtmlOptions::default()),
+            concat!(
+                "Release\n=======\n\n",
+                "See notes <https://example.com>\n\n",
+                "9. One\n   - a\n10. Two\n\n",
+                "[NOTE]\nCareful\n\n",
+                "    let x = 1;\n    x",
+            )
+        );
+    }
+
+    #[test]
+    fn should_drop_unsafe_profile_urls() {
+        let mut resolver = InMemoryResolver::new();
+        let user = User { display_name: "Ada".to_string(), profile_url: Some("javascript:alert(1)".to_string()) };
+        resolver.insert_user("u1", user);
+        let options =
+            HtmlOptions { resolvers: Resolvers::new().with_mentions(Arc::new(resolver)), ..HtmlOptions::default() };
+        let doc = Node::doc(vec![Node::paragraph(vec![Node::Mention { attrs: MentionAttrs::new("u1") }])]);
+
+        let html = to_email_html(&doc, &options);
+        assert!(!html.contains("javascript:"), "{html}");
+        assert!(html.contains(">@Ada</span>"));
+    }
+}
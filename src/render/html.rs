@@ -0,0 +1,935 @@
+use crate::date::DateFormat;
+use crate::emoji::{self, CustomEmojiFallback};
+use crate::model::{
+    CodeBlockAttrs, Color, DateAttrs, DecisionItemAttrs, DecisionListAttrs, EmojiAttrs, ExpandAttrs, HeadingAttrs,
+    InlineCardAttrs, Mark, MediaAttrs, MediaSingleAttrs, MentionAttrs, Node, OrderedListAttrs, PanelAttrs,
+    ParagraphAttrs, StatusAttrs, SubSupAttrs, TableAttrs, TableCellAttrs, TaskItemAttrs, TaskListAttrs, TaskState,
+};
+use crate::outline::{slug, with_heading_anchors};
+use crate::resolve::{self, Resolvers};
+use crate::url::safe_url;
+
+#[derive(Debug, Clone, Default)]
+pub struct HtmlOptions {
+    /// Remap colors for display on a dark background.
+    pub dark_mode: bool,
+    /// Format and timezone used to display `date` nodes.
+    pub date_format: DateFormat,
+    /// How `atlassian-*` custom emoji, which have no Unicode form, appear.
+    pub custom_emoji: CustomEmojiFallback,
+    /// Looks up user names, media URLs and link titles.
+    pub resolvers: Resolvers,
+}
+
+/// Hooks for each node and mark, for changing parts of the output while
+/// keeping the rest. Every method defaults to the free function of the same
+/// name in this module, which an override can call to fall back to the
+/// built-in markup, e.g. for panel types it does not handle. Children are
+/// rendered through [`HtmlRenderer::render_children`], so overrides apply
+/// at any depth.
+///
+/// [`HtmlOptions`] implements the trait with no overrides.
+pub trait HtmlRenderer {
+    fn options(&self) -> &HtmlOptions;
+
+    fn render_node(&self, node: &Node, out: &mut String) {
+        render_node(self, node, out)
+    }
+
+    fn render_children(&self, content: &[Node], out: &mut String) {
+        render_children(self, content, out)
+    }
+
+    fn render_doc(&self, content: &[Node], out: &mut String) {
+        render_doc(self, content, out)
+    }
+
+    fn render_blockquote(&self, content: &[Node], out: &mut String) {
+        render_blockquote(self, content, out)
+    }
+
+    fn render_bullet_list(&self, content: &[Node], out: &mut String) {
+        render_bullet_list(self, content, out)
+    }
+
+    fn render_code_block(&self, content: &[Node], attrs: Option<&CodeBlockAttrs>, out: &mut String) {
+        render_code_block(self, content, attrs, out)
+    }
+
+    fn render_date(&self, attrs: &DateAttrs, out: &mut String) {
+        render_date(self, attrs, out)
+    }
+
+    fn render_decision_item(&self, content: &[Node], attrs: &DecisionItemAttrs, out: &mut String) {
+        render_decision_item(self, content, attrs, out)
+    }
+
+    fn render_decision_list(&self, content: &[Node], attrs: &DecisionListAttrs, out: &mut String) {
+        render_decision_list(self, content, attrs, out)
+    }
+
+    fn render_emoji(&self, attrs: &EmojiAttrs, out: &mut String) {
+        render_emoji(self, attrs, out)
+    }
+
+    fn render_expand(&self, content: &[Node], attrs: &ExpandAttrs, out: &mut String) {
+        render_expand(self, content, attrs, out)
+    }
+
+    fn render_hard_break(&self, out: &mut String) {
+        render_hard_break(self, out)
+    }
+
+    fn render_heading(&self, content: &[Node], attrs: &HeadingAttrs, out: &mut String) {
+        render_heading(self, content, attrs, out)
+    }
+
+    fn render_inline_card(&self, attrs: &InlineCardAttrs, out: &mut String) {
+        render_inline_card(self, attrs, out)
+    }
+
+    fn render_list_item(&self, content: &[Node], out: &mut String) {
+        render_list_item(self, content, out)
+    }
+
+    fn render_media(&self, attrs: &MediaAttrs, out: &mut String) {
+        render_media(self, attrs, out)
+    }
+
+    fn render_media_group(&self, content: &[Node], out: &mut String) {
+        render_media_group(self, content, out)
+    }
+
+    fn render_media_single(&self, content: &[Node], attrs: &MediaSingleAttrs, out: &mut String) {
+        render_media_single(self, content, attrs, out)
+    }
+
+    fn render_mention(&self, attrs: &MentionAttrs, out: &mut String) {
+        render_mention(self, attrs, out)
+    }
+
+    fn render_nested_expand(&self, content: &[Node], attrs: &ExpandAttrs, out: &mut String) {
+        render_nested_expand(self, content, attrs, out)
+    }
+
+    fn render_ordered_list(&self, content: &[Node], attrs: Option<&OrderedListAttrs>, out: &mut String) {
+        render_ordered_list(self, content, attrs, out)
+    }
+
+    fn render_panel(&self, content: &[Node], attrs: &PanelAttrs, out: &mut String) {
+        render_panel(self, content, attrs, out)
+    }
+
+    fn render_paragraph(&self, content: &[Node], attrs: Option<&ParagraphAttrs>, out: &mut String) {
+        render_paragraph(self, content, attrs, out)
+    }
+
+    fn render_rule(&self, out: &mut String) {
+        render_rule(self, out)
+    }
+
+    fn render_status(&self, attrs: &StatusAttrs, out: &mut String) {
+        render_status(self, attrs, out)
+    }
+
+    fn render_table(&self, content: &[Node], attrs: Option<&TableAttrs>, out: &mut String) {
+        render_table(self, content, attrs, out)
+    }
+
+    fn render_table_row(&self, content: &[Node], out: &mut String) {
+        render_table_row(self, content, out)
+    }
+
+    fn render_table_cell(&self, content: &[Node], attrs: Option<&TableCellAttrs>, out: &mut String) {
+        render_table_cell(self, content, attrs, out)
+    }
+
+    fn render_table_header(&self, content: &[Node], attrs: Option<&TableCellAttrs>, out: &mut String) {
+        render_table_header(self, content, attrs, out)
+    }
+
+    fn render_task_item(&self, content: &[Node], attrs: &TaskItemAttrs, out: &mut String) {
+        render_task_item(self, content, attrs, out)
+    }
+
+    fn render_task_list(&self, content: &[Node], attrs: &TaskListAttrs, out: &mut String) {
+        render_task_list(self, content, attrs, out)
+    }
+
+    fn render_text(&self, text: &str, marks: &[Mark], out: &mut String) {
+        render_text(self, text, marks, out)
+    }
+
+    fn open_mark(&self, mark: &Mark, out: &mut String) {
+        open_mark(self, mark, out)
+    }
+
+    fn close_mark(&self, mark: &Mark, out: &mut String) {
+        close_mark(self, mark, out)
+    }
+}
+
+impl HtmlRenderer for HtmlOptions {
+    fn options(&self) -> &HtmlOptions {
+        self
+    }
+}
+
+pub fn to_html(node: &Node, options: &HtmlOptions) -> String {
+    render(node, options)
+}
+
+/// Renders with a customized renderer.
+///
+/// Heading ids are their [`OutlineEntry::anchor`](crate::outline::OutlineEntry::anchor),
+/// so they are unique in the page and match the links of a table of
+/// contents. The document is copied for that only when heading slugs repeat.
+pub fn render<R: HtmlRenderer + ?Sized>(node: &Node, renderer: &R) -> String {
+    let mut out = String::new();
+    let anchored = with_heading_anchors(node);
+    renderer.render_node(anchored.as_ref().unwrap_or(node), &mut out);
+    out
+}
+
+pub fn render_node<R: HtmlRenderer + ?Sized>(r: &R, node: &Node, out: &mut String) {
+    match node {
+        Node::Blockquote { content } => r.render_blockquote(content, out),
+        Node::BulletList { content } => r.render_bullet_list(content, out),
+        Node::CodeBlock { content, attrs } => {
+            r.render_code_block(content.as_deref().unwrap_or_default(), attrs.as_ref(), out)
+        }
+        Node::Date { attrs } => r.render_date(attrs, out),
+        Node::DecisionItem { content, attrs } => r.render_decision_item(content, attrs, out),
+        Node::DecisionList { content, attrs } => r.render_decision_list(content, attrs, out),
+        Node::Doc { content, .. } => r.render_doc(content, out),
+        Node::Emoji { attrs } => r.render_emoji(attrs, out),
+        Node::Expand { content, attrs, .. } => r.render_expand(content, attrs, out),
+        Node::HardBreak { .. } => r.render_hard_break(out),
+        Node::Heading { content, attrs } => r.render_heading(content, attrs, out),
+        Node::InlineCard { attrs } => r.render_inline_card(attrs, out),
+        Node::ListItem { content } => r.render_list_item(content, out),
+        Node::Media { attrs } => r.render_media(attrs, out),
+        Node::MediaGroup { content } => r.render_media_group(content, out),
+        Node::MediaSingle { content, attrs } => r.render_media_single(content, attrs, out),
+        Node::Mention { attrs } => r.render_mention(attrs, out),
+        Node::NestedExpand { content, attrs } => r.render_nested_expand(content, attrs, out),
+        Node::OrderedList { content, attrs } => r.render_ordered_list(content, attrs.as_ref(), out),
+        Node::Panel { content, attrs } => r.render_panel(content, attrs, out),
+        Node::Paragraph { content, attrs } => r.render_paragraph(content, attrs.as_ref(), out),
+        Node::Rule => r.render_rule(out),
+        Node::Status { attrs } => r.render_status(attrs, out),
+        Node::Table { content, attrs } => r.render_table(content, attrs.as_ref(), out),
+        Node::TableCell { content, attrs } => r.render_table_cell(content, attrs.as_ref(), out),
+        Node::TableHeader { content, attrs } => r.render_table_header(content, attrs.as_ref(), out),
+        Node::TableRow { content } => r.render_table_row(content, out),
+        Node::TaskItem { content, attrs } => r.render_task_item(content, attrs, out),
+        Node::TaskList { content, attrs } => r.render_task_list(content, attrs, out),
+        Node::Text { text, marks } => r.render_text(text, marks.as_deref().unwrap_or_default(), out),
+    }
+}
+
+pub fn render_children<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], out: &mut String) {
+    for node in content {
+        r.render_node(node, out);
+    }
+}
+
+pub fn render_doc<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], out: &mut String) {
+    r.render_children(content, out)
+}
+
+pub fn render_blockquote<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], out: &mut String) {
+    render_element(r, "blockquote", "", content, out)
+}
+
+pub fn render_bullet_list<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], out: &mut String) {
+    render_element(r, "ul", "", content, out)
+}
+
+pub fn render_code_block<R: HtmlRenderer + ?Sized>(
+    _: &R,
+    content: &[Node],
+    attrs: Option<&CodeBlockAttrs>,
+    out: &mut String,
+) {
+    out.push_str("<pre><code");
+    if let Some(attrs) = attrs {
+        push_attr(out, "class", &format!("language-{}", attrs.language));
+    }
+    out.push('>');
+    for node in content {
+        if let Node::Text { text, .. } = node {
+            push_escaped(out, text);
+        }
+    }
+    out.push_str("</code></pre>");
+}
+
+pub fn render_date<R: HtmlRenderer + ?Sized>(r: &R, attrs: &DateAttrs, out: &mut String) {
+    let date_format = &r.options().date_format;
+    match attrs.parsed_timestamp() {
+        Ok(timestamp) => {
+            let date = timestamp.to_date_time(date_format.offset);
+            out.push_str(r#"<time class="adf-date""#);
+            push_attr(out, "datetime", &format!("{:04}-{:02}-{:02}", date.year, date.month, date.day));
+            out.push('>');
+            push_escaped(out, &date_format.format(timestamp));
+            out.push_str("</time>");
+        }
+        Err(_) => {
+            out.push_str(r#"<time class="adf-date">"#);
+            push_escaped(out, &attrs.timestamp);
+            out.push_str("</time>");
+        }
+    }
+}
+
+pub fn render_decision_item<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    content: &[Node],
+    _: &DecisionItemAttrs,
+    out: &mut String,
+) {
+    render_element(r, "li", r#" class="adf-decision-item""#, content, out)
+}
+
+pub fn render_decision_list<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    content: &[Node],
+    _: &DecisionListAttrs,
+    out: &mut String,
+) {
+    render_element(r, "ul", r#" class="adf-decision-list""#, content, out)
+}
+
+pub fn render_emoji<R: HtmlRenderer + ?Sized>(r: &R, attrs: &EmojiAttrs, out: &mut String) {
+    let fallback = &r.options().custom_emoji;
+    if let Some(url) = fallback.image_url(attrs) {
+        out.push_str(r#"<img class="adf-emoji""#);
+        push_attr(out, "src", &url);
+        push_attr(out, "alt", &attrs.short_name);
+        push_attr(out, "title", &attrs.short_name);
+        out.push('>');
+        return;
+    }
+    let class = if emoji::is_custom(attrs) { "adf-emoji adf-emoji-custom" } else { "adf-emoji" };
+    out.push_str("<span");
+    push_attr(out, "class", class);
+    push_attr(out, "title", &attrs.short_name);
+    out.push('>');
+    push_escaped(out, &fallback.text(attrs));
+    out.push_str("</span>");
+}
+
+pub fn render_expand<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], attrs: &ExpandAttrs, out: &mut String) {
+    out.push_str(r#"<details class="adf-expand"><summary>"#);
+    push_escaped(out, &attrs.title);
+    out.push_str("</summary>");
+    r.render_children(content, out);
+    out.push_str("</details>");
+}
+
+pub fn render_hard_break<R: HtmlRenderer + ?Sized>(_: &R, out: &mut String) {
+    out.push_str("<br>")
+}
+
+pub fn render_heading<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], attrs: &HeadingAttrs, out: &mut String) {
+    let level = attrs.level.clamp(1, 6);
+    out.push_str(&format!("<h{}", level));
+    match &attrs.local_id {
+        Some(local_id) => push_attr(out, "id", local_id),
+        None => push_attr(out, "id", &slug(&content.iter().map(Node::text_content).collect::<String>())),
+    }
+    out.push('>');
+    r.render_children(content, out);
+    out.push_str(&format!("</h{}>", level));
+}
+
+pub fn render_inline_card<R: HtmlRenderer + ?Sized>(r: &R, attrs: &InlineCardAttrs, out: &mut String) {
+    // A card whose URL could run script is shown like one without a URL.
+    let Some(url) = attrs.url.as_deref().and_then(safe_url) else {
+        out.push_str(r#"<span class="adf-inline-card"></span>"#);
+        return;
+    };
+    out.push_str(r#"<a class="adf-inline-card""#);
+    push_attr(out, "href", url);
+    out.push('>');
+    match r.options().resolvers.card(url) {
+        Some(card) => {
+            if let Some(icon_url) = card.icon_url.as_deref().and_then(safe_url) {
+                out.push_str(r#"<img class="adf-inline-card-icon""#);
+                push_attr(out, "src", icon_url);
+                out.push_str(r#" alt="">"#);
+            }
+            push_escaped(out, &card.title);
+        }
+        None => push_escaped(out, url),
+    }
+    out.push_str("</a>");
+}
+
+pub fn render_list_item<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], out: &mut String) {
+    render_element(r, "li", "", content, out)
+}
+
+pub fn render_media<R: HtmlRenderer + ?Sized>(r: &R, attrs: &MediaAttrs, out: &mut String) {
+    // Resolved URLs are checked like document ones; an unsafe one is treated
+    // as unresolved.
+    let media = r.options().resolvers.media(attrs);
+    match media.as_ref().and_then(|media| Some((media, safe_url(&media.url)?))) {
+        Some((media, url)) => {
+            out.push_str(r#"<img class="adf-media""#);
+            push_attr(out, "src", url);
+            push_attr(out, "alt", media.alt.as_deref().unwrap_or_default());
+            if let Some(width) = media.width {
+                push_attr(out, "width", &width.to_string());
+            }
+            if let Some(height) = media.height {
+                push_attr(out, "height", &height.to_string());
+            }
+            out.push('>');
+        }
+        None => {
+            out.push_str(r#"<div class="adf-media""#);
+            push_attr(out, "data-id", &attrs.id);
+            push_attr(out, "data-collection", &attrs.collection);
+            out.push_str("></div>");
+        }
+    }
+}
+
+pub fn render_media_group<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], out: &mut String) {
+    render_element(r, "div", r#" class="adf-media-group""#, content, out)
+}
+
+pub fn render_media_single<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    content: &[Node],
+    attrs: &MediaSingleAttrs,
+    out: &mut String,
+) {
+    let mut class = String::new();
+    push_attr(&mut class, "class", &format!("adf-media-single adf-layout-{}", attrs.layout));
+    render_element(r, "figure", &class, content, out)
+}
+
+pub fn render_mention<R: HtmlRenderer + ?Sized>(r: &R, attrs: &MentionAttrs, out: &mut String) {
+    let user = r.options().resolvers.user(attrs);
+    let profile_url = user.as_ref().and_then(|user| user.profile_url.as_deref()).and_then(safe_url);
+    let tag = if profile_url.is_some() { "a" } else { "span" };
+    out.push_str(&format!(r#"<{} class="adf-mention""#, tag));
+    if let Some(profile_url) = profile_url {
+        push_attr(out, "href", profile_url);
+    }
+    push_attr(out, "data-user-id", &attrs.id);
+    out.push('>');
+    push_escaped(out, &resolve::mention_name(user.as_ref(), attrs));
+    out.push_str(&format!("</{}>", tag));
+}
+
+pub fn render_nested_expand<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    content: &[Node],
+    attrs: &ExpandAttrs,
+    out: &mut String,
+) {
+    r.render_expand(content, attrs, out)
+}
+
+pub fn render_ordered_list<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    content: &[Node],
+    attrs: Option<&OrderedListAttrs>,
+    out: &mut String,
+) {
+    let start = match attrs {
+        Some(attrs) if attrs.order != 1 => format!(r#" start="{}""#, attrs.order),
+        _ => String::new(),
+    };
+    render_element(r, "ol", &start, content, out)
+}
+
+pub fn render_panel<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], attrs: &PanelAttrs, out: &mut String) {
+    let mut class = String::new();
+    push_attr(&mut class, "class", &format!("adf-panel adf-panel-{}", attrs.panel_type));
+    render_element(r, "div", &class, content, out)
+}
+
+pub fn render_paragraph<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    content: &[Node],
+    _: Option<&ParagraphAttrs>,
+    out: &mut String,
+) {
+    render_element(r, "p", "", content, out)
+}
+
+pub fn render_rule<R: HtmlRenderer + ?Sized>(_: &R, out: &mut String) {
+    out.push_str("<hr>")
+}
+
+pub fn render_status<R: HtmlRenderer + ?Sized>(_: &R, attrs: &StatusAttrs, out: &mut String) {
+    out.push_str("<span");
+    push_attr(out, "class", &format!("adf-status adf-status-{}", attrs.color));
+    out.push('>');
+    push_escaped(out, &attrs.text);
+    out.push_str("</span>");
+}
+
+pub fn render_table<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], _: Option<&TableAttrs>, out: &mut String) {
+    out.push_str("<table><tbody>");
+    r.render_children(content, out);
+    out.push_str("</tbody></table>");
+}
+
+pub fn render_table_row<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], out: &mut String) {
+    render_element(r, "tr", "", content, out)
+}
+
+pub fn render_table_cell<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    content: &[Node],
+    attrs: Option<&TableCellAttrs>,
+    out: &mut String,
+) {
+    render_cell(r, "td", content, attrs, out)
+}
+
+pub fn render_table_header<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    content: &[Node],
+    attrs: Option<&TableCellAttrs>,
+    out: &mut String,
+) {
+    render_cell(r, "th", content, attrs, out)
+}
+
+pub fn render_task_item<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], attrs: &TaskItemAttrs, out: &mut String) {
+    out.push_str(r#"<li class="adf-task-item"><input type="checkbox" disabled"#);
+    if attrs.state == TaskState::Done {
+        out.push_str(" checked");
+    }
+    out.push('>');
+    r.render_children(content, out);
+    out.push_str("</li>");
+}
+
+pub fn render_task_list<R: HtmlRenderer + ?Sized>(r: &R, content: &[Node], _: &TaskListAttrs, out: &mut String) {
+    render_element(r, "ul", r#" class="adf-task-list""#, content, out)
+}
+
+pub fn render_text<R: HtmlRenderer + ?Sized>(r: &R, text: &str, marks: &[Mark], out: &mut String) {
+    for mark in marks {
+        r.open_mark(mark, out);
+    }
+    push_escaped(out, text);
+    for mark in marks.iter().rev() {
+        r.close_mark(mark, out);
+    }
+}
+
+pub fn open_mark<R: HtmlRenderer + ?Sized>(r: &R, mark: &Mark, out: &mut String) {
+    match mark {
+        Mark::BackgroundColor { attrs } => {
+            let style = format!("background-color: {}", color(&attrs.color, r.options()));
+            out.push_str("<span");
+            push_attr(out, "style", &style);
+            out.push('>');
+        }
+        Mark::Code => out.push_str("<code>"),
+        Mark::Em => out.push_str("<em>"),
+        Mark::Link { attrs } => {
+            out.push_str("<a");
+            // The element stays without the href so that `close_mark` matches.
+            if let Some(href) = safe_url(&attrs.href) {
+                push_attr(out, "href", href);
+            }
+            if let Some(title) = &attrs.title {
+                push_attr(out, "title", title);
+            }
+            out.push('>');
+        }
+        Mark::Strike => out.push_str("<s>"),
+        Mark::Strong => out.push_str("<strong>"),
+        Mark::SubSup { attrs: SubSupAttrs::Sub } => out.push_str("<sub>"),
+        Mark::SubSup { attrs: SubSupAttrs::Sup } => out.push_str("<sup>"),
+        Mark::TextColor { attrs } => {
+            let style = format!("color: {}", color(&attrs.color, r.options()));
+            out.push_str("<span");
+            push_attr(out, "style", &style);
+            out.push('>');
+        }
+        Mark::Underline => out.push_str("<u>"),
+    }
+}
+
+pub fn close_mark<R: HtmlRenderer + ?Sized>(_: &R, mark: &Mark, out: &mut String) {
+    out.push_str(match mark {
+        Mark::BackgroundColor { .. } | Mark::TextColor { .. } => "</span>",
+        Mark::Code => "</code>",
+        Mark::Em => "</em>",
+        Mark::Link { .. } => "</a>",
+        Mark::Strike => "</s>",
+        Mark::Strong => "</strong>",
+        Mark::SubSup { attrs: SubSupAttrs::Sub } => "</sub>",
+        Mark::SubSup { attrs: SubSupAttrs::Sup } => "</sup>",
+        Mark::Underline => "</u>",
+    });
+}
+
+fn render_element<R: HtmlRenderer + ?Sized>(r: &R, tag: &str, attrs: &str, content: &[Node], out: &mut String) {
+    out.push_str(&format!("<{}{}>", tag, attrs));
+    r.render_children(content, out);
+    out.push_str(&format!("</{}>", tag));
+}
+
+fn render_cell<R: HtmlRenderer + ?Sized>(
+    r: &R,
+    tag: &str,
+    content: &[Node],
+    attrs: Option<&TableCellAttrs>,
+    out: &mut String,
+) {
+    let mut cell_attrs = String::new();
+    if let Some(attrs) = attrs {
+        if let Some(colspan) = attrs.colspan.filter(|span| *span > 1) {
+            push_attr(&mut cell_attrs, "colspan", &colspan.to_string());
+        }
+        if let Some(rowspan) = attrs.rowspan.filter(|span| *span > 1) {
+            push_attr(&mut cell_attrs, "rowspan", &rowspan.to_string());
+        }
+        if let Some(background) = &attrs.background {
+            let style = format!("background-color: {}", color(background, r.options()));
+            push_attr(&mut cell_attrs, "style", &style);
+        }
+    }
+    render_element(r, tag, &cell_attrs, content, out)
+}
+
+fn color(color: &Color, options: &HtmlOptions) -> Color {
+    if options.dark_mode {
+        color.to_dark_mode()
+    } else {
+        *color
+    }
+}
+
+/// Appends ` name="value"`, escaping the value.
+pub fn push_attr(out: &mut String, name: &str, value: &str) {
+    out.push_str(&format!(r#" {}=""#, name));
+    push_escaped(out, value);
+    out.push('"');
+}
+
+/// Appends text with the HTML special characters escaped.
+pub fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod html_tests {
+    use super::*;
+    use crate::lexer::from_str;
+    use std::sync::Arc;
+
+    use crate::model::{MediaType, PanelType};
+    use crate::resolve::{CardInfo, InMemoryResolver, MediaInfo, User};
+
+    #[test]
+    fn should_render_paragraph_with_marks() {
+        let doc = from_str(
+            r##"{
+                "type": "doc",
+                "version": 1,
+                "content": [{
+                    "type": "paragraph",
+                    "content": [
+                        { "type": "text", "text": "Hello " },
+                        {
+                            "type": "text",
+                            "text": "<world>",
+                            "marks": [
+                                { "type": "strong" },
+                                { "type": "link", "attrs": { "href": "https://example.com?a=1&b=2" } }
+                            ]
+                        }
+                    ]
+                }]
+            }"##,
+        );
+
+        assert_eq!(
+            to_html(&doc, &HtmlOptions::default()),
+            r#"<p>Hello <strong><a href="https://example.com?a=1&amp;b=2">&lt;world&gt;</a></strong></p>"#
+        );
+    }
+
+    #[test]
+    fn should_render_colors_for_dark_mode() {
+        let doc = from_str(
+            r##"{
+                "type": "doc",
+                "version": 1,
+                "content": [{
+                    "type": "paragraph",
+                    "content": [{
+                        "type": "text",
+                        "text": "Alert",
+                        "marks": [{ "type": "textColor", "attrs": { "color": "#FF5630" } }]
+                    }]
+                }]
+            }"##,
+        );
+
+        assert_eq!(
+            to_html(&doc, &HtmlOptions::default()),
+            r#"<p><span style="color: #ff5630">Alert</span></p>"#
+        );
+        assert_eq!(
+            to_html(&doc, &HtmlOptions { dark_mode: true, ..HtmlOptions::default() }),
+            r#"<p><span style="color: #f87168">Alert</span></p>"#
+        );
+    }
+
+    #[test]
+    fn should_render_dates_with_configured_format() {
+        let doc = from_str(
+            r#"{
+                "type": "doc",
+                "version": 1,
+                "content": [{
+                    "type": "paragraph",
+                    "content": [{ "type": "date", "attrs": { "timestamp": "1582152559000" } }]
+                }]
+            }"#,
+        );
+        let options = HtmlOptions {
+            date_format: DateFormat {
+                pattern: "%e %B %Y".to_string(),
+                offset: crate::date::UtcOffset::from_minutes(-300).unwrap(),
+            },
+            ..HtmlOptions::default()
+        };
+
+        assert_eq!(
+            to_html(&doc, &options),
+            r#"<p><time class="adf-date" datetime="2020-02-19">19 February 2020</time></p>"#
+        );
+    }
+
+    #[test]
+    fn should_render_emoji_and_custom_emoji_fallback() {
+        let doc = Node::doc(vec![Node::paragraph(vec![
+            Node::Emoji { attrs: EmojiAttrs::new(":thumbsup::skin-tone-2:") },
+            Node::Emoji { attrs: EmojiAttrs::new(":parrot:").with_id("atlassian-parrot").with_text(":parrot:") },
+        ])]);
+        let options = HtmlOptions {
+            custom_emoji: CustomEmojiFallback::Image { url_template: "https://emoji.example.com/{id}.png".to_string() },
+            ..HtmlOptions::default()
+        };
+
+        assert_eq!(
+            to_html(&doc, &HtmlOptions::default()),
+            concat!(
+                r#"<p><span class="adf-emoji" title=":thumbsup::skin-tone-2:">👍🏻</span>"#,
+                r#"<span class="adf-emoji adf-emoji-custom" title=":parrot:">:parrot:</span></p>"#,
+            )
+        );
+        assert!(to_html(&doc, &options).contains(
+            r#"<img class="adf-emoji" src="https://emoji.example.com/atlassian-parrot.png" alt=":parrot:" title=":parrot:">"#
+        ));
+    }
+
+    #[test]
+    fn should_render_resolved_mentions_media_and_cards() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert_user(
+            "u1",
+            User { display_name: "Ada".to_string(), profile_url: Some("https://people/u1".to_string()) },
+        );
+        resolver.insert_card("https://a.example", CardInfo { title: "Page A".to_string(), icon_url: None });
+        let resolver = Arc::new(resolver);
+        let options = HtmlOptions {
+            resolvers: Resolvers::new().with_mentions(resolver.clone()).with_cards(resolver),
+            ..HtmlOptions::default()
+        };
+        let doc = Node::doc(vec![Node::paragraph(vec![
+            Node::Mention { attrs: MentionAttrs::new("u1") },
+            Node::Mention { attrs: MentionAttrs::new("u2").with_text("@Bo") },
+            Node::InlineCard { attrs: InlineCardAttrs::from_url("https://a.example") },
+            Node::InlineCard { attrs: InlineCardAttrs::from_url("https://b.example") },
+        ])]);
+
+        assert_eq!(
+            to_html(&doc, &options),
+            concat!(
+                r#"<p><a class="adf-mention" href="https://people/u1" data-user-id="u1">@Ada</a>"#,
+                r#"<span class="adf-mention" data-user-id="u2">@Bo</span>"#,
+                r#"<a class="adf-inline-card" href="https://a.example">Page A</a>"#,
+                r#"<a class="adf-inline-card" href="https://b.example">https://b.example</a></p>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn should_drop_unsafe_resolved_urls() {
+        let mut resolver = InMemoryResolver::new();
+        let hostile = || Some("javascript:alert(1)".to_string());
+        resolver.insert_user("u1", User { display_name: "Ada".to_string(), profile_url: hostile() });
+        resolver.insert_card("https://a.example", CardInfo { title: "Page A".to_string(), icon_url: hostile() });
+        let media = MediaInfo { url: "javascript:alert(2)".to_string(), alt: None, width: None, height: None };
+        resolver.insert_media("file-1", "docs", media);
+        let resolver = Arc::new(resolver);
+        let options = HtmlOptions {
+            resolvers: Resolvers::new()
+                .with_mentions(resolver.clone())
+                .with_media(resolver.clone())
+                .with_cards(resolver),
+            ..HtmlOptions::default()
+        };
+        let doc = Node::doc(vec![
+            Node::paragraph(vec![
+                Node::Mention { attrs: MentionAttrs::new("u1") },
+                Node::InlineCard { attrs: InlineCardAttrs::from_url("https://a.example") },
+            ]),
+            Node::Media { attrs: MediaAttrs::new(MediaType::File, "file-1", "docs") },
+        ]);
+
+        let html = to_html(&doc, &options);
+        assert!(!html.contains("javascript:"), "{html}");
+        assert!(html.contains(r#"<span class="adf-mention" data-user-id="u1">@Ada</span>"#));
+        assert!(html.contains(r#"<a class="adf-inline-card" href="https://a.example">Page A</a>"#));
+    }
+
+    struct Bootstrap(HtmlOptions);
+
+    impl HtmlRenderer for Bootstrap {
+        fn options(&self) -> &HtmlOptions {
+            &self.0
+        }
+
+        fn render_panel(&self, content: &[Node], attrs: &PanelAttrs, out: &mut String) {
+            let class = match attrs.panel_type() {
+                PanelType::Error => "danger",
+                PanelType::Warning => "warning",
+                _ => return render_panel(self, content, attrs, out),
+            };
+            out.push_str(&format!(r#"<div class="alert alert-{}">"#, class));
+            self.render_children(content, out);
+            out.push_str("</div>");
+        }
+
+        fn open_mark(&self, mark: &Mark, out: &mut String) {
+            match mark {
+                Mark::Strong => out.push_str("<b>"),
+                _ => open_mark(self, mark, out),
+            }
+        }
+
+        fn close_mark(&self, mark: &Mark, out: &mut String) {
+            match mark {
+                Mark::Strong => out.push_str("</b>"),
+                _ => close_mark(self, mark, out),
+            }
+        }
+    }
+
+    #[test]
+    fn should_render_with_overridden_nodes_and_marks() {
+        let paragraph = Node::paragraph(vec![Node::text_with_marks("Down", vec![Mark::Strong, Mark::Em])]);
+        let doc = Node::doc(vec![
+            Node::Panel { content: vec![paragraph.clone()], attrs: PanelAttrs::new(PanelType::Error) },
+            Node::Panel {
+                content: vec![Node::Panel { content: vec![paragraph], attrs: PanelAttrs::new(PanelType::Warning) }],
+                attrs: PanelAttrs::new(PanelType::Info),
+            },
+        ]);
+
+        assert_eq!(
+            render(&doc, &Bootstrap(HtmlOptions::default())),
+            concat!(
+                r#"<div class="alert alert-danger"><p><b><em>Down</em></b></p></div>"#,
+                r#"<div class="adf-panel adf-panel-info">"#,
+                r#"<div class="alert alert-warning"><p><b><em>Down</em></b></p></div></div>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn should_render_table_cell_spans_and_background() {
+        let doc = from_str(
+            r##"{
+                "type": "doc",
+                "version": 1,
+                "content": [{
+                    "type": "table",
+                    "content": [{
+                        "type": "tableRow",
+                        "content": [{
+                            "type": "tableHeader",
+                            "attrs": { "colspan": 2, "background": "#deebff" },
+                            "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "A" }] }]
+                        }]
+                    }]
+                }]
+            }"##,
+        );
+
+        assert_eq!(
+            to_html(&doc, &HtmlOptions::default()),
+            r#"<table><tbody><tr><th colspan="2" style="background-color: #deebff"><p>A</p></th></tr></tbody></table>"#
+        );
+    }
+
+    #[test]
+    fn should_give_repeated_headings_unique_ids() {
+        let doc = Node::doc(vec![
+            Node::heading(2, vec![Node::text("Usage")]),
+            Node::heading(2, vec![Node::text("Usage")]),
+        ]);
+
+        assert_eq!(to_html(&doc, &HtmlOptions::default()), r#"<h2 id="usage">Usage</h2><h2 id="usage-2">Usage</h2>"#);
+    }
+
+    #[test]
+    fn should_escape_enum_classes_and_drop_script_urls() {
+        let doc = from_str(
+            r##"{
+                "type": "doc",
+                "version": 1,
+                "content": [{
+                    "type": "panel",
+                    "attrs": { "panelType": "x\"><script>alert(1)</script>" },
+                    "content": [{
+                        "type": "paragraph",
+                        "content": [
+                            {
+                                "type": "text",
+                                "text": "click",
+                                "marks": [{ "type": "link", "attrs": { "href": " JavaScript:alert(1)" } }]
+                            },
+                            { "type": "inlineCard", "attrs": { "url": "javascript:alert(2)" } }
+                        ]
+                    }]
+                }]
+            }"##,
+        );
+
+        assert_eq!(
+            to_html(&doc, &HtmlOptions::default()),
+            concat!(
+                r#"<div class="adf-panel adf-panel-x&quot;&gt;&lt;script&gt;alert(1)&lt;/script&gt;">"#,
+                r#"<p><a>click</a><span class="adf-inline-card"></span></p></div>"#,
+            )
+        );
+    }
+}
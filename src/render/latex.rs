@@ -0,0 +1,593 @@
+use crate::color::Color;
+use crate::date::DateFormat;
+use crate::emoji::CustomEmojiFallback;
+use crate::model::{Mark, Node, PanelType, SubSupAttrs, TaskState};
+use crate::resolve::{self, Resolvers};
+use crate::table::{TableGrid, PAGE_WIDTH};
+
+/// Packages and settings the output of [`to_latex`] relies on, for the
+/// preamble of the document it is included in. `fontspec` needs LuaLaTeX or
+/// XeLaTeX.
+pub const PREAMBLE: &str = r"\usepackage{fontspec}
+\usepackage{amssymb}
+\usepackage[normalem]{ulem}
+\usepackage{xcolor}
+\usepackage{listings}
+\usepackage{longtable}
+\usepackage{multirow}
+\usepackage{tcolorbox}
+\usepackage{hyperref}
+\lstset{basicstyle=\ttfamily\small, breaklines=true, columns=fullflexible, frame=single}
+";
+
+// Code block languages known to `listings`, which stops with an error on
+// any other: (ADF language, listings name).
+const LISTINGS_LANGUAGES: [(&str, &str); 22] = [
+    ("bash", "bash"),
+    ("c", "C"),
+    ("c++", "C++"),
+    ("cpp", "C++"),
+    ("csharp", "[Sharp]C"),
+    ("haskell", "Haskell"),
+    ("html", "HTML"),
+    ("java", "Java"),
+    ("latex", "TeX"),
+    ("lua", "Lua"),
+    ("matlab", "Matlab"),
+    ("perl", "Perl"),
+    ("php", "PHP"),
+    ("python", "Python"),
+    ("r", "R"),
+    ("ruby", "Ruby"),
+    ("scala", "Scala"),
+    ("sh", "sh"),
+    ("shell", "bash"),
+    ("sql", "SQL"),
+    ("tex", "TeX"),
+    ("xml", "XML"),
+];
+
+// LaTeX allows four levels of nested lists.
+const MAX_LIST_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Default)]
+pub struct LatexOptions {
+    /// Format and timezone used to display `date` nodes.
+    pub date_format: DateFormat,
+    /// How custom emoji appear. LaTeX cannot load remote images, so an image
+    /// fallback shows the short name.
+    pub custom_emoji: CustomEmojiFallback,
+    /// Looks up user names, media file paths and link titles.
+    pub resolvers: Resolvers,
+}
+
+/// LaTeX for a node, to be included in a document whose preamble has
+/// [`PREAMBLE`].
+///
+/// Panels and expands become `tcolorbox`es, code blocks `lstlisting`s and
+/// tables `longtable`s, or `tabular`s where a long table is not allowed, with
+/// column widths following `colwidth` and spans written with `\multicolumn`
+/// and `\multirow`. Text colors use `xcolor`. Media become framed
+/// placeholders naming the resolved file path, so the output compiles whether
+/// or not the files are at hand.
+///
+/// The output is written for LuaLaTeX or XeLaTeX, which read any Unicode
+/// text; characters the font lacks are left out with a warning rather than
+/// stopping the build. Emoji, which few text fonts have, are written as their
+/// short names.
+pub fn to_latex(node: &Node, options: &LatexOptions) -> String {
+    let mut out = Writer { options }.block(node, Context::default());
+    out.push('\n');
+    out
+}
+
+/// A complete `article` document around [`to_latex`]'s output.
+pub fn to_latex_document(node: &Node, options: &LatexOptions) -> String {
+    format!(
+        "\\documentclass{{article}}\n{}\n\\begin{{document}}\n\n{}\n\\end{{document}}\n",
+        PREAMBLE,
+        to_latex(node, options)
+    )
+}
+
+// Where a block is written, which limits what LaTeX accepts there.
+#[derive(Debug, Clone, Copy, Default)]
+struct Context {
+    in_box: bool,
+    in_table: bool,
+    lists: usize,
+}
+
+impl Context {
+    // `longtable` only works outside boxes, lists and other tables.
+    fn allows_long_table(self) -> bool {
+        !self.in_box && !self.in_table && self.lists == 0
+    }
+
+    // Table cells may be macro arguments, which cannot hold a blank line.
+    fn separator(self) -> &'static str {
+        if self.in_table { "\n\\endgraf\n" } else { "\n\n" }
+    }
+}
+
+struct Writer<'a> {
+    options: &'a LatexOptions,
+}
+
+impl Writer<'_> {
+    fn blocks(&self, content: &[Node], cx: Context) -> String {
+        content
+            .iter()
+            .map(|node| self.block(node, cx))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(cx.separator())
+    }
+
+    fn block(&self, node: &Node, cx: Context) -> String {
+        match node {
+            Node::Doc { content, .. } | Node::ListItem { content } => self.blocks(content, cx),
+            Node::Paragraph { content, .. } => {
+                // A line break with no line before or after it is an error.
+                let is_break = |node: &Node| matches!(node, Node::HardBreak { .. });
+                let start = content.iter().position(|node| !is_break(node)).unwrap_or(content.len());
+                let end = content.iter().rposition(|node| !is_break(node)).map_or(start, |end| end + 1);
+                self.inline(&content[start..end])
+            }
+            Node::Heading { content, attrs } => {
+                let text = self.inline(content).replace("\\newline\n", " ");
+                if cx.in_table {
+                    return format!("\\textbf{{{}}}", text);
+                }
+                let command = match attrs.level() {
+                    1 => "section",
+                    2 => "subsection",
+                    3 => "subsubsection",
+                    4 => "paragraph",
+                    _ => "subparagraph",
+                };
+                format!("\\{}*{{{}}}", command, text)
+            }
+            Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } | Node::DecisionList { .. } => {
+                self.list(node, cx)
+            }
+            Node::Blockquote { content } => {
+                format!("\\begin{{quote}}\n{}\n\\end{{quote}}", self.blocks(content, cx))
+            }
+            Node::CodeBlock { content, attrs } => {
+                let code: String = content.iter().flatten().map(Node::text_content).collect();
+                // A listing cannot sit in a macro argument, which a spanning cell is.
+                if cx.in_table {
+                    return code
+                        .lines()
+                        .map(|line| format!("\\texttt{{{}}}", escape(line).replace(' ', "~")))
+                        .collect::<Vec<_>>()
+                        .join("\\newline\n");
+                }
+                let language = attrs.as_ref().and_then(|attrs| {
+                    let language = attrs.language().to_lowercase();
+                    LISTINGS_LANGUAGES.iter().find(|(name, _)| *name == language).map(|(_, listings)| *listings)
+                });
+                let (escape_char, code) = guard_listing_end(&code);
+                let mut options: Vec<String> = Vec::new();
+                options.extend(language.map(|language| format!("language={{{}}}", language)));
+                options.extend(escape_char.map(|c| format!("escapechar={}", c)));
+                let options = if options.is_empty() { String::new() } else { format!("[{}]", options.join(", ")) };
+                format!("\\begin{{lstlisting}}{}\n{}\n\\end{{lstlisting}}", options, code)
+            }
+            Node::Panel { content, attrs } => {
+                let (background, frame, title) = panel_style(attrs.panel_type());
+                let title = title.map(|title| format!(", title={{{}}}", title)).unwrap_or_default();
+                let body = self.blocks(content, Context { in_box: true, ..cx });
+                format!(
+                    "\\begin{{tcolorbox}}[colback={{{}}}, colframe={{{}}}{}]\n{}\n\\end{{tcolorbox}}",
+                    color_spec(background),
+                    color_spec(frame),
+                    title,
+                    body
+                )
+            }
+            Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+                let title = match attrs.title().trim() {
+                    "" => String::new(),
+                    title => format!(", title={{{}}}", escape(title)),
+                };
+                let body = self.blocks(content, Context { in_box: true, ..cx });
+                format!(
+                    "\\begin{{tcolorbox}}[colback=white, colframe={{{}}}{}]\n{}\n\\end{{tcolorbox}}",
+                    color_spec(Color::new(0x6b, 0x77, 0x8c)),
+                    title,
+                    body
+                )
+            }
+            Node::Rule => "\\noindent\\rule{\\linewidth}{0.4pt}".to_string(),
+            Node::Table { .. } => self.table(node, cx),
+            Node::MediaSingle { content, .. } | Node::MediaGroup { content } => self.blocks(content, cx),
+            Node::Media { attrs } => {
+                let (path, alt, width) = match self.options.resolvers.media(attrs) {
+                    Some(info) => (info.url, info.alt, info.width),
+                    None => (attrs.id().to_string(), None, None),
+                };
+                let fraction = width.map_or(0.5, |width| (f64::from(width) / PAGE_WIDTH).min(1.0));
+                let mut label = format!("Image: \\texttt{{{}}}", escape(&path).replace('/', "/\\allowbreak{}"));
+                if let Some(alt) = alt.filter(|alt| !alt.is_empty()) {
+                    label.push_str(&format!("\\\\ {}", escape(&alt)));
+                }
+                format!(
+                    "\\begin{{center}}\n\\fbox{{\\parbox{{{:.3}\\linewidth}}{{\\centering {}}}}}\n\\end{{center}}",
+                    fraction, label
+                )
+            }
+            inline_node => self.inline(std::slice::from_ref(inline_node)),
+        }
+    }
+
+    fn list(&self, list: &Node, cx: Context) -> String {
+        let start = match list {
+            Node::OrderedList { attrs, .. } => attrs.as_ref().map_or(1, |attrs| attrs.order()) as usize,
+            _ => 1,
+        };
+        let inner = Context { lists: cx.lists + 1, ..cx };
+        let mut items = Vec::new();
+        for (index, item) in list.content().into_iter().flatten().enumerate() {
+            // Every list is an `itemize` with its own labels, so ordered
+            // lists keep their start and nesting depth is counted once.
+            let (label, body) = match item {
+                Node::TaskItem { content, attrs } => {
+                    let check = if *attrs.state() == TaskState::Done { "$\\boxtimes$" } else { "$\\square$" };
+                    (Some(check.to_string()), self.inline(content))
+                }
+                Node::DecisionItem { content, .. } => (Some("$\\Rightarrow$".to_string()), self.inline(content)),
+                Node::TaskList { .. } => (Some(String::new()), self.list(item, inner)),
+                _ if matches!(list, Node::OrderedList { .. }) => {
+                    (Some(format!("{}.", start + index)), self.block(item, inner))
+                }
+                _ => (None, self.block(item, inner)),
+            };
+            items.push((label, body));
+        }
+        if items.is_empty() {
+            return String::new();
+        }
+        if cx.lists >= MAX_LIST_DEPTH {
+            // Too deep for LaTeX: the items become paragraphs led by their labels.
+            return items
+                .into_iter()
+                .map(|(label, body)| format!("{} {}", label.unwrap_or_else(|| "\\textbullet{}".to_string()), body))
+                .collect::<Vec<_>>()
+                .join(cx.separator());
+        }
+        let items: Vec<String> = items
+            .into_iter()
+            .map(|(label, body)| match label {
+                Some(label) => format!("\\item[{}] {}", label, body),
+                // A bracket right after `\item` would be read as a label.
+                None if body.starts_with('[') => format!("\\item{{}} {}", body),
+                None => format!("\\item {}", body),
+            })
+            .collect();
+        format!("\\begin{{itemize}}\n{}\n\\end{{itemize}}", items.join("\n"))
+    }
+
+    fn table(&self, table: &Node, cx: Context) -> String {
+        let Ok(grid) = TableGrid::new(table) else {
+            return self.blocks(table.content().map(Vec::as_slice).unwrap_or_default(), cx);
+        };
+        let (rows, columns) = (grid.rows(), grid.columns());
+        if rows == 0 || columns == 0 {
+            return String::new();
+        }
+        let fractions = grid.column_fractions();
+        // A column of the given share of the line, less its padding.
+        let column_spec = |fraction: f64| format!("p{{\\dimexpr {:.3}\\linewidth-2\\tabcolsep\\relax}}", fraction);
+        let spec: String = fractions.iter().map(|fraction| format!("|{}", column_spec(*fraction))).collect();
+        let environment = if cx.allows_long_table() { "longtable" } else { "tabular" };
+        let header = grid.header_rows() > 0 && grid.header_rows() < rows;
+        let inner = Context { in_table: true, ..cx };
+
+        let mut out = format!("\\begin{{{}}}{{{}|}}\n\\hline", environment, spec);
+        for row in 0..rows {
+            let mut cells = Vec::new();
+            let mut column = 0;
+            while column < columns {
+                let Some(cell) = grid.cell(row, column) else {
+                    cells.push(String::new());
+                    column += 1;
+                    continue;
+                };
+                let mut text = String::new();
+                // Rows below the first of a rowspan leave its slots empty.
+                if cell.row == row {
+                    text = self.blocks(cell.content(), inner);
+                    if cell.is_header() && !text.is_empty() {
+                        text = format!("\\bfseries {}", text);
+                    }
+                    if cell.rowspan > 1 {
+                        text = format!("\\multirow{{{}}}{{=}}{{{}}}", cell.rowspan, text);
+                    }
+                }
+                if cell.colspan > 1 {
+                    let fraction = fractions[column..column + cell.colspan].iter().sum();
+                    let left = if column == 0 { "|" } else { "" };
+                    let spec = format!("{}{}|", left, column_spec(fraction));
+                    text = format!("\\multicolumn{{{}}}{{{}}}{{{}}}", cell.colspan, spec, text);
+                }
+                cells.push(text);
+                column += cell.colspan;
+            }
+            out.push_str(&format!("\n{} \\\\\n", cells.join(" & ")));
+            out.push_str(&row_rule(&grid, row));
+            if header && environment == "longtable" && row + 1 == grid.header_rows() {
+                out.push_str("\n\\endhead");
+            }
+        }
+        out.push_str(&format!("\n\\end{{{}}}", environment));
+        out
+    }
+
+    fn inline(&self, content: &[Node]) -> String {
+        let mut out = String::new();
+        for node in content {
+            match node {
+                Node::Text { text, marks } => {
+                    out.push_str(&text_with_marks(text, marks.as_deref().unwrap_or_default()))
+                }
+                Node::HardBreak { .. } => out.push_str("\\newline\n"),
+                Node::Mention { attrs } => {
+                    let user = self.options.resolvers.user(attrs);
+                    let name = resolve::mention_name(user.as_ref(), attrs);
+                    match user.and_then(|user| user.profile_url) {
+                        Some(url) => out.push_str(&format!("\\href{{{}}}{{{}}}", escape_url(&url), escape(&name))),
+                        None => out.push_str(&escape(&name)),
+                    }
+                }
+                Node::Emoji { attrs } => {
+                    let text = self.options.custom_emoji.text(attrs);
+                    out.push_str(&escape(if text.is_ascii() { &text } else { attrs.short_name() }));
+                }
+                Node::Date { attrs } => match attrs.parsed_timestamp() {
+                    Ok(timestamp) => out.push_str(&escape(&self.options.date_format.format(timestamp))),
+                    Err(_) => out.push_str(&escape(attrs.timestamp())),
+                },
+                Node::Status { attrs } => {
+                    out.push_str(&format!("\\fbox{{\\footnotesize {}}}", escape(&attrs.text().to_uppercase())))
+                }
+                Node::InlineCard { attrs } => {
+                    let url = attrs.url().unwrap_or_default();
+                    let text = match self.options.resolvers.card(url) {
+                        Some(card) => escape(&card.title),
+                        None => format!("\\texttt{{{}}}", escape(url).replace('/', "/\\allowbreak{}")),
+                    };
+                    out.push_str(&format!("\\href{{{}}}{{{}}}", escape_url(url), text));
+                }
+                other => out.push_str(&escape(&other.text_content())),
+            }
+        }
+        out
+    }
+}
+
+fn text_with_marks(text: &str, marks: &[Mark]) -> String {
+    let mut out = if marks.contains(&Mark::Code) { format!("\\texttt{{{}}}", escape(text)) } else { escape(text) };
+    for mark in marks {
+        out = match mark {
+            Mark::SubSup { attrs: SubSupAttrs::Sub } => format!("\\textsubscript{{{}}}", out),
+            Mark::SubSup { attrs: SubSupAttrs::Sup } => format!("\\textsuperscript{{{}}}", out),
+            _ => continue,
+        };
+    }
+    for (mark, command) in [
+        (Mark::Strike, "sout"),
+        (Mark::Underline, "uline"),
+        (Mark::Em, "emph"),
+        (Mark::Strong, "textbf"),
+    ] {
+        if marks.contains(&mark) {
+            out = format!("\\{}{{{}}}", command, out);
+        }
+    }
+    for mark in marks {
+        out = match mark {
+            Mark::TextColor { attrs } => format!("\\textcolor{{{}}}{{{}}}", color_spec(attrs.color()), out),
+            Mark::BackgroundColor { attrs } => format!("\\colorbox{{{}}}{{{}}}", color_spec(attrs.color()), out),
+            _ => continue,
+        };
+    }
+    for mark in marks {
+        if let Mark::Link { attrs } = mark {
+            out = format!("\\href{{{}}}{{{}}}", escape_url(attrs.href()), out);
+        }
+    }
+    out
+}
+
+// Rule under a row, broken where a rowspan continues into the next row.
+fn row_rule(grid: &TableGrid, row: usize) -> String {
+    let closed: Vec<bool> = (0..grid.columns())
+        .map(|column| grid.cell(row, column).is_none_or(|cell| cell.row + cell.rowspan - 1 == row))
+        .collect();
+    if closed.iter().all(|closed| *closed) {
+        return "\\hline".to_string();
+    }
+    let mut rules = Vec::new();
+    let mut column = 0;
+    while column < closed.len() {
+        if closed[column] {
+            let start = column;
+            while column < closed.len() && closed[column] {
+                column += 1;
+            }
+            rules.push(format!("\\cline{{{}-{}}}", start + 1, column));
+        } else {
+            column += 1;
+        }
+    }
+    rules.join(" ")
+}
+
+fn panel_style(panel_type: &PanelType) -> (Color, Color, Option<&'static str>) {
+    match panel_type {
+        PanelType::Info => (Color::new(0xde, 0xeb, 0xff), Color::new(0x00, 0x52, 0xcc), Some("Info")),
+        PanelType::Note => (Color::new(0xea, 0xe6, 0xff), Color::new(0x52, 0x43, 0xaa), Some("Note")),
+        PanelType::Success => (Color::new(0xe3, 0xfc, 0xef), Color::new(0x00, 0x87, 0x5a), Some("Success")),
+        PanelType::Warning => (Color::new(0xff, 0xfa, 0xe6), Color::new(0xff, 0x8b, 0x00), Some("Warning")),
+        PanelType::Error => (Color::new(0xff, 0xeb, 0xe6), Color::new(0xde, 0x35, 0x0b), Some("Error")),
+        PanelType::Custom | PanelType::Other(_) => (Color::new(0xf4, 0xf5, 0xf7), Color::new(0x6b, 0x77, 0x8c), None),
+    }
+}
+
+// An `xcolor` color expression, usable without defining a named color.
+fn color_spec(color: Color) -> String {
+    format!("rgb,255:red,{};green,{};blue,{}", color.r, color.g, color.b)
+}
+
+// A listing ends at the first `\end{lstlisting}` in it, even one that is part
+// of the code. Its backslash is typeset through an escape character that
+// does not otherwise occur in the code, which hides the terminator from
+// `listings`. Code holding every candidate has a space put after the
+// backslash instead.
+fn guard_listing_end(code: &str) -> (Option<char>, String) {
+    const END: &str = "\\end{lstlisting}";
+    if !code.contains(END) {
+        return (None, code.to_string());
+    }
+    match "`|!@?".chars().find(|c| !code.contains(*c)) {
+        Some(c) => (Some(c), code.replace(END, &format!("{}\\textbackslash{{}}{}end{{lstlisting}}", c, c))),
+        None => (None, code.replace(END, "\\ end{lstlisting}")),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// `\href` takes `#` and `%` escaped; characters that would unbalance the
+// argument are percent-encoded.
+fn escape_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            '#' | '%' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\\' => out.push_str("\\%5C"),
+            '{' => out.push_str("\\%7B"),
+            '}' => out.push_str("\\%7D"),
+            ' ' => out.push_str("\\%20"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod latex_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+    use crate::model::{ColorAttrs, LinkAttrs, PanelAttrs, TableCellAttrs};
+
+    #[test]
+    fn should_render_blocks_and_marks() {
+        let doc = Node::doc(vec![
+            Node::heading(2, vec![Node::text("Scope & goals")]),
+            Node::paragraph(vec![
+                Node::text_with_marks("red", vec![Mark::TextColor { attrs: ColorAttrs::new(Color::new(255, 0, 0)) }]),
+                Node::text(" costs 5$ for 10% of_all "),
+                Node::text_with_marks("docs", vec![
+                    Mark::Strong,
+                    Mark::Link { attrs: LinkAttrs::new("https://d.example/#a") },
+                ]),
+                Node::HardBreak { attrs: None },
+            ]),
+            Node::ordered_list(vec![
+                Node::list_item(vec![paragraph("first")]),
+                Node::list_item(vec![paragraph("second")]),
+            ]),
+            Node::code_block(Some("Python"), "print('}')"),
+            Node::Panel { content: vec![paragraph("Mind it")], attrs: PanelAttrs::new(PanelType::Warning) },
+        ]);
+
+        assert_eq!(
+            to_latex(&doc, &LatexOptions::default()),
+            concat!(
+                "\\subsection*{Scope \\& goals}\n\n",
+                "\\textcolor{rgb,255:red,255;green,0;blue,0}{red} costs 5\\$ for 10\\% of\\_all ",
+                "\\href{https://d.example/\\#a}{\\textbf{docs}}\n\n",
+                "\\begin{itemize}\n\\item[1.] first\n\\item[2.] second\n\\end{itemize}\n\n",
+                "\\begin{lstlisting}[language={Python}]\nprint('}')\n\\end{lstlisting}\n\n",
+                "\\begin{tcolorbox}[colback={rgb,255:red,255;green,250;blue,230}, ",
+                "colframe={rgb,255:red,255;green,139;blue,0}, title={Warning}]\nMind it\n\\end{tcolorbox}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_keep_unicode_and_hide_listing_terminators() {
+        let doc = Node::doc(vec![
+            paragraph("✅ done → 完了"),
+            Node::code_block(None, "s = \"→\"\n\\end{lstlisting}"),
+            Node::code_block(Some("tex"), "`|!@?\\end{lstlisting}"),
+        ]);
+
+        assert_eq!(
+            to_latex(&doc, &LatexOptions::default()),
+            concat!(
+                "✅ done → 完了\n\n",
+                "\\begin{lstlisting}[escapechar=`]\ns = \"→\"\n",
+                "`\\textbackslash{}`end{lstlisting}\n\\end{lstlisting}\n\n",
+                "\\begin{lstlisting}[language={TeX}]\n`|!@?\\ end{lstlisting}\n\\end{lstlisting}\n",
+            )
+        );
+        assert!(PREAMBLE.contains("fontspec") && !PREAMBLE.contains("inputenc"));
+    }
+
+    #[test]
+    fn should_render_tables_with_widths_and_spans() {
+        let table = Node::table(vec![
+            Node::table_row(vec![
+                Node::TableHeader {
+                    content: vec![paragraph("Key")],
+                    attrs: Some(TableCellAttrs::default().with_colwidth(vec![190])),
+                },
+                Node::TableHeader {
+                    content: vec![paragraph("Value")],
+                    attrs: Some(TableCellAttrs::default().with_colwidth(vec![570])),
+                },
+            ]),
+            Node::table_row(vec![
+                Node::TableCell {
+                    content: vec![paragraph("tall"), paragraph("more")],
+                    attrs: Some(TableCellAttrs::default().with_rowspan(2)),
+                },
+                Node::table_cell(vec![paragraph("a")]),
+            ]),
+            Node::table_row(vec![Node::table_cell(vec![paragraph("b")])]),
+        ]);
+
+        assert_eq!(
+            to_latex(&table, &LatexOptions::default()),
+            concat!(
+                "\\begin{longtable}{|p{\\dimexpr 0.250\\linewidth-2\\tabcolsep\\relax}",
+                "|p{\\dimexpr 0.750\\linewidth-2\\tabcolsep\\relax}|}\n\\hline\n",
+                "\\bfseries Key & \\bfseries Value \\\\\n\\hline\n\\endhead\n",
+                "\\multirow{2}{=}{tall\n\\endgraf\nmore} & a \\\\\n\\cline{2-2}\n",
+                " & b \\\\\n\\hline\n",
+                "\\end{longtable}\n",
+            )
+        );
+    }
+}
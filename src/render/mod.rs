@@ -0,0 +1,16 @@
+#[cfg(feature = "ansi")]
+pub mod ansi;
+#[cfg(feature = "asciidoc")]
+pub mod asciidoc;
+#[cfg(feature = "email")]
+pub mod email;
+#[cfg(feature = "html")]
+pub mod html;
+#[cfg(feature = "latex")]
+pub mod latex;
+#[cfg(feature = "rst")]
+pub mod rst;
+#[cfg(feature = "slack")]
+pub mod slack;
+#[cfg(feature = "typst")]
+pub mod typst;
@@ -0,0 +1,576 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::date::DateFormat;
+use crate::emoji::CustomEmojiFallback;
+use crate::model::{Mark, Node, PanelType, SubSupAttrs, TaskState};
+use crate::resolve::{self, Resolvers};
+use crate::table::TableGrid;
+
+// Underlines for section depths 1 to 6, in the order Sphinx projects
+// commonly use them.
+const UNDERLINES: [char; 6] = ['=', '-', '~', '^', '"', '\''];
+
+#[derive(Debug, Clone, Default)]
+pub struct RstOptions {
+    /// Format and timezone used to display `date` nodes.
+    pub date_format: DateFormat,
+    /// How custom emoji appear. Inline images need substitution definitions,
+    /// so an image fallback shows the short name.
+    pub custom_emoji: CustomEmojiFallback,
+    /// Looks up user names, media URLs and link titles.
+    pub resolvers: Resolvers,
+}
+
+/// reStructuredText for a node, using the Sphinx `code-block` directive for
+/// code with a language.
+///
+/// Panels become `note`, `tip`, `warning` and `error` admonitions and
+/// expands generic `admonition`s with their title. Tables are written as grid
+/// tables, which keep both row and column spans. Paragraphs with hard breaks
+/// become line blocks. reST inline markup does not nest, so text with several
+/// marks keeps the one that matters most: a link, then code, strong, emphasis
+/// and subscript or superscript.
+///
+/// Top-level headings become section titles, nested as in
+/// [`outline`](crate::outline::outline) so that skipped levels do not skip
+/// an underline. Headings inside other blocks cannot start a section and
+/// become `rubric` directives.
+pub fn to_rst(node: &Node, options: &RstOptions) -> String {
+    let mut out = Writer { options }.block(node);
+    out.push('\n');
+    out
+}
+
+struct Writer<'a> {
+    options: &'a RstOptions,
+}
+
+impl Writer<'_> {
+    fn blocks(&self, content: &[Node]) -> String {
+        content.iter().map(|node| self.block(node)).filter(|text| !text.is_empty()).collect::<Vec<_>>().join("\n\n")
+    }
+
+    fn block(&self, node: &Node) -> String {
+        match node {
+            Node::Doc { content, .. } => self.sections(content),
+            Node::ListItem { content } => self.blocks(content),
+            Node::Paragraph { content, .. } => {
+                if content.iter().any(|node| matches!(node, Node::HardBreak { .. })) {
+                    content
+                        .split(|node| matches!(node, Node::HardBreak { .. }))
+                        .map(|line| format!("| {}", self.inline(line)).trim_end().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                } else {
+                    guard_line_start(self.inline(content))
+                }
+            }
+            Node::Heading { content, .. } => {
+                let text = self.inline(content);
+                if text.is_empty() {
+                    return text;
+                }
+                format!(".. rubric:: {}", text)
+            }
+            Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } | Node::DecisionList { .. } => {
+                self.list(node)
+            }
+            Node::Blockquote { content } => indent(&self.blocks(content), "   "),
+            Node::CodeBlock { content, attrs } => {
+                let code: String = content.iter().flatten().map(Node::text_content).collect();
+                if code.trim().is_empty() {
+                    return String::new();
+                }
+                match attrs.as_ref().map(|attrs| attrs.language()).filter(|language| !language.is_empty()) {
+                    Some(language) => format!(".. code-block:: {}\n\n{}", language, indent(&code, "   ")),
+                    None => format!("::\n\n{}", indent(&code, "   ")),
+                }
+            }
+            Node::Panel { content, attrs } => {
+                let directive = match attrs.panel_type() {
+                    PanelType::Success => "tip",
+                    PanelType::Warning => "warning",
+                    PanelType::Error => "error",
+                    _ => "note",
+                };
+                format!(".. {}::\n\n{}", directive, indent(&self.blocks(content), "   "))
+            }
+            Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+                let title = match attrs.title().trim() {
+                    "" => "Details",
+                    title => title,
+                };
+                format!(".. admonition:: {}\n\n{}", title, indent(&self.blocks(content), "   "))
+            }
+            Node::Rule => "----".to_string(),
+            Node::Table { .. } => self.table(node),
+            Node::MediaSingle { content, .. } | Node::MediaGroup { content } => self.blocks(content),
+            Node::Media { attrs } => match self.options.resolvers.media(attrs) {
+                Some(info) => {
+                    let mut out = format!(".. image:: {}", escape_url(&info.url));
+                    if let Some(alt) = info.alt {
+                        out.push_str(&format!("\n   :alt: {}", alt));
+                    }
+                    if let Some(width) = info.width {
+                        out.push_str(&format!("\n   :width: {}px", width));
+                    }
+                    out
+                }
+                None => format!(".. image:: {}", attrs.id()),
+            },
+            inline_node => self.inline(std::slice::from_ref(inline_node)),
+        }
+    }
+
+    // docutils ranks underlines in the order they first appear and rejects a
+    // new one more than a level below the current section, so they follow
+    // the depth of each heading rather than its level.
+    fn sections(&self, content: &[Node]) -> String {
+        let mut open: Vec<i8> = Vec::new();
+        let mut blocks = Vec::new();
+        for node in content {
+            let Node::Heading { content, attrs } = node else {
+                blocks.push(self.block(node));
+                continue;
+            };
+            let text = self.inline(content);
+            if text.is_empty() {
+                continue;
+            }
+            while open.last().is_some_and(|level| *level >= attrs.level()) {
+                open.pop();
+            }
+            open.push(attrs.level());
+            let underline = UNDERLINES[open.len().min(UNDERLINES.len()) - 1];
+            blocks.push(format!("{}\n{}", text, underline.to_string().repeat(text.width())));
+        }
+        blocks.retain(|text| !text.is_empty());
+        blocks.join("\n\n")
+    }
+
+    fn list(&self, list: &Node) -> String {
+        let start = match list {
+            Node::OrderedList { attrs, .. } => attrs.as_ref().map_or(1, |attrs| attrs.order()) as usize,
+            _ => 1,
+        };
+        let mut items = Vec::new();
+        for (index, item) in list.content().into_iter().flatten().enumerate() {
+            let (marker, body) = match item {
+                Node::TaskItem { content, attrs } => {
+                    let check = if *attrs.state() == TaskState::Done { "[x]" } else { "[ ]" };
+                    ("- ".to_string(), format!("{} {}", check, self.inline(content)))
+                }
+                Node::DecisionItem { content, .. } => ("- ".to_string(), self.inline(content)),
+                // A task list nested in another sits among its items.
+                Node::TaskList { .. } => {
+                    items.push(indent(&self.list(item), "  "));
+                    continue;
+                }
+                _ if matches!(list, Node::OrderedList { .. }) => (format!("{}. ", start + index), self.block(item)),
+                _ => ("- ".to_string(), self.block(item)),
+            };
+            // Continuation lines line up with the text after the marker.
+            let hanging = indent(&body, &" ".repeat(marker.len()));
+            items.push(format!("{}{}", marker, hanging.trim_start()));
+        }
+        // Items holding more than a line need blank lines between them.
+        let separator = if items.iter().any(|item| item.contains('\n')) { "\n\n" } else { "\n" };
+        items.join(separator)
+    }
+
+    fn table(&self, table: &Node) -> String {
+        let Ok(grid) = TableGrid::new(table) else {
+            return self.blocks(table.content().map(Vec::as_slice).unwrap_or_default());
+        };
+        let (rows, columns) = (grid.rows(), grid.columns());
+        if rows == 0 || columns == 0 {
+            return String::new();
+        }
+        // Every cell once, at its top left corner. Gaps in a ragged table
+        // are filled with empty cells.
+        let mut cells = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                match grid.cell(row, column) {
+                    Some(cell) if cell.row == row && cell.column == column => {
+                        let lines = self.blocks(cell.content()).lines().map(str::to_string).collect();
+                        cells.push(Placed { row, column, rowspan: cell.rowspan, colspan: cell.colspan, lines });
+                    }
+                    Some(_) => {}
+                    None => cells.push(Placed { row, column, rowspan: 1, colspan: 1, lines: Vec::new() }),
+                }
+            }
+        }
+
+        // Size columns and rows for unspanned cells first, then widen the
+        // last column or row a spanning cell covers if it still does not fit.
+        let mut widths = vec![1; columns];
+        let mut heights = vec![1; rows];
+        for spanning in [false, true] {
+            for cell in cells.iter().filter(|cell| (cell.colspan > 1) == spanning) {
+                let width = cell.lines.iter().map(|line| slots(line)).max().unwrap_or(0);
+                let last = cell.column + cell.colspan - 1;
+                let available = widths[cell.column..=last].iter().sum::<usize>() + 3 * (cell.colspan - 1);
+                widths[last] += width.saturating_sub(available);
+            }
+            for cell in cells.iter().filter(|cell| (cell.rowspan > 1) == spanning) {
+                let last = cell.row + cell.rowspan - 1;
+                let available = heights[cell.row..=last].iter().sum::<usize>() + cell.rowspan - 1;
+                heights[last] += cell.lines.len().saturating_sub(available);
+            }
+        }
+        let xs: Vec<usize> = std::iter::once(0).chain(widths.iter().scan(0, |x, width| {
+            *x += width + 3;
+            Some(*x)
+        })).collect();
+        let ys: Vec<usize> = std::iter::once(0).chain(heights.iter().scan(0, |y, height| {
+            *y += height + 1;
+            Some(*y)
+        })).collect();
+        let header = (grid.header_rows() > 0 && grid.header_rows() < rows).then(|| ys[grid.header_rows()]);
+
+        // Each slot holds what is printed in one column of the terminal, so
+        // a wide character leaves the slot after it empty.
+        let mut canvas = vec![vec![" ".to_string(); xs[columns] + 1]; ys[rows] + 1];
+        for cell in &cells {
+            let (x0, x1) = (xs[cell.column], xs[cell.column + cell.colspan]);
+            let (y0, y1) = (ys[cell.row], ys[cell.row + cell.rowspan]);
+            for y in [y0, y1] {
+                let edge = if Some(y) == header { '=' } else { '-' };
+                for x in x0 + 1..x1 {
+                    draw(&mut canvas, x, y, edge);
+                }
+                draw(&mut canvas, x0, y, '+');
+                draw(&mut canvas, x1, y, '+');
+            }
+            for y in y0 + 1..y1 {
+                draw(&mut canvas, x0, y, '|');
+                draw(&mut canvas, x1, y, '|');
+            }
+            for (index, line) in cell.lines.iter().enumerate() {
+                let row = &mut canvas[y0 + 1 + index];
+                let mut x = x0 + 2;
+                for c in line.chars() {
+                    match c.width().unwrap_or(0) {
+                        0 => row[x.saturating_sub(1).max(x0 + 2)].push(c),
+                        width => {
+                            row[x] = c.to_string();
+                            for slot in &mut row[x + 1..x + width] {
+                                slot.clear();
+                            }
+                            x += width;
+                        }
+                    }
+                }
+            }
+        }
+        canvas.iter().map(|row| row.concat()).collect::<Vec<_>>().join("\n")
+    }
+
+    fn inline(&self, content: &[Node]) -> String {
+        let mut pieces = Vec::new();
+        for node in content {
+            match node {
+                Node::Text { text, marks } => self.text(text, marks.as_deref().unwrap_or_default(), &mut pieces),
+                Node::HardBreak { .. } => pieces.push(Piece::Plain(" ".to_string())),
+                Node::Mention { attrs } => {
+                    let user = self.options.resolvers.user(attrs);
+                    let name = resolve::mention_name(user.as_ref(), attrs);
+                    match user.and_then(|user| user.profile_url) {
+                        Some(url) => pieces.push(Piece::Markup(link(&name, &url))),
+                        None => pieces.push(Piece::Plain(escape(&name))),
+                    }
+                }
+                Node::Emoji { attrs } => pieces.push(Piece::Plain(escape(&self.options.custom_emoji.text(attrs)))),
+                Node::Date { attrs } => {
+                    let text = match attrs.parsed_timestamp() {
+                        Ok(timestamp) => self.options.date_format.format(timestamp),
+                        Err(_) => attrs.timestamp().to_string(),
+                    };
+                    pieces.push(Piece::Plain(escape(&text)));
+                }
+                Node::Status { attrs } => pieces.push(Piece::Markup(format!("``{}``", attrs.text().to_uppercase()))),
+                Node::InlineCard { attrs } => {
+                    let url = attrs.url().unwrap_or_default();
+                    match self.options.resolvers.card(url) {
+                        Some(card) => pieces.push(Piece::Markup(link(&card.title, url))),
+                        // reST links a standalone URL by itself.
+                        None => pieces.push(Piece::Markup(escape_url(url))),
+                    }
+                }
+                other => pieces.push(Piece::Plain(escape(&other.text_content()))),
+            }
+        }
+        join_pieces(&pieces)
+    }
+
+    fn text(&self, text: &str, marks: &[Mark], pieces: &mut Vec<Piece>) {
+        // Markup may not start or end with whitespace, so it stays outside.
+        let core = text.trim();
+        let start = text.len() - text.trim_start().len();
+        let (before, after) = (&text[..start], &text[start + core.len()..]);
+        let link_href = marks.iter().find_map(|mark| match mark {
+            Mark::Link { attrs } => Some(attrs.href()),
+            _ => None,
+        });
+        let markup = if core.is_empty() {
+            None
+        } else if let Some(href) = link_href {
+            Some(link(core, href))
+        } else if marks.contains(&Mark::Code) {
+            Some(format!("``{}``", core))
+        } else if marks.contains(&Mark::Strong) {
+            Some(format!("**{}**", escape(core)))
+        } else if marks.contains(&Mark::Em) {
+            Some(format!("*{}*", escape(core)))
+        } else if marks.contains(&Mark::SubSup { attrs: SubSupAttrs::Sub }) {
+            Some(format!(":sub:`{}`", escape(core)))
+        } else if marks.contains(&Mark::SubSup { attrs: SubSupAttrs::Sup }) {
+            Some(format!(":sup:`{}`", escape(core)))
+        } else {
+            None
+        };
+        match markup {
+            Some(markup) => {
+                pieces.push(Piece::Plain(before.to_string()));
+                pieces.push(Piece::Markup(markup));
+                pieces.push(Piece::Plain(after.to_string()));
+            }
+            None => pieces.push(Piece::Plain(escape(text))),
+        }
+    }
+}
+
+// Slots a line takes on the canvas. Summed per character, as the canvas is
+// filled, so joined sequences such as 👨‍👩‍👧 cannot overrun their cell.
+fn slots(line: &str) -> usize {
+    line.chars().map(|c| c.width().unwrap_or(0)).sum()
+}
+
+// Corners always win over the edges passing through them.
+fn draw(canvas: &mut [Vec<String>], x: usize, y: usize, c: char) {
+    if c == '+' || canvas[y][x] == " " {
+        canvas[y][x] = c.to_string();
+    }
+}
+
+struct Placed {
+    row: usize,
+    column: usize,
+    rowspan: usize,
+    colspan: usize,
+    lines: Vec<String>,
+}
+
+enum Piece {
+    Plain(String),
+    Markup(String),
+}
+
+// Inline markup is only recognised next to whitespace or certain
+// punctuation. Elsewhere an escaped space, which renders as nothing, is put
+// between it and the text around it.
+fn join_pieces(pieces: &[Piece]) -> String {
+    let mut out = String::new();
+    let mut after_markup = false;
+    for piece in pieces {
+        match piece {
+            Piece::Plain(text) => {
+                if after_markup && text.starts_with(|c: char| !c.is_whitespace() && !"'\")]}>-/:.,;!?\\".contains(c)) {
+                    out.push_str("\\ ");
+                }
+                out.push_str(text);
+                after_markup = after_markup && text.is_empty();
+            }
+            Piece::Markup(markup) => {
+                let opens = out.chars().next_back().is_none_or(|c| c.is_whitespace() || "'\"([{<-/:".contains(c));
+                if !opens || after_markup {
+                    out.push_str("\\ ");
+                }
+                out.push_str(markup);
+                after_markup = true;
+            }
+        }
+    }
+    out
+}
+
+fn link(text: &str, url: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '<') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    format!("`{} <{}>`__", escaped, escape_url(url))
+}
+
+// Characters that would end or change the target of a link; a trailing `_`
+// would make it a reference name.
+fn escape_url(url: &str) -> String {
+    let escaped =
+        url.replace('\\', "%5C").replace(' ', "%20").replace('<', "%3C").replace('>', "%3E").replace('`', "%60");
+    match escaped.strip_suffix('_') {
+        Some(url) => format!("{}%5F", url),
+        None => escaped,
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '`' | '_' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("{}{}", prefix, line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// A paragraph starting like a list item, directive or literal block marker
+// would be read as one.
+fn guard_line_start(text: String) -> String {
+    let marker = text.split_once(' ').map_or(text.as_str(), |(first, _)| first);
+    let enumerator = marker
+        .strip_suffix(['.', ')'])
+        .is_some_and(|number| number == "#" || (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())));
+    if matches!(marker, "-" | "+" | "•" | "..") || enumerator || text.starts_with("::") {
+        format!("\\{}", text)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod rst_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+    use crate::model::{LinkAttrs, PanelAttrs, TableCellAttrs};
+
+    #[test]
+    fn should_render_blocks_and_marks() {
+        let doc = Node::doc(vec![
+            Node::heading(2, vec![Node::text("Design")]),
+            Node::paragraph(vec![
+                Node::text("Set "),
+                Node::text_with_marks("max_size", vec![Mark::Code]),
+                Node::text("s and "),
+                Node::text_with_marks("read ", vec![Mark::Strong, Mark::Em]),
+                Node::text_with_marks("docs", vec![Mark::Link { attrs: LinkAttrs::new("https://d.example") }]),
+                Node::text(". Use *_args."),
+            ]),
+            Node::paragraph(vec![Node::text("1. not a list")]),
+            Node::bullet_list(vec![
+                Node::list_item(vec![
+                    paragraph("one"),
+                    Node::ordered_list(vec![Node::list_item(vec![paragraph("nested")])]),
+                ]),
+                Node::list_item(vec![paragraph("two")]),
+            ]),
+            Node::code_block(Some("python"), "print(1)"),
+            Node::Panel { content: vec![paragraph("Mind it")], attrs: PanelAttrs::new(PanelType::Warning) },
+        ]);
+
+        assert_eq!(
+            to_rst(&doc, &RstOptions::default()),
+            concat!(
+                "Design\n======\n\n",
+                "Set ``max_size``\\ s and **read** `docs <https://d.example>`__. Use \\*\\_args.\n\n",
+                "\\1. not a list\n\n",
+                "- one\n\n  1. nested\n\n- two\n\n",
+                ".. code-block:: python\n\n   print(1)\n\n",
+                ".. warning::\n\n   Mind it\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_underline_sections_by_depth_and_nest_rubrics() {
+        let doc = Node::doc(vec![
+            Node::heading(1, vec![Node::text("Guide")]),
+            Node::heading(3, vec![Node::text("Install")]),
+            Node::Panel {
+                content: vec![Node::heading(2, vec![Node::text("Note")])],
+                attrs: PanelAttrs::new(PanelType::Info),
+            },
+            Node::heading(1, vec![Node::text("API")]),
+            Node::heading(2, vec![Node::text("Types")]),
+            Node::paragraph(vec![Node::text_with_marks(
+                "odd",
+                vec![Mark::Link { attrs: LinkAttrs::new("https://o.example/a>b`c d_") }],
+            )]),
+        ]);
+
+        assert_eq!(
+            to_rst(&doc, &RstOptions::default()),
+            concat!(
+                "Guide\n=====\n\n",
+                "Install\n-------\n\n",
+                ".. note::\n\n   .. rubric:: Note\n\n",
+                "API\n===\n\n",
+                "Types\n-----\n\n",
+                "`odd <https://o.example/a%3Eb%60c%20d%5F>`__\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_render_grid_tables_with_spans() {
+        let table = Node::table(vec![
+            Node::table_row(vec![
+                Node::table_header(vec![paragraph("Key")]),
+                Node::table_header(vec![paragraph("Value")]),
+                Node::table_header(vec![paragraph("Note")]),
+            ]),
+            Node::table_row(vec![
+                Node::TableCell {
+                    content: vec![paragraph("spans two columns")],
+                    attrs: Some(TableCellAttrs::default().with_colspan(2)),
+                },
+                Node::TableCell {
+                    content: vec![paragraph("tall")],
+                    attrs: Some(TableCellAttrs::default().with_rowspan(2)),
+                },
+            ]),
+            Node::table_row(vec![Node::table_cell(vec![paragraph("日本")]), Node::table_cell(vec![paragraph("x")])]),
+        ]);
+
+        assert_eq!(
+            to_rst(&table, &RstOptions::default()),
+            concat!(
+                "+------+------------+------+\n",
+                "| Key  | Value      | Note |\n",
+                "+======+============+======+\n",
+                "| spans two columns | tall |\n",
+                "+------+------------+      |\n",
+                "| 日本 | x          |      |\n",
+                "+------+------------+------+\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_size_cells_holding_joined_emoji() {
+        let table = Node::table(vec![Node::table_row(vec![
+            Node::table_cell(vec![paragraph("👨\u{200d}👩\u{200d}👧")]),
+            Node::table_cell(vec![paragraph("family")]),
+        ])]);
+
+        assert_eq!(
+            to_rst(&table, &RstOptions::default()),
+            concat!(
+                "+--------+--------+\n",
+                "| 👨\u{200d}👩\u{200d}👧 | family |\n",
+                "+--------+--------+\n",
+            )
+        );
+    }
+}
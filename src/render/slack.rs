@@ -0,0 +1,721 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::{json, Map, Value};
+
+use crate::date::DateFormat;
+use crate::emoji::{self, CustomEmojiFallback};
+use crate::model::{DateAttrs, EmojiAttrs, Mark, Node, PanelType, TaskState};
+use crate::resolve::Resolvers;
+use crate::table::TableGrid;
+
+// Limits Slack enforces on a message.
+const MAX_BLOCKS: usize = 50;
+const MAX_HEADER_CHARS: usize = 150;
+const MAX_TEXT_CHARS: usize = 3000;
+// Rich text styles for marks.
+const STYLES: [(Mark, &str); 4] =
+    [(Mark::Strong, "bold"), (Mark::Em, "italic"), (Mark::Strike, "strike"), (Mark::Code, "code")];
+// Wider cells are cut so that a table stays readable in a message.
+const MAX_CELL_CHARS: usize = 30;
+
+pub trait SlackUserResolver: Send + Sync {
+    /// The Slack member id (`U…`) of the user with the given Atlassian
+    /// account id, or `None` if they are not in the workspace.
+    fn slack_user_id(&self, account_id: &str) -> Option<String>;
+}
+
+#[derive(Clone, Default)]
+pub struct SlackOptions {
+    /// Maps mentioned users to Slack members, who are then mentioned in Slack
+    /// too. Anyone else is written as `@` and their name.
+    pub users: Option<Arc<dyn SlackUserResolver>>,
+    /// Format of the fallback text shown for dates by clients that cannot
+    /// localize them.
+    pub date_format: DateFormat,
+    /// How custom emoji, which Slack does not know, appear. Messages cannot
+    /// hold inline images, so an image fallback shows the short name.
+    pub custom_emoji: CustomEmojiFallback,
+    /// Looks up user names, media URLs and link titles.
+    pub resolvers: Resolvers,
+}
+
+impl fmt::Debug for SlackOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlackOptions")
+            .field("users", &self.users.is_some())
+            .field("date_format", &self.date_format)
+            .field("custom_emoji", &self.custom_emoji)
+            .field("resolvers", &self.resolvers)
+            .finish()
+    }
+}
+
+/// Slack `mrkdwn` text for a node.
+///
+/// Headings are written in bold, panels as quotes opened by an emoji, and
+/// tables as monospace text, with long cells and, past Slack's 3000
+/// character limit on text, trailing rows cut. Underline, colours and
+/// subscripts have no `mrkdwn` form and are dropped.
+pub fn to_mrkdwn(node: &Node, options: &SlackOptions) -> String {
+    Mrkdwn { options }.block(node)
+}
+
+/// Block Kit blocks for a node, as a JSON array to send as a message's
+/// `blocks`.
+///
+/// Headings become `header` blocks and rules `divider` blocks. Paragraphs,
+/// lists, code blocks and quotes share `rich_text` blocks. Panels, expands
+/// and tables, which Block Kit has no element for, become `section` blocks
+/// of [`to_mrkdwn`] text, and resolved images `image` blocks. Text is cut to
+/// fit Slack's limits, and past 50 blocks the rest is replaced by a note of
+/// how many were left out.
+pub fn to_block_kit(node: &Node, options: &SlackOptions) -> Value {
+    let mut builder = BlockKit { options, mrkdwn: Mrkdwn { options }, blocks: Vec::new(), rich: Vec::new() };
+    builder.block(node);
+    builder.flush();
+    let mut blocks = builder.blocks;
+    if blocks.len() > MAX_BLOCKS {
+        let hidden = blocks.len() - (MAX_BLOCKS - 1);
+        blocks.truncate(MAX_BLOCKS - 1);
+        blocks.push(section(&format!("_{} more blocks not shown_", hidden)));
+    }
+    Value::Array(blocks)
+}
+
+// ------ mrkdwn -------
+
+struct Mrkdwn<'a> {
+    options: &'a SlackOptions,
+}
+
+impl Mrkdwn<'_> {
+    fn blocks(&self, content: &[Node]) -> String {
+        content.iter().map(|node| self.block(node)).filter(|text| !text.is_empty()).collect::<Vec<_>>().join("\n\n")
+    }
+
+    fn block(&self, node: &Node) -> String {
+        match node {
+            Node::Doc { content, .. } | Node::ListItem { content } => self.blocks(content),
+            Node::Paragraph { content, .. } => self.inline(content),
+            Node::Heading { content, .. } => bold(&escape(&plain_text(content))),
+            Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } | Node::DecisionList { .. } => {
+                self.list(node, 0).join("\n")
+            }
+            Node::Blockquote { content } => quote(&self.blocks(content)),
+            Node::CodeBlock { content, .. } => {
+                let code: String = content.iter().flatten().map(Node::text_content).collect();
+                format!("```\n{}\n```", escape(&code))
+            }
+            Node::Panel { content, attrs } => {
+                let icon = match attrs.panel_type() {
+                    PanelType::Info => ":information_source:",
+                    PanelType::Success => ":white_check_mark:",
+                    PanelType::Warning => ":warning:",
+                    PanelType::Error => ":x:",
+                    _ => ":memo:",
+                };
+                quote(&format!("{} {}", icon, self.blocks(content)))
+            }
+            Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+                match attrs.title().trim() {
+                    "" => self.blocks(content),
+                    title => format!("{}\n{}", bold(&escape(title)), self.blocks(content)),
+                }
+            }
+            Node::Rule => "──────────".to_string(),
+            Node::Table { .. } => format!("```\n{}\n```", table_text(node)),
+            Node::MediaSingle { content, .. } | Node::MediaGroup { content } => {
+                content.iter().map(|media| self.block(media)).collect::<Vec<_>>().join("\n")
+            }
+            Node::Media { attrs } => match self.options.resolvers.media(attrs) {
+                Some(info) => format!("<{}|{}>", escape_url(&info.url), escape(info.alt.as_deref().unwrap_or("image"))),
+                None => ":paperclip: attachment".to_string(),
+            },
+            inline_node => self.inline(std::slice::from_ref(inline_node)),
+        }
+    }
+
+    fn list(&self, list: &Node, depth: usize) -> Vec<String> {
+        let indent = "    ".repeat(depth);
+        let start = match list {
+            Node::OrderedList { attrs, .. } => attrs.as_ref().map_or(1, |attrs| attrs.order()) as usize,
+            _ => 1,
+        };
+        let mut lines = Vec::new();
+        for (index, item) in list.content().into_iter().flatten().enumerate() {
+            let marker = match (list, item) {
+                (Node::OrderedList { .. }, _) => format!("{}.", start + index),
+                (_, Node::TaskItem { attrs, .. }) if *attrs.state() == TaskState::Done => "☑".to_string(),
+                (_, Node::TaskItem { .. }) => "☐".to_string(),
+                (_, Node::DecisionItem { .. }) => "◆".to_string(),
+                _ => "•".to_string(),
+            };
+            match item {
+                Node::TaskItem { content, .. } | Node::DecisionItem { content, .. } => {
+                    lines.push(format!("{}{} {}", indent, marker, self.inline(content)))
+                }
+                // Nested task lists sit directly in their parent list.
+                Node::TaskList { .. } => lines.extend(self.list(item, depth + 1)),
+                _ => {
+                    let mut first = true;
+                    for child in item.content().into_iter().flatten() {
+                        match child {
+                            Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } => {
+                                lines.extend(self.list(child, depth + 1))
+                            }
+                            _ if first => lines.push(format!("{}{} {}", indent, marker, self.block(child))),
+                            _ => {
+                                let nested = format!("{}    ", indent);
+                                lines.extend(self.block(child).lines().map(|line| format!("{}{}", nested, line)))
+                            }
+                        }
+                        first = false;
+                    }
+                    if first {
+                        lines.push(format!("{}{}", indent, marker));
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    fn inline(&self, content: &[Node]) -> String {
+        let mut out = String::new();
+        for node in content {
+            match node {
+                Node::Text { text, marks } => out.push_str(&self.text(text, marks.as_deref().unwrap_or_default())),
+                Node::HardBreak { .. } => out.push('\n'),
+                Node::Mention { attrs } => match slack_user(self.options, &attrs.id) {
+                    Some(user_id) => out.push_str(&format!("<@{}>", user_id)),
+                    None => out.push_str(&escape(&self.options.resolvers.mention_name(attrs))),
+                },
+                Node::Emoji { attrs } => out.push_str(&escape(&emoji_text(self.options, attrs))),
+                Node::Date { attrs } => match attrs.parsed_timestamp() {
+                    Ok(timestamp) => out.push_str(&format!(
+                        "<!date^{}^{{date_short}}|{}>",
+                        timestamp.as_seconds(),
+                        escape(&self.options.date_format.format(timestamp))
+                    )),
+                    Err(_) => out.push_str(&escape(attrs.timestamp())),
+                },
+                Node::Status { attrs } => out.push_str(&format!("`{}`", escape(&attrs.text().to_uppercase()))),
+                Node::InlineCard { attrs } => {
+                    let url = attrs.url().unwrap_or_default();
+                    match self.options.resolvers.card(url) {
+                        _ if url.is_empty() => {}
+                        Some(card) => out.push_str(&format!("<{}|{}>", escape_url(url), escape(&card.title))),
+                        None => out.push_str(&format!("<{}>", escape_url(url))),
+                    }
+                }
+                other => out.push_str(&escape(&other.text_content())),
+            }
+        }
+        out
+    }
+
+    fn text(&self, text: &str, marks: &[Mark]) -> String {
+        let mut out = escape(text);
+        if marks.contains(&Mark::Code) {
+            out = delimit(&out, "`");
+        } else {
+            for (mark, delimiter) in [(Mark::Strike, "~"), (Mark::Em, "_"), (Mark::Strong, "*")] {
+                if marks.contains(&mark) {
+                    out = delimit(&out, delimiter);
+                }
+            }
+        }
+        for mark in marks {
+            if let Mark::Link { attrs } = mark {
+                out = format!("<{}|{}>", escape_url(attrs.href()), out);
+            }
+        }
+        out
+    }
+}
+
+// Slack only reads a delimiter next to the text it formats, so surrounding
+// whitespace is kept outside.
+fn delimit(text: &str, delimiter: &str) -> String {
+    let core = text.trim();
+    if core.is_empty() {
+        return text.to_string();
+    }
+    let start = text.len() - text.trim_start().len();
+    let end = start + core.len();
+    format!("{}{}{}{}{}", &text[..start], delimiter, core, delimiter, &text[end..])
+}
+
+fn bold(text: &str) -> String {
+    delimit(text, "*")
+}
+
+fn quote(text: &str) -> String {
+    text.lines().map(|line| format!("> {}", line).trim_end().to_string()).collect::<Vec<_>>().join("\n")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// `|` would end the URL of a `<url|text>` link and `<` or `>` the link itself,
+// so they are percent-encoded; `&` is escaped as everywhere else.
+fn escape_url(url: &str) -> String {
+    url.replace('&', "&amp;").replace('|', "%7C").replace('<', "%3C").replace('>', "%3E")
+}
+
+fn plain_text(content: &[Node]) -> String {
+    content.iter().map(Node::text_content).collect()
+}
+
+fn slack_user(options: &SlackOptions, account_id: &str) -> Option<String> {
+    options.users.as_ref()?.slack_user_id(account_id)
+}
+
+// Slack knows the standard shortcodes but not Atlassian's custom emoji, which
+// fall back as configured.
+fn emoji_text(options: &SlackOptions, attrs: &EmojiAttrs) -> String {
+    if emoji::is_custom(attrs) {
+        return options.custom_emoji.text(attrs);
+    }
+    let name = attrs.short_name().trim_matches(':');
+    format!(":{}:", name)
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max.saturating_sub(1)) {
+        Some((end, _)) if text[end..].chars().nth(1).is_some() => format!("{}…", &text[..end]),
+        _ => text.to_string(),
+    }
+}
+
+// Monospace rows with `|` between cells, cut short enough to fit a message.
+fn table_text(table: &Node) -> String {
+    let Ok(grid) = TableGrid::new(table) else {
+        return escape(&table.text_content());
+    };
+    let rows: Vec<Vec<String>> = (0..grid.rows())
+        .map(|row| {
+            (0..grid.columns())
+                .map(|column| match grid.cell(row, column) {
+                    Some(cell) if cell.row == row && cell.column == column => {
+                        let text = cell.content().iter().map(Node::text_content).collect::<Vec<_>>().join(" ");
+                        truncate(&text.split_whitespace().collect::<Vec<_>>().join(" "), MAX_CELL_CHARS)
+                    }
+                    _ => String::new(),
+                })
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = (0..grid.columns())
+        .map(|column| rows.iter().map(|row| row[column].chars().count()).max().unwrap_or(0))
+        .collect();
+    let mut lines = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(text, width)| format!("{}{}", escape(text), " ".repeat(width - text.chars().count())))
+            .collect();
+        lines.push(cells.join(" | ").trim_end().to_string());
+        if index + 1 == grid.header_rows() && index + 1 < rows.len() {
+            lines.push(widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>().join("-+-"));
+        }
+    }
+    // Leave room for the fences and the note of cut rows.
+    let budget = MAX_TEXT_CHARS - 40;
+    let mut length = 0;
+    let kept = lines.iter().take_while(|line| {
+        length += line.chars().count() + 1;
+        length <= budget
+    });
+    let kept = kept.count();
+    if kept < lines.len() {
+        let cut = lines.len() - kept;
+        lines.truncate(kept);
+        lines.push(format!("… {} more rows", cut));
+    }
+    lines.join("\n")
+}
+
+// ------ Block Kit -------
+
+struct BlockKit<'a> {
+    options: &'a SlackOptions,
+    mrkdwn: Mrkdwn<'a>,
+    blocks: Vec<Value>,
+    // Elements of the `rich_text` block being built.
+    rich: Vec<Value>,
+}
+
+impl BlockKit<'_> {
+    fn flush(&mut self) {
+        if !self.rich.is_empty() {
+            let elements = std::mem::take(&mut self.rich);
+            self.blocks.push(json!({ "type": "rich_text", "elements": elements }));
+        }
+    }
+
+    fn push(&mut self, block: Value) {
+        self.flush();
+        self.blocks.push(block);
+    }
+
+    fn block(&mut self, node: &Node) {
+        match node {
+            Node::Doc { content, .. } => content.iter().for_each(|child| self.block(child)),
+            Node::Paragraph { content, .. } => {
+                let elements = self.inline(content, &Map::new());
+                if !elements.is_empty() {
+                    self.rich.push(json!({ "type": "rich_text_section", "elements": elements }));
+                }
+            }
+            Node::Heading { content, .. } => {
+                let text = truncate(plain_text(content).trim(), MAX_HEADER_CHARS);
+                if !text.is_empty() {
+                    let text = json!({ "type": "plain_text", "text": text, "emoji": true });
+                    self.push(json!({ "type": "header", "text": text }));
+                }
+            }
+            Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } | Node::DecisionList { .. } => {
+                let mut elements = Vec::new();
+                self.list(node, 0, &mut elements);
+                self.rich.extend(elements);
+            }
+            Node::CodeBlock { content, .. } => {
+                let code: String = content.iter().flatten().map(Node::text_content).collect();
+                if !code.is_empty() {
+                    let code = truncate(&code, MAX_TEXT_CHARS);
+                    self.rich.push(json!({
+                        "type": "rich_text_preformatted",
+                        "elements": [{ "type": "text", "text": code }],
+                    }));
+                }
+            }
+            Node::Blockquote { content } => {
+                let mut elements = Vec::new();
+                for block in content {
+                    let inline = match block {
+                        Node::Paragraph { content, .. } | Node::Heading { content, .. } => {
+                            self.inline(content, &Map::new())
+                        }
+                        other => vec![json!({ "type": "text", "text": other.text_content() })],
+                    };
+                    if !elements.is_empty() && !inline.is_empty() {
+                        elements.push(json!({ "type": "text", "text": "\n" }));
+                    }
+                    elements.extend(inline);
+                }
+                if !elements.is_empty() {
+                    self.rich.push(json!({ "type": "rich_text_quote", "elements": elements }));
+                }
+            }
+            Node::Rule => self.push(json!({ "type": "divider" })),
+            Node::MediaSingle { content, .. } | Node::MediaGroup { content } => {
+                for media in content {
+                    let info = match media {
+                        Node::Media { attrs } => self.options.resolvers.media(attrs),
+                        _ => None,
+                    };
+                    match info {
+                        Some(info) => self.push(json!({
+                            "type": "image",
+                            "image_url": info.url,
+                            "alt_text": info.alt.unwrap_or_else(|| "image".to_string()),
+                        })),
+                        None => self.push(section(&self.mrkdwn.block(media))),
+                    }
+                }
+            }
+            node if node.is_inline() => self.block(&Node::paragraph(vec![node.clone()])),
+            other => {
+                let text = self.mrkdwn.block(other);
+                if !text.is_empty() {
+                    self.push(section(&text));
+                }
+            }
+        }
+    }
+
+    // Slack lists are flat: nesting is an `indent` on a list of its own, so
+    // a list is split wherever an item holds a nested one.
+    fn list(&self, list: &Node, indent: usize, out: &mut Vec<Value>) {
+        let ordered = matches!(list, Node::OrderedList { .. });
+        let start = match list {
+            Node::OrderedList { attrs, .. } => attrs.as_ref().map_or(1, |attrs| attrs.order()) as usize,
+            _ => 1,
+        };
+        let mut sections = Vec::new();
+        let mut emitted = 0;
+        let flush = |sections: &mut Vec<Value>, emitted: &mut usize, out: &mut Vec<Value>| {
+            if sections.is_empty() {
+                return;
+            }
+            let mut element = json!({
+                "type": "rich_text_list",
+                "style": if ordered { "ordered" } else { "bullet" },
+                "indent": indent,
+                "elements": sections.clone(),
+            });
+            if ordered && start - 1 + *emitted > 0 {
+                element["offset"] = json!(start - 1 + *emitted);
+            }
+            *emitted += sections.len();
+            sections.clear();
+            out.push(element);
+        };
+        for item in list.content().into_iter().flatten() {
+            let (prefix, inline, nested): (Option<&str>, &[Node], Vec<&Node>) = match item {
+                Node::TaskItem { content, attrs } => {
+                    let mark = if *attrs.state() == TaskState::Done { "☑ " } else { "☐ " };
+                    (Some(mark), content, Vec::new())
+                }
+                Node::DecisionItem { content, .. } => (None, content, Vec::new()),
+                Node::TaskList { .. } => (None, &[], vec![item]),
+                _ => {
+                    let children = item.content().map(Vec::as_slice).unwrap_or_default();
+                    let first = match children.first() {
+                        Some(Node::Paragraph { content, .. }) => content.as_slice(),
+                        _ => &[],
+                    };
+                    (None, first, children.iter().filter(|child| !matches!(child, Node::Paragraph { .. })).collect())
+                }
+            };
+            if !matches!(item, Node::TaskList { .. }) {
+                let mut elements = Vec::new();
+                if let Some(prefix) = prefix {
+                    elements.push(json!({ "type": "text", "text": prefix }));
+                }
+                elements.extend(self.inline(inline, &Map::new()));
+                sections.push(json!({ "type": "rich_text_section", "elements": elements }));
+            }
+            for child in nested {
+                match child {
+                    Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } => {
+                        flush(&mut sections, &mut emitted, out);
+                        self.list(child, indent + 1, out);
+                    }
+                    // Code blocks and media in an item have no place in a Slack list.
+                    other => {
+                        let text = other.text_content();
+                        let elements = sections.last_mut().map(|section| &mut section["elements"]);
+                        if let Some(Value::Array(elements)) = elements {
+                            elements.push(json!({ "type": "text", "text": format!("\n{}", text) }));
+                        }
+                    }
+                }
+            }
+        }
+        flush(&mut sections, &mut emitted, out);
+    }
+
+    fn inline(&self, content: &[Node], style: &Map<String, Value>) -> Vec<Value> {
+        let mut elements = Vec::new();
+        for node in content {
+            let element = match node {
+                Node::Text { text, marks } => {
+                    let marks = marks.as_deref().unwrap_or_default();
+                    let mut style = style.clone();
+                    for (mark, name) in &STYLES {
+                        if marks.contains(mark) {
+                            style.insert(name.to_string(), json!(true));
+                        }
+                    }
+                    let link = marks.iter().find_map(|mark| match mark {
+                        Mark::Link { attrs } => Some(attrs.href()),
+                        _ => None,
+                    });
+                    let mut element = match link {
+                        Some(url) => json!({ "type": "link", "url": url, "text": text }),
+                        None => json!({ "type": "text", "text": text }),
+                    };
+                    if !style.is_empty() {
+                        element["style"] = Value::Object(style);
+                    }
+                    element
+                }
+                Node::HardBreak { .. } => json!({ "type": "text", "text": "\n" }),
+                Node::Mention { attrs } => match slack_user(self.options, &attrs.id) {
+                    Some(user_id) => json!({ "type": "user", "user_id": user_id }),
+                    None => json!({ "type": "text", "text": self.options.resolvers.mention_name(attrs) }),
+                },
+                Node::Emoji { attrs } if !emoji::is_custom(attrs) => {
+                    let name = attrs.short_name().trim_matches(':');
+                    match name.split_once("::skin-tone-") {
+                        Some((name, tone)) => match tone.parse::<u8>() {
+                            Ok(tone) => json!({ "type": "emoji", "name": name, "skin_tone": tone }),
+                            Err(_) => json!({ "type": "emoji", "name": name }),
+                        },
+                        None => json!({ "type": "emoji", "name": name }),
+                    }
+                }
+                Node::Emoji { attrs } => json!({ "type": "text", "text": emoji_text(self.options, attrs) }),
+                Node::Date { attrs } => self.date(attrs),
+                Node::Status { attrs } => {
+                    json!({ "type": "text", "text": attrs.text().to_uppercase(), "style": { "code": true } })
+                }
+                Node::InlineCard { attrs } => {
+                    let url = attrs.url().unwrap_or_default();
+                    // A card without a URL has no text either, and is left out.
+                    match self.options.resolvers.card(url) {
+                        _ if url.is_empty() => json!({ "type": "text", "text": "" }),
+                        Some(card) => json!({ "type": "link", "url": url, "text": card.title }),
+                        None => json!({ "type": "link", "url": url }),
+                    }
+                }
+                other => json!({ "type": "text", "text": other.text_content() }),
+            };
+            if element["type"] != "text" || element["text"].as_str().is_some_and(|text| !text.is_empty()) {
+                elements.push(element);
+            }
+        }
+        elements
+    }
+
+    fn date(&self, attrs: &DateAttrs) -> Value {
+        match attrs.parsed_timestamp() {
+            Ok(timestamp) => json!({
+                "type": "date",
+                "timestamp": timestamp.as_seconds(),
+                "format": "{date_short}",
+                "fallback": self.options.date_format.format(timestamp),
+            }),
+            Err(_) => json!({ "type": "text", "text": attrs.timestamp() }),
+        }
+    }
+}
+
+fn section(text: &str) -> Value {
+    json!({ "type": "section", "text": { "type": "mrkdwn", "text": truncate(text, MAX_TEXT_CHARS) } })
+}
+
+#[cfg(test)]
+mod slack_tests {
+    use super::*;
+    use crate::model::{InlineCardAttrs, LinkAttrs, MentionAttrs, PanelAttrs};
+
+    struct Members;
+
+    impl SlackUserResolver for Members {
+        fn slack_user_id(&self, account_id: &str) -> Option<String> {
+            (account_id == "abc").then(|| "U024BE7LH".to_string())
+        }
+    }
+
+    fn options() -> SlackOptions {
+        SlackOptions { users: Some(Arc::new(Members)), ..SlackOptions::default() }
+    }
+
+    fn sample() -> Node {
+        Node::doc(vec![
+            Node::heading(2, vec![Node::text("Release <1.2>")]),
+            Node::paragraph(vec![
+                Node::Mention { attrs: MentionAttrs::new("abc") },
+                Node::text(" shipped "),
+                Node::text_with_marks("bold ", vec![Mark::Strong]),
+                Node::text_with_marks("docs", vec![Mark::Link { attrs: LinkAttrs::new("https://d.example") }]),
+                Node::text(" "),
+                Node::Emoji { attrs: EmojiAttrs::new(":tada:") },
+                Node::text(" with "),
+                Node::Mention { attrs: MentionAttrs::new("xyz").with_text("@Ann") },
+            ]),
+            Node::bullet_list(vec![Node::list_item(vec![
+                Node::paragraph(vec![Node::text("one")]),
+                Node::ordered_list(vec![Node::list_item(vec![Node::paragraph(vec![Node::text("two")])])]),
+            ])]),
+            Node::Panel {
+                content: vec![Node::paragraph(vec![Node::text("Mind the gap")])],
+                attrs: PanelAttrs::new(PanelType::Warning),
+            },
+            Node::Rule,
+        ])
+    }
+
+    #[test]
+    fn should_render_mrkdwn() {
+        assert_eq!(
+            to_mrkdwn(&sample(), &options()),
+            concat!(
+                "*Release &lt;1.2&gt;*\n\n",
+                "<@U024BE7LH> shipped *bold* <https://d.example|docs> :tada: with @Ann\n\n",
+                "• one\n    1. two\n\n",
+                "> :warning: Mind the gap\n\n",
+                "──────────",
+            )
+        );
+    }
+
+    #[test]
+    fn should_render_block_kit() {
+        let blocks = to_block_kit(&sample(), &options());
+
+        assert_eq!(
+            blocks,
+            json!([
+                { "type": "header", "text": { "type": "plain_text", "text": "Release <1.2>", "emoji": true } },
+                { "type": "rich_text", "elements": [
+                    { "type": "rich_text_section", "elements": [
+                        { "type": "user", "user_id": "U024BE7LH" },
+                        { "type": "text", "text": " shipped " },
+                        { "type": "text", "text": "bold ", "style": { "bold": true } },
+                        { "type": "link", "url": "https://d.example", "text": "docs" },
+                        { "type": "text", "text": " " },
+                        { "type": "emoji", "name": "tada" },
+                        { "type": "text", "text": " with " },
+                        { "type": "text", "text": "@Ann" },
+                    ] },
+                    { "type": "rich_text_list", "style": "bullet", "indent": 0, "elements": [
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "one" }] },
+                    ] },
+                    { "type": "rich_text_list", "style": "ordered", "indent": 1, "elements": [
+                        { "type": "rich_text_section", "elements": [{ "type": "text", "text": "two" }] },
+                    ] },
+                ] },
+                { "type": "section", "text": { "type": "mrkdwn", "text": "> :warning: Mind the gap" } },
+                { "type": "divider" },
+            ])
+        );
+    }
+
+    #[test]
+    fn should_escape_link_urls_and_drop_empty_cards() {
+        let href = "https://e.example/?a=1&b=<2>|x";
+        let doc = Node::paragraph(vec![
+            Node::text_with_marks("odd", vec![Mark::Link { attrs: LinkAttrs::new(href) }]),
+            Node::InlineCard { attrs: InlineCardAttrs::default() },
+        ]);
+
+        assert_eq!(to_mrkdwn(&doc, &options()), "<https://e.example/?a=1&amp;b=%3C2%3E%7Cx|odd>");
+        assert_eq!(
+            to_block_kit(&doc, &options()),
+            json!([{ "type": "rich_text", "elements": [
+                { "type": "rich_text_section", "elements": [{ "type": "link", "url": href, "text": "odd" }] },
+            ] }])
+        );
+    }
+
+    #[test]
+    fn should_keep_within_slack_limits() {
+        let long = "x".repeat(50);
+        let rows = (0..200)
+            .map(|_| {
+                Node::table_row(vec![
+                    Node::table_cell(vec![Node::paragraph(vec![Node::text(long.as_str())])]),
+                    Node::table_cell(vec![Node::paragraph(vec![Node::text("y")])]),
+                ])
+            })
+            .collect();
+        let mut content = vec![Node::table(rows)];
+        content.extend((0..60).map(|_| Node::Rule));
+        let blocks = to_block_kit(&Node::doc(content), &SlackOptions::default());
+        let blocks = blocks.as_array().unwrap();
+
+        assert_eq!(blocks.len(), MAX_BLOCKS);
+        assert_eq!(blocks[49]["text"]["text"], "_12 more blocks not shown_");
+        let table = blocks[0]["text"]["text"].as_str().unwrap();
+        assert!(table.chars().count() <= MAX_TEXT_CHARS);
+        assert!(table.starts_with(&format!("```\n{}… | y\n", "x".repeat(29))));
+        assert!(table.ends_with("more rows\n```"));
+    }
+}
@@ -0,0 +1,488 @@
+use crate::color::Color;
+use crate::date::DateFormat;
+use crate::emoji::CustomEmojiFallback;
+use crate::model::{Mark, Node, PanelType, SubSupAttrs, TaskState};
+use crate::resolve::{self, Resolvers};
+use crate::table::{TableGrid, PAGE_WIDTH};
+
+#[derive(Debug, Clone, Default)]
+pub struct TypstOptions {
+    /// Format and timezone used to display `date` nodes.
+    pub date_format: DateFormat,
+    /// How custom emoji appear. Typst cannot load remote images, so an image
+    /// fallback shows the short name.
+    pub custom_emoji: CustomEmojiFallback,
+    /// Looks up user names, media file paths and link titles.
+    pub resolvers: Resolvers,
+}
+
+/// Typst markup for a node, using only built-in functions.
+///
+/// Panels and expands become filled and outlined `block`s, code blocks raw
+/// blocks and tables `table`s, with column widths following `colwidth`,
+/// header rows repeated on every page and spans kept. Marks are written as
+/// function calls (`#strong[..]`, `#text(fill: ..)[..]`), which unlike `*`
+/// and `_` also apply inside words. Media become framed placeholders naming
+/// the resolved file path, so the output compiles whether or not the files
+/// are at hand.
+pub fn to_typst(node: &Node, options: &TypstOptions) -> String {
+    let mut out = Writer { options }.block(node);
+    out.push('\n');
+    out
+}
+
+struct Writer<'a> {
+    options: &'a TypstOptions,
+}
+
+impl Writer<'_> {
+    fn blocks(&self, content: &[Node]) -> String {
+        content.iter().map(|node| self.block(node)).filter(|text| !text.is_empty()).collect::<Vec<_>>().join("\n\n")
+    }
+
+    fn block(&self, node: &Node) -> String {
+        match node {
+            Node::Doc { content, .. } | Node::ListItem { content } => self.blocks(content),
+            Node::Paragraph { content, .. } => content
+                .split(|node| matches!(node, Node::HardBreak { .. }))
+                .map(|line| guard_line_start(self.inline(line)))
+                .collect::<Vec<_>>()
+                .join(" \\\n"),
+            Node::Heading { content, attrs } => {
+                let text = self.inline(content);
+                if text.is_empty() {
+                    return text;
+                }
+                format!("{} {}", "=".repeat(attrs.level().clamp(1, 6) as usize), text)
+            }
+            Node::BulletList { .. } | Node::OrderedList { .. } | Node::TaskList { .. } | Node::DecisionList { .. } => {
+                self.list(node)
+            }
+            Node::Blockquote { content } => format!("#quote(block: true)[\n{}\n]", self.blocks(content)),
+            Node::CodeBlock { content, attrs } => {
+                let code: String = content.iter().flatten().map(Node::text_content).collect();
+                // The fence has to be longer than any run of backticks in the code.
+                let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+                let fence = "`".repeat((longest + 1).max(3));
+                let language: String = attrs
+                    .as_ref()
+                    .map(|attrs| attrs.language())
+                    .unwrap_or_default()
+                    .chars()
+                    .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '_'))
+                    .collect();
+                format!("{}{}\n{}\n{}", fence, language, code, fence)
+            }
+            Node::Panel { content, attrs } => {
+                let (background, frame, title) = panel_style(attrs.panel_type());
+                let title = title.map(|title| format!("#strong[{}]\n\n", title)).unwrap_or_default();
+                format!(
+                    "#block(fill: {}, stroke: 0.5pt + {}, inset: 8pt, radius: 3pt, width: 100%)[\n{}{}\n]",
+                    rgb(background),
+                    rgb(frame),
+                    title,
+                    self.blocks(content)
+                )
+            }
+            Node::Expand { content, attrs, .. } | Node::NestedExpand { content, attrs } => {
+                let title = match attrs.title().trim() {
+                    "" => String::new(),
+                    title => format!("#strong[{}]\n\n", escape(title)),
+                };
+                format!(
+                    "#block(stroke: 0.5pt + {}, inset: 8pt, radius: 3pt, width: 100%)[\n{}{}\n]",
+                    rgb(Color::new(0x6b, 0x77, 0x8c)),
+                    title,
+                    self.blocks(content)
+                )
+            }
+            Node::Rule => "#line(length: 100%)".to_string(),
+            Node::Table { .. } => self.table(node),
+            Node::MediaSingle { content, .. } | Node::MediaGroup { content } => self.blocks(content),
+            Node::Media { attrs } => {
+                let (path, alt, width) = match self.options.resolvers.media(attrs) {
+                    Some(info) => (info.url, info.alt, info.width),
+                    None => (attrs.id().to_string(), None, None),
+                };
+                let fraction = width.map_or(0.5, |width| (f64::from(width) / PAGE_WIDTH).min(1.0));
+                let mut label = format!("Image: #raw({})", string(&path));
+                if let Some(alt) = alt.filter(|alt| !alt.is_empty()) {
+                    label.push_str(&format!(" \\ {}", escape(&alt)));
+                }
+                format!(
+                    "#align(center, block(stroke: 0.5pt + gray, inset: 8pt, width: {:.1}%)[{}])",
+                    fraction * 100.0,
+                    label
+                )
+            }
+            inline_node => self.inline(std::slice::from_ref(inline_node)),
+        }
+    }
+
+    fn list(&self, list: &Node) -> String {
+        let start = match list {
+            Node::OrderedList { attrs, .. } => attrs.as_ref().map_or(1, |attrs| attrs.order()) as usize,
+            _ => 1,
+        };
+        let mut items = Vec::new();
+        for (index, item) in list.content().into_iter().flatten().enumerate() {
+            let (marker, body) = match item {
+                Node::TaskItem { content, attrs } => {
+                    let check = if *attrs.state() == TaskState::Done { "\\[x\\]" } else { "\\[ \\]" };
+                    ("- ".to_string(), format!("{} {}", check, self.inline(content)))
+                }
+                Node::DecisionItem { content, .. } => ("- ".to_string(), guard_line_start(self.inline(content))),
+                // A task list nested in another sits among its items.
+                Node::TaskList { .. } => {
+                    items.push(indent(&self.list(item), "  "));
+                    continue;
+                }
+                // Numbering every item keeps the list's start.
+                _ if matches!(list, Node::OrderedList { .. }) => (format!("{}. ", start + index), self.block(item)),
+                _ => ("- ".to_string(), self.block(item)),
+            };
+            // Continuation lines line up with the text after the marker.
+            let hanging = indent(&body, &" ".repeat(marker.len()));
+            items.push(format!("{}{}", marker, hanging.trim_start()));
+        }
+        let separator = if items.iter().any(|item| item.contains('\n')) { "\n\n" } else { "\n" };
+        items.join(separator)
+    }
+
+    fn table(&self, table: &Node) -> String {
+        let Ok(grid) = TableGrid::new(table) else {
+            return self.blocks(table.content().map(Vec::as_slice).unwrap_or_default());
+        };
+        let (rows, columns) = (grid.rows(), grid.columns());
+        if rows == 0 || columns == 0 {
+            return String::new();
+        }
+        let widths: Vec<String> =
+            grid.column_fractions().iter().map(|fraction| format!("{:.1}%", fraction * 100.0)).collect();
+        let header_rows = if grid.header_rows() < rows { grid.header_rows() } else { 0 };
+
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                // Typst places cells in the slots rowspans leave free, so
+                // only a cell's first slot is written.
+                let text = match grid.cell(row, column) {
+                    Some(cell) if cell.row == row && cell.column == column => {
+                        let mut text = self.blocks(cell.content());
+                        if cell.is_header() && !text.is_empty() {
+                            text = format!("#strong[{}]", text);
+                        }
+                        let mut spans = Vec::new();
+                        if cell.colspan > 1 {
+                            spans.push(format!("colspan: {}", cell.colspan));
+                        }
+                        if cell.rowspan > 1 {
+                            spans.push(format!("rowspan: {}", cell.rowspan));
+                        }
+                        if spans.is_empty() {
+                            format!("[{}]", text)
+                        } else {
+                            format!("table.cell({})[{}]", spans.join(", "), text)
+                        }
+                    }
+                    Some(_) => continue,
+                    None => "[]".to_string(),
+                };
+                if row < header_rows { header.push(text) } else { body.push(text) }
+            }
+        }
+        let mut out = format!("#table(\n  columns: ({}),", widths.join(", "));
+        if !header.is_empty() {
+            out.push_str(&format!("\n  table.header(\n{}\n  ),", indent(&header.join(",\n"), "    ")));
+        }
+        if !body.is_empty() {
+            out.push_str(&format!("\n{},", indent(&body.join(",\n"), "  ")));
+        }
+        out.push_str("\n)");
+        out
+    }
+
+    fn inline(&self, content: &[Node]) -> String {
+        let mut pieces = Vec::new();
+        for node in content {
+            match node {
+                Node::Text { text, marks } => pieces.push(text_with_marks(text, marks.as_deref().unwrap_or_default())),
+                Node::HardBreak { .. } => pieces.push(Piece::Plain(" \\\n".to_string())),
+                Node::Mention { attrs } => {
+                    let user = self.options.resolvers.user(attrs);
+                    let name = resolve::mention_name(user.as_ref(), attrs);
+                    match user.and_then(|user| user.profile_url) {
+                        Some(url) => pieces.push(Piece::Call(format!("#link({})[{}]", string(&url), escape(&name)))),
+                        None => pieces.push(Piece::Plain(escape(&name))),
+                    }
+                }
+                Node::Emoji { attrs } => pieces.push(Piece::Plain(escape(&self.options.custom_emoji.text(attrs)))),
+                Node::Date { attrs } => {
+                    let text = match attrs.parsed_timestamp() {
+                        Ok(timestamp) => self.options.date_format.format(timestamp),
+                        Err(_) => attrs.timestamp().to_string(),
+                    };
+                    pieces.push(Piece::Plain(escape(&text)));
+                }
+                Node::Status { attrs } => pieces.push(Piece::Call(format!(
+                    "#box(stroke: 0.5pt, inset: (x: 2pt), outset: (y: 2pt))[#text(size: 0.8em)[{}]]",
+                    escape(&attrs.text().to_uppercase())
+                ))),
+                Node::InlineCard { attrs } => {
+                    let url = attrs.url().unwrap_or_default();
+                    match self.options.resolvers.card(url) {
+                        Some(card) => {
+                            pieces.push(Piece::Call(format!("#link({})[{}]", string(url), escape(&card.title))))
+                        }
+                        None => pieces.push(Piece::Call(format!("#link({})", string(url)))),
+                    }
+                }
+                other => pieces.push(Piece::Plain(escape(&other.text_content()))),
+            }
+        }
+        join_pieces(&pieces)
+    }
+}
+
+enum Piece {
+    Plain(String),
+    Call(String),
+}
+
+fn text_with_marks(text: &str, marks: &[Mark]) -> Piece {
+    if marks.is_empty() {
+        return Piece::Plain(escape(text));
+    }
+    let mut out = if marks.contains(&Mark::Code) { format!("#raw({})", string(text)) } else { escape(text) };
+    let mut wrap = |function: String| out = format!("#{}[{}]", function, out);
+    for mark in marks {
+        match mark {
+            Mark::SubSup { attrs: SubSupAttrs::Sub } => wrap("sub".to_string()),
+            Mark::SubSup { attrs: SubSupAttrs::Sup } => wrap("super".to_string()),
+            _ => {}
+        }
+    }
+    for (mark, function) in [
+        (Mark::Strike, "strike"),
+        (Mark::Underline, "underline"),
+        (Mark::Em, "emph"),
+        (Mark::Strong, "strong"),
+    ] {
+        if marks.contains(&mark) {
+            wrap(function.to_string());
+        }
+    }
+    for mark in marks {
+        match mark {
+            Mark::TextColor { attrs } => wrap(format!("text(fill: {})", rgb(attrs.color()))),
+            Mark::BackgroundColor { attrs } => wrap(format!("highlight(fill: {})", rgb(attrs.color()))),
+            _ => {}
+        }
+    }
+    for mark in marks {
+        if let Mark::Link { attrs } = mark {
+            wrap(format!("link({})", string(attrs.href())));
+        }
+    }
+    if out.starts_with('#') { Piece::Call(out) } else { Piece::Plain(out) }
+}
+
+// An embedded call followed by `.` or `(` would carry on as a field access
+// or more arguments; a `;` ends it there.
+fn join_pieces(pieces: &[Piece]) -> String {
+    let mut out = String::new();
+    let mut after_call = false;
+    for piece in pieces {
+        let text = match piece {
+            Piece::Plain(text) | Piece::Call(text) => text,
+        };
+        if after_call && text.starts_with(['.', '(']) {
+            out.push(';');
+        }
+        out.push_str(text);
+        after_call = match piece {
+            Piece::Call(_) => true,
+            Piece::Plain(text) => after_call && text.is_empty(),
+        };
+    }
+    out
+}
+
+fn panel_style(panel_type: &PanelType) -> (Color, Color, Option<&'static str>) {
+    match panel_type {
+        PanelType::Info => (Color::new(0xde, 0xeb, 0xff), Color::new(0x00, 0x52, 0xcc), Some("Info")),
+        PanelType::Note => (Color::new(0xea, 0xe6, 0xff), Color::new(0x52, 0x43, 0xaa), Some("Note")),
+        PanelType::Success => (Color::new(0xe3, 0xfc, 0xef), Color::new(0x00, 0x87, 0x5a), Some("Success")),
+        PanelType::Warning => (Color::new(0xff, 0xfa, 0xe6), Color::new(0xff, 0x8b, 0x00), Some("Warning")),
+        PanelType::Error => (Color::new(0xff, 0xeb, 0xe6), Color::new(0xde, 0x35, 0x0b), Some("Error")),
+        PanelType::Custom | PanelType::Other(_) => (Color::new(0xf4, 0xf5, 0xf7), Color::new(0x6b, 0x77, 0x8c), None),
+    }
+}
+
+fn rgb(color: Color) -> String {
+    format!("rgb(\"{}\")", color.to_hex())
+}
+
+// A Typst string literal.
+fn string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Backslash escapes for everything that starts markup. A `/` is only
+// escaped where it could open a comment and a `-` where it could start a
+// dash or soft hyphen, keeping URLs and plain hyphens readable.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        let special = match c {
+            '\\' | '#' | '*' | '_' | '`' | '$' | '<' | '>' | '@' | '[' | ']' | '~' => true,
+            '/' => matches!(next, None | Some('/' | '*')),
+            '-' => matches!(next, Some('-' | '?')),
+            _ => false,
+        };
+        if special {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("{}{}", prefix, line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// A line starting like a heading, list item or term would be read as one.
+fn guard_line_start(text: String) -> String {
+    let marker = text.split_once(' ').map_or(text.as_str(), |(first, _)| first);
+    let enumerator = marker
+        .strip_suffix('.')
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()));
+    if text.starts_with('=') || matches!(marker, "-" | "+" | "/") || enumerator {
+        format!("\\{}", text)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod typst_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+    use crate::model::{ColorAttrs, LinkAttrs, PanelAttrs, TableCellAttrs};
+
+    #[test]
+    fn should_render_blocks_and_marks() {
+        let doc = Node::doc(vec![
+            Node::heading(2, vec![Node::text("Scope")]),
+            Node::paragraph(vec![
+                Node::text_with_marks("red", vec![Mark::TextColor { attrs: ColorAttrs::new(Color::new(255, 0, 0)) }]),
+                Node::text(". Mail @ops about #12 at "),
+                Node::text_with_marks("docs", vec![
+                    Mark::Strong,
+                    Mark::Link { attrs: LinkAttrs::new("https://d.example") },
+                ]),
+                Node::HardBreak { attrs: None },
+                Node::text("- not a list"),
+            ]),
+            Node::ordered_list(vec![
+                Node::list_item(vec![paragraph("first")]),
+                Node::list_item(vec![paragraph("second")]),
+            ]),
+            Node::code_block(Some("rust"), "let s = \"```\";"),
+            Node::Panel { content: vec![paragraph("Mind it")], attrs: PanelAttrs::new(PanelType::Warning) },
+        ]);
+
+        assert_eq!(
+            to_typst(&doc, &TypstOptions::default()),
+            concat!(
+                "== Scope\n\n",
+                "#text(fill: rgb(\"#ff0000\"))[red];. Mail \\@ops about \\#12 at ",
+                "#link(\"https://d.example\")[#strong[docs]] \\\n\\- not a list\n\n",
+                "1. first\n2. second\n\n",
+                "````rust\nlet s = \"```\";\n````\n\n",
+                "#block(fill: rgb(\"#fffae6\"), stroke: 0.5pt + rgb(\"#ff8b00\"), ",
+                "inset: 8pt, radius: 3pt, width: 100%)[\n",
+                "#strong[Warning]\n\nMind it\n]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_escape_markup_strings_and_call_ends() {
+        let doc = Node::doc(vec![
+            paragraph("= not a heading"),
+            paragraph("12. not a list"),
+            Node::paragraph(vec![
+                Node::text_with_marks("bold", vec![Mark::Strong]),
+                Node::text("(aside) a--b // c /* d $x$"),
+            ]),
+            Node::paragraph(vec![Node::text_with_marks(
+                "q",
+                vec![Mark::Link { attrs: LinkAttrs::new("https://q.example/?s=\"a\\b\"") }],
+            )]),
+        ]);
+
+        assert_eq!(
+            to_typst(&doc, &TypstOptions::default()),
+            concat!(
+                "\\= not a heading\n\n",
+                "\\12. not a list\n\n",
+                "#strong[bold];(aside) a\\--b \\// c \\/\\* d \\$x\\$\n\n",
+                "#link(\"https://q.example/?s=\\\"a\\\\b\\\"\")[q]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn should_render_tables_with_widths_and_spans() {
+        let table = Node::table(vec![
+            Node::table_row(vec![
+                Node::TableHeader {
+                    content: vec![paragraph("Key")],
+                    attrs: Some(TableCellAttrs::default().with_colwidth(vec![190])),
+                },
+                Node::TableHeader {
+                    content: vec![paragraph("Value")],
+                    attrs: Some(TableCellAttrs::default().with_colwidth(vec![570])),
+                },
+            ]),
+            Node::table_row(vec![
+                Node::TableCell {
+                    content: vec![paragraph("tall")],
+                    attrs: Some(TableCellAttrs::default().with_rowspan(2)),
+                },
+                Node::table_cell(vec![paragraph("a")]),
+            ]),
+            Node::table_row(vec![Node::table_cell(vec![paragraph("b")])]),
+        ]);
+
+        assert_eq!(
+            to_typst(&table, &TypstOptions::default()),
+            concat!(
+                "#table(\n  columns: (25.0%, 75.0%),\n",
+                "  table.header(\n    [#strong[Key]],\n    [#strong[Value]]\n  ),\n",
+                "  table.cell(rowspan: 2)[tall],\n  [a],\n  [b],\n)\n",
+            )
+        );
+    }
+}
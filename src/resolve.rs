@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use crate::model::{MediaAttrs, MentionAttrs};
+
+/// A mentioned user, as shown in place of their account id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub display_name: String,
+    pub profile_url: Option<String>,
+}
+
+/// Where to load a media file from and how to describe it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaInfo {
+    pub url: String,
+    pub alt: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// What a smart link points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardInfo {
+    pub title: String,
+    pub icon_url: Option<String>,
+}
+
+pub trait MentionResolver: Send + Sync {
+    /// The user with the given account id, or `None` if unknown.
+    fn resolve_user(&self, id: &str) -> Option<User>;
+}
+
+pub trait MediaResolver: Send + Sync {
+    /// The file with the given id in the given collection, or `None` if
+    /// unknown.
+    fn resolve_media(&self, id: &str, collection: &str) -> Option<MediaInfo>;
+}
+
+pub trait CardResolver: Send + Sync {
+    /// Details of the page at the given URL, or `None` if unknown.
+    fn resolve_card(&self, url: &str) -> Option<CardInfo>;
+}
+
+/// The resolvers a renderer consults, each optional. Anything a resolver
+/// cannot resolve renders from the node's own attributes, as it does with
+/// no resolver at all.
+#[derive(Clone, Default)]
+pub struct Resolvers {
+    mentions: Option<Arc<dyn MentionResolver>>,
+    media: Option<Arc<dyn MediaResolver>>,
+    cards: Option<Arc<dyn CardResolver>>,
+}
+
+impl Resolvers {
+    pub fn new() -> Self {
+        Resolvers::default()
+    }
+
+    pub fn with_mentions(mut self, resolver: Arc<dyn MentionResolver>) -> Self {
+        self.mentions = Some(resolver);
+        self
+    }
+
+    pub fn with_media(mut self, resolver: Arc<dyn MediaResolver>) -> Self {
+        self.media = Some(resolver);
+        self
+    }
+
+    pub fn with_cards(mut self, resolver: Arc<dyn CardResolver>) -> Self {
+        self.cards = Some(resolver);
+        self
+    }
+
+    pub fn user(&self, attrs: &MentionAttrs) -> Option<User> {
+        self.mentions.as_ref()?.resolve_user(&attrs.id)
+    }
+
+    /// The name shown for a mention, with a single leading `@`: the user's
+    /// display name, else the node's own text, else the account id.
+    pub fn mention_name(&self, attrs: &MentionAttrs) -> String {
+        mention_name(self.user(attrs).as_ref(), attrs)
+    }
+
+    /// The resolved file, with its dimensions taken from the node when the
+    /// resolver leaves them out.
+    pub fn media(&self, attrs: &MediaAttrs) -> Option<MediaInfo> {
+        let mut info = self.media.as_ref()?.resolve_media(&attrs.id, &attrs.collection)?;
+        info.width = info.width.or(attrs.width);
+        info.height = info.height.or(attrs.height);
+        Some(info)
+    }
+
+    pub fn card(&self, url: &str) -> Option<CardInfo> {
+        self.cards.as_ref()?.resolve_card(url)
+    }
+}
+
+/// [`Resolvers::mention_name`] for a user looked up already, as renderers
+/// linking to the profile have.
+pub fn mention_name(user: Option<&User>, attrs: &MentionAttrs) -> String {
+    let name = match (user, &attrs.text) {
+        (Some(user), _) => &user.display_name,
+        (None, Some(text)) => text,
+        (None, None) => &attrs.id,
+    };
+    format!("@{}", name.trim_start_matches('@'))
+}
+
+impl fmt::Debug for Resolvers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resolvers")
+            .field("mentions", &self.mentions.is_some())
+            .field("media", &self.media.is_some())
+            .field("cards", &self.cards.is_some())
+            .finish()
+    }
+}
+
+/// Resolves from lookup tables filled in ahead of time, e.g. from a single
+/// bulk API request before rendering.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryResolver {
+    users: HashMap<String, User>,
+    media: HashMap<(String, String), MediaInfo>,
+    cards: HashMap<String, CardInfo>,
+}
+
+impl InMemoryResolver {
+    pub fn new() -> Self {
+        InMemoryResolver::default()
+    }
+
+    pub fn insert_user<S: Into<String>>(&mut self, id: S, user: User) {
+        self.users.insert(id.into(), user);
+    }
+
+    pub fn insert_media<I: Into<String>, C: Into<String>>(&mut self, id: I, collection: C, media: MediaInfo) {
+        self.media.insert((id.into(), collection.into()), media);
+    }
+
+    pub fn insert_card<S: Into<String>>(&mut self, url: S, card: CardInfo) {
+        self.cards.insert(url.into(), card);
+    }
+}
+
+impl MentionResolver for InMemoryResolver {
+    fn resolve_user(&self, id: &str) -> Option<User> {
+        self.users.get(id).cloned()
+    }
+}
+
+impl MediaResolver for InMemoryResolver {
+    fn resolve_media(&self, id: &str, collection: &str) -> Option<MediaInfo> {
+        self.media.get(&(id.to_string(), collection.to_string())).cloned()
+    }
+}
+
+impl CardResolver for InMemoryResolver {
+    fn resolve_card(&self, url: &str) -> Option<CardInfo> {
+        self.cards.get(url).cloned()
+    }
+}
+
+/// Remembers the answers of another resolver, including misses, so each
+/// user, file or link is looked up once however often it appears.
+#[derive(Debug, Default)]
+pub struct Cached<R> {
+    inner: R,
+    users: Mutex<HashMap<String, Option<User>>>,
+    media: Mutex<HashMap<(String, String), Option<MediaInfo>>>,
+    cards: Mutex<HashMap<String, Option<CardInfo>>>,
+}
+
+impl<R> Cached<R> {
+    pub fn new(inner: R) -> Self {
+        Cached { inner, users: Mutex::default(), media: Mutex::default(), cards: Mutex::default() }
+    }
+
+    pub fn clear(&self) {
+        lock(&self.users).clear();
+        lock(&self.media).clear();
+        lock(&self.cards).clear();
+    }
+}
+
+impl<R: MentionResolver> MentionResolver for Cached<R> {
+    fn resolve_user(&self, id: &str) -> Option<User> {
+        cached(&self.users, id.to_string(), || self.inner.resolve_user(id))
+    }
+}
+
+impl<R: MediaResolver> MediaResolver for Cached<R> {
+    fn resolve_media(&self, id: &str, collection: &str) -> Option<MediaInfo> {
+        cached(&self.media, (id.to_string(), collection.to_string()), || {
+            self.inner.resolve_media(id, collection)
+        })
+    }
+}
+
+impl<R: CardResolver> CardResolver for Cached<R> {
+    fn resolve_card(&self, url: &str) -> Option<CardInfo> {
+        cached(&self.cards, url.to_string(), || self.inner.resolve_card(url))
+    }
+}
+
+// The lock is not held while resolving, so a slow lookup does not block
+// others; two threads missing at once may both resolve the same key.
+fn cached<K: Eq + Hash, V: Clone>(
+    cache: &Mutex<HashMap<K, Option<V>>>,
+    key: K,
+    resolve: impl FnOnce() -> Option<V>,
+) -> Option<V> {
+    if let Some(value) = lock(cache).get(&key) {
+        return value.clone();
+    }
+    let value = resolve();
+    lock(cache).insert(key, value.clone());
+    value
+}
+
+// A panic in another thread cannot leave a map half-updated, so a poisoned
+// lock is still safe to use.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod resolve_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::model::MediaType;
+
+    struct Counting(AtomicUsize);
+
+    impl MentionResolver for Counting {
+        fn resolve_user(&self, id: &str) -> Option<User> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            (id == "known").then(|| User { display_name: "Known".to_string(), profile_url: None })
+        }
+    }
+
+    #[test]
+    fn should_cache_hits_and_misses() {
+        let cached = Cached::new(Counting(AtomicUsize::new(0)));
+
+        for _ in 0..3 {
+            assert_eq!(cached.resolve_user("known").unwrap().display_name, "Known");
+            assert_eq!(cached.resolve_user("unknown"), None);
+        }
+        assert_eq!(cached.inner.0.load(Ordering::Relaxed), 2);
+
+        cached.clear();
+        cached.resolve_user("known");
+        assert_eq!(cached.inner.0.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn should_fall_back_to_node_dimensions() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert_media(
+            "file-1",
+            "uploads",
+            MediaInfo { url: "https://cdn/file-1".to_string(), alt: None, width: Some(10), height: None },
+        );
+        let resolvers = Resolvers::new().with_media(Arc::new(resolver));
+        let attrs = MediaAttrs::new(MediaType::File, "file-1", "uploads").with_size(640, 480);
+
+        let info = resolvers.media(&attrs).unwrap();
+        assert_eq!((info.width, info.height), (Some(10), Some(480)));
+        assert_eq!(resolvers.media(&MediaAttrs::new(MediaType::File, "file-2", "uploads")), None);
+        assert_eq!(resolvers.user(&MentionAttrs::new("known")), None);
+    }
+
+    #[test]
+    fn should_name_mentions_with_one_at_sign() {
+        let resolvers = Resolvers::new().with_mentions(Arc::new(Counting(AtomicUsize::new(0))));
+
+        assert_eq!(resolvers.mention_name(&MentionAttrs::new("known").with_text("@Old")), "@Known");
+        assert_eq!(resolvers.mention_name(&MentionAttrs::new("gone").with_text("@Ann")), "@Ann");
+        assert_eq!(resolvers.mention_name(&MentionAttrs::new("gone").with_text("Ann")), "@Ann");
+        assert_eq!(resolvers.mention_name(&MentionAttrs::new("gone")), "@gone");
+    }
+}
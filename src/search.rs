@@ -0,0 +1,366 @@
+use std::fmt;
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::model::{Mark, Node};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Treat the pattern as a regular expression rather than literal text.
+    pub regex: bool,
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchError(regex::Error);
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid search pattern: {}", self.0)
+    }
+}
+
+impl std::error::Error for SearchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Position inside a text node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextPosition {
+    /// Path from the root to the `text` node.
+    pub path: Vec<usize>,
+    /// Byte offset into the node's text.
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMatch {
+    pub text: String,
+    pub start: TextPosition,
+    /// Exclusive end, which may lie in a later text node than the start.
+    pub end: TextPosition,
+}
+
+/// Marks given to replacement text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplaceMarks {
+    /// The marks of the text node the match starts in.
+    Preserve,
+    /// Exactly these marks, whatever the matched text had. Replacements in
+    /// code blocks, whose text cannot have marks, get none.
+    Apply(Vec<Mark>),
+}
+
+/// A compiled search over the text of a document. Matches run across
+/// adjacent text nodes whatever their marks, but not across blocks or
+/// across inline nodes such as mentions and hard breaks.
+#[derive(Debug, Clone)]
+pub struct Search {
+    regex: Regex,
+    expand: bool,
+}
+
+impl Search {
+    pub fn new(pattern: &str, options: SearchOptions) -> Result<Self, SearchError> {
+        let pattern = if options.regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(options.case_insensitive)
+            .build()
+            .map_err(SearchError)?;
+        Ok(Search { regex, expand: options.regex })
+    }
+
+    pub fn find(&self, root: &Node) -> Vec<TextMatch> {
+        let mut matches = Vec::new();
+        for_each_run(root, &mut Vec::new(), &mut |block, run| {
+            let text = run.text();
+            for found in self.regex.find_iter(&text) {
+                matches.push(TextMatch {
+                    text: found.as_str().to_string(),
+                    start: run.position(block, found.start(), false),
+                    end: run.position(block, found.end(), true),
+                });
+            }
+        });
+        matches
+    }
+
+    /// Replaces every match, rewriting text nodes across the boundaries the
+    /// match covers. With a regex search, `$1` and `$name` in the
+    /// replacement expand to capture groups. Returns the number of matches
+    /// replaced.
+    pub fn replace(&self, root: &mut Node, replacement: &str, marks: &ReplaceMarks) -> usize {
+        let mut replaced = 0;
+        replace_in(root, &mut |content, code| {
+            let marks = if code { &ReplaceMarks::Apply(Vec::new()) } else { marks };
+            let runs = runs(content);
+            // Back to front, so indices of earlier runs stay valid.
+            for run in runs.iter().rev() {
+                let text = run.text();
+                let mut edits = Vec::new();
+                for captures in self.regex.captures_iter(&text) {
+                    let found = captures.get(0).expect("group 0 is the whole match");
+                    let mut with = String::new();
+                    if self.expand {
+                        captures.expand(replacement, &mut with);
+                    } else {
+                        with.push_str(replacement);
+                    }
+                    edits.push((found.range(), with));
+                }
+                if edits.is_empty() {
+                    continue;
+                }
+                replaced += edits.len();
+                let nodes = run.rewrite(&text, &edits, marks);
+                content.splice(run.children.clone(), nodes);
+            }
+        });
+        replaced
+    }
+}
+
+// Adjacent text nodes inside a text block.
+struct Run {
+    children: Range<usize>,
+    // Text and marks of each node, in order.
+    segments: Vec<(String, Option<Vec<Mark>>)>,
+}
+
+impl Run {
+    fn text(&self) -> String {
+        self.segments.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    // Maps an offset in the run's text to a node and offset. An offset on a
+    // boundary belongs to the earlier node for ends and the later for starts.
+    fn position(&self, block: &[usize], offset: usize, end: bool) -> TextPosition {
+        let mut start = 0;
+        for (index, (text, _)) in self.segments.iter().enumerate() {
+            let last = index + 1 == self.segments.len();
+            if offset < start + text.len() || (end && offset == start + text.len()) || last {
+                let mut path = block.to_vec();
+                path.push(self.children.start + index);
+                return TextPosition { path, offset: offset - start };
+            }
+            start += text.len();
+        }
+        unreachable!("runs have at least one segment")
+    }
+
+    fn marks_at(&self, offset: usize) -> Option<Vec<Mark>> {
+        let mut start = 0;
+        for (text, marks) in &self.segments {
+            if offset < start + text.len() {
+                return marks.clone();
+            }
+            start += text.len();
+        }
+        self.segments.last().and_then(|(_, marks)| marks.clone())
+    }
+
+    fn rewrite(&self, text: &str, edits: &[(Range<usize>, String)], marks: &ReplaceMarks) -> Vec<Node> {
+        let mut pieces: Vec<(String, Option<Vec<Mark>>)> = Vec::new();
+        let mut cursor = 0;
+        for (range, with) in edits {
+            self.copy(cursor..range.start, &mut pieces);
+            let marks = match marks {
+                ReplaceMarks::Preserve => self.marks_at(range.start),
+                ReplaceMarks::Apply(marks) => Some(marks.clone()).filter(|marks| !marks.is_empty()),
+            };
+            pieces.push((with.clone(), marks));
+            cursor = range.end;
+        }
+        self.copy(cursor..text.len(), &mut pieces);
+
+        let mut nodes: Vec<(String, Option<Vec<Mark>>)> = Vec::new();
+        for (text, marks) in pieces.into_iter().filter(|(text, _)| !text.is_empty()) {
+            match nodes.last_mut() {
+                Some((previous, previous_marks)) if *previous_marks == marks => previous.push_str(&text),
+                _ => nodes.push((text, marks)),
+            }
+        }
+        nodes.into_iter().map(|(text, marks)| Node::Text { text, marks }).collect()
+    }
+
+    // Copies the run's text in `range` keeping each node's marks.
+    fn copy(&self, range: Range<usize>, pieces: &mut Vec<(String, Option<Vec<Mark>>)>) {
+        let mut start = 0;
+        for (text, marks) in &self.segments {
+            let end = start + text.len();
+            let from = range.start.max(start);
+            let to = range.end.min(end);
+            if from < to {
+                pieces.push((text[from - start..to - start].to_string(), marks.clone()));
+            }
+            start = end;
+        }
+    }
+}
+
+fn is_text_block(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Paragraph { .. }
+            | Node::Heading { .. }
+            | Node::CodeBlock { .. }
+            | Node::TaskItem { .. }
+            | Node::DecisionItem { .. }
+    )
+}
+
+fn runs(content: &[Node]) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for (index, node) in content.iter().enumerate() {
+        let Node::Text { text, marks } = node else {
+            continue;
+        };
+        match runs.last_mut() {
+            Some(run) if run.children.end == index => {
+                run.children.end += 1;
+                run.segments.push((text.clone(), marks.clone()));
+            }
+            _ => runs.push(Run { children: index..index + 1, segments: vec![(text.clone(), marks.clone())] }),
+        }
+    }
+    runs
+}
+
+fn for_each_run(node: &Node, path: &mut Vec<usize>, f: &mut dyn FnMut(&[usize], &Run)) {
+    let Some(content) = node.content() else {
+        return;
+    };
+    if is_text_block(node) {
+        for run in runs(content) {
+            f(path, &run);
+        }
+        return;
+    }
+    for (index, child) in content.iter().enumerate() {
+        path.push(index);
+        for_each_run(child, path, f);
+        path.pop();
+    }
+}
+
+// Calls `f` with the content of each text block and whether it is a code
+// block.
+fn replace_in(node: &mut Node, f: &mut dyn FnMut(&mut Vec<Node>, bool)) {
+    let text_block = is_text_block(node);
+    let code = matches!(node, Node::CodeBlock { .. });
+    let Some(content) = node.content_mut() else {
+        return;
+    };
+    if text_block {
+        f(content, code);
+        return;
+    }
+    for child in content {
+        replace_in(child, f);
+    }
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+
+    fn position(path: &[usize], offset: usize) -> TextPosition {
+        TextPosition { path: path.to_vec(), offset }
+    }
+
+    fn doc() -> Node {
+        Node::doc(vec![
+            Node::paragraph(vec![
+                Node::text("Release the "),
+                Node::text_with_marks("new ex", vec![Mark::Strong]),
+                Node::text("porter today"),
+            ]),
+            Node::bullet_list(vec![Node::list_item(vec![Node::paragraph(vec![
+                Node::text("NEW"),
+                Node::HardBreak { attrs: None },
+                Node::text("exporter"),
+            ])])]),
+        ])
+    }
+
+    #[test]
+    fn should_find_matches_across_text_nodes() {
+        let search = Search::new("new exporter", SearchOptions::default()).unwrap();
+
+        assert_eq!(
+            search.find(&doc()),
+            vec![TextMatch {
+                text: "new exporter".to_string(),
+                start: position(&[0, 1], 0),
+                end: position(&[0, 2], 6),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_find_case_insensitive_and_regex_matches() {
+        let options = SearchOptions { regex: true, case_insensitive: true };
+        let search = Search::new(r"new\b", options).unwrap();
+        let found: Vec<_> = search.find(&doc()).into_iter().map(|found| (found.text, found.start)).collect();
+
+        assert_eq!(
+            found,
+            vec![("new".to_string(), position(&[0, 1], 0)), ("NEW".to_string(), position(&[1, 0, 0, 0], 0))]
+        );
+        assert!(Search::new("(", options).is_err());
+    }
+
+    #[test]
+    fn should_replace_across_nodes_preserving_marks() {
+        let mut doc = doc();
+        let search = Search::new("new exporter", SearchOptions::default()).unwrap();
+
+        assert_eq!(search.replace(&mut doc, "CSV export", &ReplaceMarks::Preserve), 1);
+        assert_eq!(
+            doc.descendant(&[0]),
+            Some(&Node::paragraph(vec![
+                Node::text("Release the "),
+                Node::text_with_marks("CSV export", vec![Mark::Strong]),
+                Node::text(" today"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn should_replace_applying_marks_and_expanding_groups() {
+        let mut doc = doc();
+        let options = SearchOptions { regex: true, case_insensitive: false };
+        let search = Search::new(r"(\w+) today", options).unwrap();
+
+        assert_eq!(search.replace(&mut doc, "$1 tomorrow", &ReplaceMarks::Apply(vec![Mark::Em])), 1);
+        assert_eq!(
+            doc.descendant(&[0]),
+            Some(&Node::paragraph(vec![
+                Node::text("Release the "),
+                Node::text_with_marks("new ", vec![Mark::Strong]),
+                Node::text_with_marks("exporter tomorrow", vec![Mark::Em]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn should_not_apply_marks_inside_code_blocks() {
+        let mut doc = Node::doc(vec![
+            Node::code_block(Some("sh"), "run today"),
+            Node::paragraph(vec![Node::text("today")]),
+        ]);
+        let search = Search::new("today", SearchOptions::default()).unwrap();
+
+        assert_eq!(search.replace(&mut doc, "now", &ReplaceMarks::Apply(vec![Mark::Strong])), 2);
+        assert_eq!(doc.descendant(&[0]), Some(&Node::code_block(Some("sh"), "run now")));
+        let bold = Node::text_with_marks("now", vec![Mark::Strong]);
+        assert_eq!(doc.descendant(&[1]), Some(&Node::paragraph(vec![bold])));
+    }
+}
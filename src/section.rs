@@ -0,0 +1,166 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::model::Node;
+
+/// Identifies the heading that starts a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionQuery<'a> {
+    /// Heading text, compared trimmed and case-insensitively.
+    Text(&'a str),
+    LocalId(&'a str),
+}
+
+/// Location of a section among the top-level blocks of a doc.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Index of the heading.
+    pub heading: usize,
+    pub level: i8,
+    /// Blocks after the heading, up to the next heading of the same or a
+    /// higher level.
+    pub body: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SectionError {
+    NotADoc,
+    NotFound,
+}
+
+impl fmt::Display for SectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SectionError::NotADoc => write!(f, "node is not a doc"),
+            SectionError::NotFound => write!(f, "no heading matches the section"),
+        }
+    }
+}
+
+impl std::error::Error for SectionError {}
+
+/// Finds the first top-level heading matching the query. Headings nested in
+/// panels, expands or tables do not start sections.
+pub fn find_section(doc: &Node, query: SectionQuery) -> Result<Section, SectionError> {
+    let Node::Doc { content, .. } = doc else {
+        return Err(SectionError::NotADoc);
+    };
+    let (heading, level) = content
+        .iter()
+        .enumerate()
+        .find_map(|(index, node)| match node {
+            Node::Heading { attrs, .. } if is_match(node, query) => Some((index, attrs.level)),
+            _ => None,
+        })
+        .ok_or(SectionError::NotFound)?;
+    let end = content[heading + 1..]
+        .iter()
+        .position(|node| matches!(node, Node::Heading { attrs, .. } if attrs.level <= level))
+        .map_or(content.len(), |offset| heading + 1 + offset);
+    Ok(Section { heading, level, body: heading + 1..end })
+}
+
+/// The blocks under the matching heading.
+pub fn section_content<'a>(doc: &'a Node, query: SectionQuery) -> Result<&'a [Node], SectionError> {
+    let section = find_section(doc, query)?;
+    Ok(&doc_content(doc)[section.body])
+}
+
+/// Replaces the blocks under the matching heading, keeping the heading, and
+/// returns the blocks that were there.
+pub fn replace_section(doc: &mut Node, query: SectionQuery, blocks: Vec<Node>) -> Result<Vec<Node>, SectionError> {
+    let section = find_section(doc, query)?;
+    Ok(doc_content_mut(doc).splice(section.body, blocks).collect())
+}
+
+/// Adds blocks at the end of the section, before the next heading.
+pub fn append_to_section(doc: &mut Node, query: SectionQuery, blocks: Vec<Node>) -> Result<(), SectionError> {
+    let section = find_section(doc, query)?;
+    let end = section.body.end;
+    doc_content_mut(doc).splice(end..end, blocks);
+    Ok(())
+}
+
+/// Removes the heading and its blocks, returning them.
+pub fn delete_section(doc: &mut Node, query: SectionQuery) -> Result<Vec<Node>, SectionError> {
+    let section = find_section(doc, query)?;
+    Ok(doc_content_mut(doc).drain(section.heading..section.body.end).collect())
+}
+
+fn is_match(heading: &Node, query: SectionQuery) -> bool {
+    let Node::Heading { attrs, .. } = heading else {
+        return false;
+    };
+    match query {
+        SectionQuery::Text(text) => heading.text_content().trim().to_lowercase() == text.trim().to_lowercase(),
+        SectionQuery::LocalId(local_id) => attrs.local_id.as_deref() == Some(local_id),
+    }
+}
+
+fn doc_content(doc: &Node) -> &Vec<Node> {
+    doc.content().expect("sections are only found in docs")
+}
+
+fn doc_content_mut(doc: &mut Node) -> &mut Vec<Node> {
+    doc.content_mut().expect("sections are only found in docs")
+}
+
+#[cfg(test)]
+mod section_tests {
+    use super::*;
+    use crate::fixtures::paragraph;
+    use crate::model::HeadingAttrs;
+
+    fn description() -> Node {
+        Node::doc(vec![
+            Node::heading(2, vec![Node::text("Summary")]),
+            paragraph("Add export."),
+            Node::Heading {
+                content: vec![Node::text("Acceptance Criteria ")],
+                attrs: HeadingAttrs::new(2).with_local_id("ac"),
+            },
+            paragraph("Exports CSV."),
+            Node::heading(3, vec![Node::text("Edge cases")]),
+            paragraph("Empty tables."),
+            Node::heading(2, vec![Node::text("Notes")]),
+        ])
+    }
+
+    fn texts(blocks: &[Node]) -> Vec<String> {
+        blocks.iter().map(Node::text_content).collect()
+    }
+
+    #[test]
+    fn should_find_section_up_to_next_heading_of_same_level() {
+        let doc = description();
+        let section = find_section(&doc, SectionQuery::Text("acceptance criteria")).unwrap();
+
+        assert_eq!(section, Section { heading: 2, level: 2, body: 3..6 });
+        assert_eq!(
+            texts(section_content(&doc, SectionQuery::LocalId("ac")).unwrap()),
+            vec!["Exports CSV.", "Edge cases", "Empty tables."]
+        );
+        assert_eq!(section_content(&doc, SectionQuery::Text("Notes")).unwrap(), &[]);
+        assert_eq!(find_section(&doc, SectionQuery::Text("Missing")), Err(SectionError::NotFound));
+    }
+
+    #[test]
+    fn should_replace_append_and_delete_sections() {
+        let mut doc = description();
+
+        let removed = replace_section(&mut doc, SectionQuery::LocalId("ac"), vec![paragraph("Exports TSV.")]).unwrap();
+        assert_eq!(texts(&removed), vec!["Exports CSV.", "Edge cases", "Empty tables."]);
+
+        append_to_section(&mut doc, SectionQuery::Text("Summary"), vec![paragraph("And import.")]).unwrap();
+        delete_section(&mut doc, SectionQuery::Text("Notes")).unwrap();
+
+        assert_eq!(
+            texts(doc_content(&doc)),
+            vec!["Summary", "Add export.", "And import.", "Acceptance Criteria ", "Exports TSV."]
+        );
+        assert_eq!(
+            delete_section(&mut paragraph("x"), SectionQuery::Text("x")),
+            Err(SectionError::NotADoc)
+        );
+    }
+}
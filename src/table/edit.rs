@@ -0,0 +1,705 @@
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::Range;
+
+use super::{TableError, TableGrid};
+use crate::model::{Node, TableCellAttrs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// Inserts an empty row before row `at`, or after the last row when `at`
+/// equals the row count. Cells spanning across the insertion point grow to
+/// cover the new row.
+pub fn insert_row(table: &mut Node, at: usize) -> Result<(), TableError> {
+    edit(table, |grid| {
+        if at > grid.rows.len() {
+            return Err(TableError::IndexOutOfBounds { index: at });
+        }
+        if at > 0 && at < grid.rows.len() {
+            let crossing: Vec<_> = (0..grid.columns).map(|column| grid.origin_of(at, column)).collect();
+            grid.check_growth(crossing.into_iter().filter(|(origin_row, _)| *origin_row < at), 1, 0)?;
+        }
+        let mut row = Vec::with_capacity(grid.columns);
+        for column in 0..grid.columns {
+            if at > 0 && at < grid.rows.len() {
+                let (origin_row, origin_column) = grid.origin_of(at, column);
+                if origin_row < at {
+                    if origin_column == column {
+                        grid.grow(origin_row, origin_column, 1, 0)?;
+                    }
+                    row.push(None);
+                    continue;
+                }
+            }
+            let header = neighbours(at, grid.rows.len()).all(|row| grid.is_header_at(row, column));
+            row.push(Some(empty_cell(header)));
+        }
+        grid.rows.insert(at, row);
+        Ok(())
+    })
+}
+
+/// Removes row `at`. Cells spanning into the row shrink, and cells starting
+/// in it with a rowspan move down to the next row.
+pub fn delete_row(table: &mut Node, at: usize) -> Result<(), TableError> {
+    edit(table, |grid| {
+        if at >= grid.rows.len() {
+            return Err(TableError::IndexOutOfBounds { index: at });
+        }
+        if grid.rows.len() == 1 {
+            return Err(TableError::WouldBeEmpty);
+        }
+        for (origin_row, origin_column) in grid.origins_in_row(at) {
+            if origin_row < at {
+                grid.grow(origin_row, origin_column, -1, 0)?;
+            } else if let Some(mut cell) = grid.rows[at][origin_column].take() {
+                let (rowspan, colspan) = span(&cell);
+                if rowspan > 1 {
+                    set_span(&mut cell, rowspan - 1, colspan)?;
+                    grid.rows[at + 1][origin_column] = Some(cell);
+                }
+            }
+        }
+        grid.rows.remove(at);
+        Ok(())
+    })
+}
+
+/// Inserts an empty column before column `at`, or after the last column when
+/// `at` equals the column count. Cells spanning across the insertion point
+/// grow to cover the new column, repeating their neighbouring `colwidth`.
+pub fn insert_column(table: &mut Node, at: usize) -> Result<(), TableError> {
+    edit(table, |grid| {
+        if at > grid.columns {
+            return Err(TableError::IndexOutOfBounds { index: at });
+        }
+        if at > 0 && at < grid.columns {
+            let crossing: Vec<_> = (0..grid.rows.len()).map(|row| grid.origin_of(row, at)).collect();
+            grid.check_growth(crossing.into_iter().filter(|(_, origin_column)| *origin_column < at), 0, 1)?;
+        }
+        for row in 0..grid.rows.len() {
+            if at > 0 && at < grid.columns {
+                let (origin_row, origin_column) = grid.origin_of(row, at);
+                if origin_column < at {
+                    if origin_row == row {
+                        grid.grow(origin_row, origin_column, 0, 1)?;
+                        if let Some(widths) = grid.colwidth_mut(origin_row, origin_column) {
+                            let offset = at - origin_column;
+                            let width = widths.get(offset - 1).copied().unwrap_or(0);
+                            widths.insert(offset.min(widths.len()), width);
+                        }
+                    }
+                    grid.rows[row].insert(at, None);
+                    continue;
+                }
+            }
+            let header = neighbours(at, grid.columns).all(|column| grid.is_header_at(row, column));
+            grid.rows[row].insert(at, Some(empty_cell(header)));
+        }
+        grid.columns += 1;
+        Ok(())
+    })
+}
+
+/// Removes column `at`. Cells spanning into the column shrink, and cells
+/// starting in it with a colspan move right to the next column.
+pub fn delete_column(table: &mut Node, at: usize) -> Result<(), TableError> {
+    edit(table, |grid| {
+        if at >= grid.columns {
+            return Err(TableError::IndexOutOfBounds { index: at });
+        }
+        if grid.columns == 1 {
+            return Err(TableError::WouldBeEmpty);
+        }
+        let mut origins: Vec<_> = (0..grid.rows.len()).map(|row| grid.origin_of(row, at)).collect();
+        origins.dedup();
+        for (origin_row, origin_column) in origins {
+            if let Some(widths) = grid.colwidth_mut(origin_row, origin_column) {
+                if at - origin_column < widths.len() {
+                    widths.remove(at - origin_column);
+                }
+            }
+            if origin_column < at {
+                grid.grow(origin_row, origin_column, 0, -1)?;
+            } else if let Some(mut cell) = grid.rows[origin_row][at].take() {
+                let (rowspan, colspan) = span(&cell);
+                if colspan > 1 {
+                    set_span(&mut cell, rowspan, colspan - 1)?;
+                    grid.rows[origin_row][at + 1] = Some(cell);
+                }
+            }
+        }
+        for row in &mut grid.rows {
+            row.remove(at);
+        }
+        grid.columns -= 1;
+        Ok(())
+    })
+}
+
+/// Merges the cells covering the given rows and columns into the top-left
+/// cell, appending the content of the others. Fails if a cell reaches out of
+/// the area.
+pub fn merge_cells(table: &mut Node, rows: Range<usize>, columns: Range<usize>) -> Result<(), TableError> {
+    edit(table, |grid| {
+        if rows.is_empty() || rows.end > grid.rows.len() {
+            return Err(TableError::IndexOutOfBounds { index: rows.end });
+        }
+        if columns.is_empty() || columns.end > grid.columns {
+            return Err(TableError::IndexOutOfBounds { index: columns.end });
+        }
+        let mut origins = Vec::new();
+        for row in rows.clone() {
+            for column in columns.clone() {
+                let (origin_row, origin_column) = grid.origin_of(row, column);
+                let (rowspan, colspan) = span(grid.cell(origin_row, origin_column));
+                if !rows.contains(&origin_row)
+                    || !columns.contains(&origin_column)
+                    || origin_row + rowspan > rows.end
+                    || origin_column + colspan > columns.end
+                {
+                    return Err(TableError::SpanConflict { row: origin_row, column: origin_column });
+                }
+                if (origin_row, origin_column) == (row, column) {
+                    origins.push((row, column));
+                }
+            }
+        }
+
+        let widths: Option<Vec<u16>> = columns
+            .clone()
+            .map(|column| {
+                let (origin_row, origin_column) = grid.origin_of(rows.start, column);
+                let attrs = cell_attrs(grid.cell(origin_row, origin_column))?;
+                attrs.colwidth()?.get(column - origin_column).copied()
+            })
+            .collect();
+        let mut merged = Vec::new();
+        for (row, column) in origins.iter().skip(1) {
+            let mut cell = grid.rows[*row][*column].take().expect("origin holds a cell");
+            let content = cell.content_mut().map(mem::take).unwrap_or_default();
+            merged.extend(content.into_iter().filter(|node| !is_empty_paragraph(node)));
+        }
+
+        let target = grid.rows[rows.start][columns.start].as_mut().expect("origin holds a cell");
+        set_span(target, rows.len(), columns.len())?;
+        cell_attrs_mut(target).colwidth = widths;
+        if let Some(content) = target.content_mut() {
+            if !merged.is_empty() {
+                content.retain(|node| !is_empty_paragraph(node));
+            }
+            content.append(&mut merged);
+            if content.is_empty() {
+                content.push(Node::paragraph(vec![]));
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Splits the cell covering the slot back into single slots, filling the
+/// slots it covered with empty cells of the same kind.
+pub fn split_cell(table: &mut Node, row: usize, column: usize) -> Result<(), TableError> {
+    edit(table, |grid| {
+        if row >= grid.rows.len() {
+            return Err(TableError::IndexOutOfBounds { index: row });
+        }
+        if column >= grid.columns {
+            return Err(TableError::IndexOutOfBounds { index: column });
+        }
+        let (origin_row, origin_column) = grid.origin_of(row, column);
+        let origin = grid.cell(origin_row, origin_column);
+        let (rowspan, colspan) = span(origin);
+        let header = matches!(origin, Node::TableHeader { .. });
+        let widths = cell_attrs(origin).and_then(TableCellAttrs::colwidth).map(<[u16]>::to_vec);
+
+        for (row_offset, cells) in grid.rows[origin_row..origin_row + rowspan].iter_mut().enumerate() {
+            for (column_offset, slot) in cells[origin_column..origin_column + colspan].iter_mut().enumerate() {
+                let width = widths.as_ref().and_then(|widths| widths.get(column_offset)).copied();
+                let cell = match slot {
+                    Some(cell) if row_offset == 0 && column_offset == 0 => cell,
+                    _ => slot.insert(empty_cell(header)),
+                };
+                set_span(cell, 1, 1)?;
+                cell_attrs_mut(cell).colwidth = width.map(|width| vec![width]);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Moves row `from` so that it ends up at index `to`. Rows taking part in a
+/// rowspan cannot move, and a row cannot be dropped into the middle of one.
+pub fn move_row(table: &mut Node, from: usize, to: usize) -> Result<(), TableError> {
+    edit(table, |grid| {
+        for index in [from, to] {
+            if index >= grid.rows.len() {
+                return Err(TableError::IndexOutOfBounds { index });
+            }
+        }
+        grid.check_row_is_free(from)?;
+        let row = grid.rows.remove(from);
+        if let Some((origin_row, origin_column)) = grid.span_crossing(to) {
+            grid.rows.insert(from, row);
+            return Err(TableError::SpanConflict { row: origin_row, column: origin_column });
+        }
+        grid.rows.insert(to, row);
+        Ok(())
+    })
+}
+
+/// Turns the first row into `tableHeader` cells, or back into `tableCell`s
+/// when it already is a header row.
+pub fn toggle_header_row(table: &mut Node) -> Result<(), TableError> {
+    edit(table, |grid| {
+        let origins: Vec<_> = grid.origins_in_row(0).into_iter().filter(|(row, _)| *row == 0).collect();
+        let header = !origins.iter().all(|(row, column)| grid.is_header_at(*row, *column));
+        for (row, column) in origins {
+            set_header(grid.rows[row][column].as_mut().expect("origin holds a cell"), header);
+        }
+        Ok(())
+    })
+}
+
+/// Turns the first column into `tableHeader` cells, or back into
+/// `tableCell`s when it already is a header column.
+pub fn toggle_header_column(table: &mut Node) -> Result<(), TableError> {
+    edit(table, |grid| {
+        let mut origins: Vec<_> = (0..grid.rows.len()).map(|row| grid.origin_of(row, 0)).collect();
+        origins.dedup();
+        let header = !origins.iter().all(|(row, column)| grid.is_header_at(*row, *column));
+        for (row, column) in origins {
+            set_header(grid.rows[row][column].as_mut().expect("origin holds a cell"), header);
+        }
+        Ok(())
+    })
+}
+
+/// Sorts the rows below the header rows by the text of a column. Values that
+/// read as numbers compare numerically and come before any other text, which
+/// compares case-insensitively. The sort is stable.
+pub fn sort_rows(table: &mut Node, column: usize, order: SortOrder) -> Result<(), TableError> {
+    let header_rows = TableGrid::new(table)?.header_rows();
+    edit(table, |grid| {
+        if column >= grid.columns {
+            return Err(TableError::IndexOutOfBounds { index: column });
+        }
+        for row in header_rows..grid.rows.len() {
+            grid.check_row_is_free(row)?;
+        }
+        let mut body: Vec<_> = grid.rows.drain(header_rows..).collect();
+        let key = |row: &Vec<Option<Node>>| {
+            let mut slot = column;
+            while row[slot].is_none() {
+                slot -= 1;
+            }
+            row[slot].as_ref().map(Node::text_content).unwrap_or_default()
+        };
+        body.sort_by(|a, b| {
+            let ordering = compare_text(&key(a), &key(b));
+            match order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        });
+        grid.rows.extend(body);
+        Ok(())
+    })
+}
+
+// Cells laid out on the grid: the slot where a cell starts holds it, slots
+// covered by its span hold `None`.
+struct Grid {
+    rows: Vec<Vec<Option<Node>>>,
+    columns: usize,
+}
+
+impl Grid {
+    fn origin_of(&self, row: usize, column: usize) -> (usize, usize) {
+        for origin_row in (0..=row).rev() {
+            for origin_column in (0..=column).rev() {
+                if let Some(cell) = &self.rows[origin_row][origin_column] {
+                    let (rowspan, colspan) = span(cell);
+                    if origin_row + rowspan > row && origin_column + colspan > column {
+                        return (origin_row, origin_column);
+                    }
+                }
+            }
+        }
+        unreachable!("every slot of a valid table is covered")
+    }
+
+    // Origins of the cells covering a row, each once, left to right.
+    fn origins_in_row(&self, row: usize) -> Vec<(usize, usize)> {
+        let mut origins: Vec<_> = (0..self.columns).map(|column| self.origin_of(row, column)).collect();
+        origins.dedup();
+        origins
+    }
+
+    fn cell(&self, row: usize, column: usize) -> &Node {
+        self.rows[row][column].as_ref().expect("origin holds a cell")
+    }
+
+    fn is_header_at(&self, row: usize, column: usize) -> bool {
+        let (row, column) = self.origin_of(row, column);
+        matches!(self.cell(row, column), Node::TableHeader { .. })
+    }
+
+    fn grow(&mut self, row: usize, column: usize, rows: isize, columns: isize) -> Result<(), TableError> {
+        let cell = self.rows[row][column].as_mut().expect("origin holds a cell");
+        let (rowspan, colspan) = span(cell);
+        set_span(cell, rowspan.saturating_add_signed(rows), colspan.saturating_add_signed(columns))
+    }
+
+    // Fails before anything is changed when growing one of the cells would
+    // take its span past what the attrs hold.
+    fn check_growth(
+        &self,
+        origins: impl Iterator<Item = (usize, usize)>,
+        rows: isize,
+        columns: isize,
+    ) -> Result<(), TableError> {
+        for (row, column) in origins {
+            let (rowspan, colspan) = span(self.cell(row, column));
+            span_attr(rowspan.saturating_add_signed(rows))?;
+            span_attr(colspan.saturating_add_signed(columns))?;
+        }
+        Ok(())
+    }
+
+    fn colwidth_mut(&mut self, row: usize, column: usize) -> Option<&mut Vec<u16>> {
+        let cell = self.rows[row][column].as_mut()?;
+        match cell {
+            Node::TableCell { attrs, .. } | Node::TableHeader { attrs, .. } => attrs.as_mut()?.colwidth.as_mut(),
+            _ => None,
+        }
+    }
+
+    // A row is free to move when no rowspan starts in it or passes through it.
+    fn check_row_is_free(&self, row: usize) -> Result<(), TableError> {
+        for (origin_row, origin_column) in self.origins_in_row(row) {
+            if origin_row != row || span(self.cell(origin_row, origin_column)).0 > 1 {
+                return Err(TableError::SpanConflict { row: origin_row, column: origin_column });
+            }
+        }
+        Ok(())
+    }
+
+    // A cell whose rowspan covers both row `at - 1` and row `at`.
+    fn span_crossing(&self, at: usize) -> Option<(usize, usize)> {
+        if at == 0 || at >= self.rows.len() {
+            return None;
+        }
+        self.origins_in_row(at).into_iter().find(|(origin_row, _)| *origin_row < at)
+    }
+}
+
+// Takes the cells out of the table, applies the edit and writes the rows
+// back, whether or not the edit succeeded.
+fn edit<F>(table: &mut Node, f: F) -> Result<(), TableError>
+where
+    F: FnOnce(&mut Grid) -> Result<(), TableError>,
+{
+    let (placements, columns) = {
+        let grid = TableGrid::new(table)?;
+        let placements: Vec<_> = grid.cells().map(|cell| (cell.row, cell.column)).collect();
+        (placements, grid.columns())
+    };
+    let Node::Table { content, .. } = table else {
+        return Err(TableError::NotATable);
+    };
+    let mut grid = Grid {
+        rows: (0..content.len()).map(|_| (0..columns).map(|_| None).collect()).collect(),
+        columns,
+    };
+    let mut placements = placements.into_iter();
+    for mut row in mem::take(content) {
+        for cell in row.content_mut().map(mem::take).unwrap_or_default() {
+            let (row, column) = placements.next().expect("one placement per cell");
+            grid.rows[row][column] = Some(cell);
+        }
+    }
+
+    let result = f(&mut grid);
+    *content = grid
+        .rows
+        .into_iter()
+        .map(|row| Node::table_row(row.into_iter().flatten().collect()))
+        .collect();
+    result
+}
+
+// Rows or columns next to an insertion point, whose header state the new
+// cells copy.
+fn neighbours(at: usize, len: usize) -> impl Iterator<Item = usize> {
+    let before = at.checked_sub(1);
+    let after = Some(at).filter(|at| *at < len);
+    before.into_iter().chain(after)
+}
+
+fn empty_cell(header: bool) -> Node {
+    let content = vec![Node::paragraph(vec![])];
+    if header {
+        Node::table_header(content)
+    } else {
+        Node::table_cell(content)
+    }
+}
+
+fn is_empty_paragraph(node: &Node) -> bool {
+    matches!(node, Node::Paragraph { content, .. } if content.is_empty())
+}
+
+fn cell_attrs(cell: &Node) -> Option<&TableCellAttrs> {
+    match cell {
+        Node::TableCell { attrs, .. } | Node::TableHeader { attrs, .. } => attrs.as_ref(),
+        _ => None,
+    }
+}
+
+fn cell_attrs_mut(cell: &mut Node) -> &mut TableCellAttrs {
+    match cell {
+        Node::TableCell { attrs, .. } | Node::TableHeader { attrs, .. } => attrs.get_or_insert_with(Default::default),
+        _ => unreachable!("grid slots only hold table cells"),
+    }
+}
+
+fn span(cell: &Node) -> (usize, usize) {
+    let attrs = cell_attrs(cell);
+    let rowspan = attrs.map_or(1, TableCellAttrs::rowspan).max(1) as usize;
+    let colspan = attrs.map_or(1, TableCellAttrs::colspan).max(1) as usize;
+    (rowspan, colspan)
+}
+
+fn set_span(cell: &mut Node, rowspan: usize, colspan: usize) -> Result<(), TableError> {
+    let (rowspan, colspan) = (span_attr(rowspan)?, span_attr(colspan)?);
+    let attrs = cell_attrs_mut(cell);
+    attrs.rowspan = Some(rowspan).filter(|span| *span > 1);
+    attrs.colspan = Some(colspan).filter(|span| *span > 1);
+    Ok(())
+}
+
+fn span_attr(span: usize) -> Result<u16, TableError> {
+    u16::try_from(span).map_err(|_| TableError::SpanTooLarge { span })
+}
+
+fn set_header(cell: &mut Node, header: bool) {
+    if header == matches!(cell, Node::TableHeader { .. }) {
+        return;
+    }
+    let content = cell.content_mut().map(mem::take).unwrap_or_default();
+    let attrs = match cell {
+        Node::TableCell { attrs, .. } | Node::TableHeader { attrs, .. } => attrs.take(),
+        _ => None,
+    };
+    *cell = if header {
+        Node::TableHeader { content, attrs }
+    } else {
+        Node::TableCell { content, attrs }
+    };
+}
+
+// A total order, as `sort_by` requires: numbers first, then text.
+fn compare_text(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+#[cfg(test)]
+mod edit_tests {
+    use super::*;
+
+    fn cell(text: &str) -> Node {
+        Node::table_cell(vec![Node::paragraph(vec![Node::text(text)])])
+    }
+
+    fn header(text: &str) -> Node {
+        Node::table_header(vec![Node::paragraph(vec![Node::text(text)])])
+    }
+
+    fn spanning(text: &str, rowspan: u16, colspan: u16) -> Node {
+        let attrs = TableCellAttrs::default().with_rowspan(rowspan).with_colspan(colspan);
+        Node::TableCell { content: vec![Node::paragraph(vec![Node::text(text)])], attrs: Some(attrs) }
+    }
+
+    // Renders the grid as rows of cell texts, "^" for slots covered by a span.
+    fn layout(table: &Node) -> Vec<Vec<String>> {
+        let grid = TableGrid::new(table).unwrap();
+        (0..grid.rows())
+            .map(|row| {
+                (0..grid.columns())
+                    .map(|column| {
+                        let cell = grid.cell(row, column).unwrap();
+                        if (cell.row, cell.column) == (row, column) {
+                            cell.node.text_content()
+                        } else {
+                            "^".to_string()
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn table() -> Node {
+        // | H1 | H2 | H3 |
+        // | A (2x2) | B  |
+        // |         | C  |
+        Node::table(vec![
+            Node::table_row(vec![header("H1"), header("H2"), header("H3")]),
+            Node::table_row(vec![spanning("A", 2, 2), cell("B")]),
+            Node::table_row(vec![cell("C")]),
+        ])
+    }
+
+    fn table_with_body() -> Node {
+        let mut table = table();
+        insert_row(&mut table, 3).unwrap();
+        table
+    }
+
+    #[test]
+    fn should_insert_rows_growing_crossing_spans() {
+        let mut table = table();
+        insert_row(&mut table, 2).unwrap();
+        insert_row(&mut table, 0).unwrap();
+
+        assert_eq!(
+            layout(&table),
+            vec![
+                vec!["", "", ""],
+                vec!["H1", "H2", "H3"],
+                vec!["A", "^", "B"],
+                vec!["^", "^", ""],
+                vec!["^", "^", "C"],
+            ]
+        );
+        assert!(matches!(
+            TableGrid::new(&table).unwrap().cell(0, 0).unwrap().node,
+            Node::TableHeader { .. }
+        ));
+    }
+
+    #[test]
+    fn should_refuse_to_grow_spans_past_u16() {
+        let mut table = Node::table(vec![Node::table_row(vec![spanning("A", 1, u16::MAX), cell("B")])]);
+        let before = table.clone();
+
+        assert_eq!(insert_column(&mut table, 1), Err(TableError::SpanTooLarge { span: 65536 }));
+        assert_eq!(table, before);
+    }
+
+    #[test]
+    fn should_delete_rows_moving_spanning_cells_down() {
+        let mut table = table();
+        delete_row(&mut table, 1).unwrap();
+
+        assert_eq!(layout(&table), vec![vec!["H1", "H2", "H3"], vec!["A", "^", "C"]]);
+    }
+
+    #[test]
+    fn should_insert_and_delete_columns_adjusting_colwidth() {
+        let attrs = TableCellAttrs::default().with_colspan(2).with_colwidth(vec![100, 200]);
+        let mut table = Node::table(vec![
+            Node::table_row(vec![Node::TableCell { content: vec![Node::paragraph(vec![])], attrs: Some(attrs) }]),
+            Node::table_row(vec![cell("A"), cell("B")]),
+        ]);
+
+        insert_column(&mut table, 1).unwrap();
+        assert_eq!(layout(&table), vec![vec!["", "^", "^"], vec!["A", "", "B"]]);
+        assert_eq!(TableGrid::new(&table).unwrap().column_widths(), vec![Some(100), Some(100), Some(200)]);
+
+        delete_column(&mut table, 0).unwrap();
+        delete_column(&mut table, 0).unwrap();
+        assert_eq!(layout(&table), vec![vec![""], vec!["B"]]);
+        assert_eq!(TableGrid::new(&table).unwrap().column_widths(), vec![Some(200)]);
+        assert_eq!(delete_column(&mut table, 0), Err(TableError::WouldBeEmpty));
+    }
+
+    #[test]
+    fn should_merge_and_split_cells() {
+        let mut table = table();
+        assert_eq!(
+            merge_cells(&mut table, 1..2, 1..3),
+            Err(TableError::SpanConflict { row: 1, column: 0 })
+        );
+
+        merge_cells(&mut table, 1..3, 0..3).unwrap();
+        assert_eq!(layout(&table), vec![vec!["H1", "H2", "H3"], vec!["A\nB\nC", "^", "^"], vec!["^", "^", "^"]]);
+
+        split_cell(&mut table, 2, 2).unwrap();
+        assert_eq!(layout(&table), vec![vec!["H1", "H2", "H3"], vec!["A\nB\nC", "", ""], vec!["", "", ""]]);
+    }
+
+    #[test]
+    fn should_move_rows_outside_of_spans() {
+        let mut table = Node::table(vec![
+            Node::table_row(vec![cell("1")]),
+            Node::table_row(vec![cell("2")]),
+            Node::table_row(vec![cell("3")]),
+        ]);
+        move_row(&mut table, 0, 2).unwrap();
+        assert_eq!(layout(&table), vec![vec!["2"], vec!["3"], vec!["1"]]);
+
+        let mut table = table_with_body();
+        assert_eq!(move_row(&mut table, 1, 0), Err(TableError::SpanConflict { row: 1, column: 0 }));
+    }
+
+    #[test]
+    fn should_toggle_header_row_and_column() {
+        let mut table = table();
+        toggle_header_row(&mut table).unwrap();
+        assert_eq!(TableGrid::new(&table).unwrap().header_rows(), 0);
+
+        toggle_header_column(&mut table).unwrap();
+        assert_eq!(TableGrid::new(&table).unwrap().header_columns(), 1);
+        toggle_header_row(&mut table).unwrap();
+        assert_eq!(TableGrid::new(&table).unwrap().header_rows(), 1);
+    }
+
+    #[test]
+    fn should_sort_body_rows_by_column_text() {
+        let mut table = Node::table(vec![
+            Node::table_row(vec![header("Name"), header("Count")]),
+            Node::table_row(vec![cell("b"), cell("10")]),
+            Node::table_row(vec![cell("A"), cell("9")]),
+            Node::table_row(vec![cell("c"), cell("100")]),
+        ]);
+
+        sort_rows(&mut table, 0, SortOrder::Ascending).unwrap();
+        assert_eq!(
+            layout(&table),
+            vec![vec!["Name", "Count"], vec!["A", "9"], vec!["b", "10"], vec!["c", "100"]]
+        );
+
+        sort_rows(&mut table, 1, SortOrder::Descending).unwrap();
+        assert_eq!(
+            layout(&table),
+            vec![vec!["Name", "Count"], vec!["c", "100"], vec!["b", "10"], vec!["A", "9"]]
+        );
+        assert_eq!(
+            sort_rows(&mut table_with_body(), 0, SortOrder::Ascending),
+            Err(TableError::SpanConflict { row: 1, column: 0 })
+        );
+    }
+
+    #[test]
+    fn should_sort_numbers_before_text() {
+        let values = ["b", "10", "NaN", "A", "-inf", "9", "1e3", "x", "2", "0", "Y", "-0", "z", "inf"];
+        let mut rows = vec![Node::table_row(vec![header("Value")])];
+        rows.extend(values.iter().cycle().take(56).map(|value| Node::table_row(vec![cell(value)])));
+        let mut table = Node::table(rows);
+
+        sort_rows(&mut table, 0, SortOrder::Ascending).unwrap();
+        let sorted: Vec<String> = layout(&table).into_iter().skip(1).map(|row| row[0].clone()).step_by(4).collect();
+        assert_eq!(sorted, ["-inf", "-0", "0", "2", "9", "10", "1e3", "inf", "NaN", "A", "b", "x", "Y", "z"]);
+    }
+}
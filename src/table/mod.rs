@@ -0,0 +1,375 @@
+use std::fmt;
+
+use crate::model::{Node, TableCellAttrs};
+
+mod edit;
+
+/// Width in pixels of the editor's fixed-width page, which `colwidth` and
+/// media widths are measured against.
+pub(crate) const PAGE_WIDTH: f64 = 760.0;
+
+/// Most slots a [`TableGrid`] allocates, so a few cells with huge spans
+/// cannot claim unbounded memory.
+pub const MAX_GRID_SLOTS: usize = 1_000_000;
+
+pub use edit::{
+    delete_column, delete_row, insert_column, insert_row, merge_cells, move_row, sort_rows, split_cell,
+    toggle_header_column, toggle_header_row, SortOrder,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// The node is not a `table`.
+    NotATable,
+    /// A table holds something other than `tableRow`, or a row something
+    /// other than `tableCell` and `tableHeader`.
+    UnexpectedNode { row: usize, index: Option<usize> },
+    /// A cell would cover a slot already covered by another cell's span.
+    OverlappingSpan { row: usize, column: usize },
+    /// A rowspan reaches past the last row.
+    SpanOutOfBounds { row: usize, column: usize },
+    /// A row covers fewer or more columns than the widest row.
+    Ragged { row: usize, columns: usize, expected: usize },
+    /// A row or column index past the end of the table.
+    IndexOutOfBounds { index: usize },
+    /// A cell spanning across the boundary of the rows or cells being
+    /// edited prevents the edit.
+    SpanConflict { row: usize, column: usize },
+    /// The edit would leave the table without rows or columns.
+    WouldBeEmpty,
+    /// The spans cover more than [`MAX_GRID_SLOTS`] slots.
+    TooLarge { max: usize },
+    /// An edit would give a cell a `colspan` or `rowspan` past `u16::MAX`.
+    SpanTooLarge { span: usize },
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::NotATable => write!(f, "node is not a table"),
+            TableError::UnexpectedNode { row, index: None } => {
+                write!(f, "table child {} is not a table row", row)
+            }
+            TableError::UnexpectedNode { row, index: Some(index) } => {
+                write!(f, "child {} of row {} is not a table cell", index, row)
+            }
+            TableError::OverlappingSpan { row, column } => {
+                write!(f, "cell spans overlap at row {}, column {}", row, column)
+            }
+            TableError::SpanOutOfBounds { row, column } => {
+                write!(f, "rowspan of the cell at row {}, column {} reaches past the table", row, column)
+            }
+            TableError::Ragged { row, columns, expected } => {
+                write!(f, "row {} covers {} columns, expected {}", row, columns, expected)
+            }
+            TableError::IndexOutOfBounds { index } => write!(f, "index {} is outside the table", index),
+            TableError::SpanConflict { row, column } => {
+                write!(f, "the cell at row {}, column {} spans across the edited area", row, column)
+            }
+            TableError::WouldBeEmpty => write!(f, "a table needs at least one row and column"),
+            TableError::TooLarge { max } => write!(f, "table spans cover more than {} slots", max),
+            TableError::SpanTooLarge { span } => write!(f, "a span of {} is more than a cell can hold", span),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+/// A cell placed on the grid, positioned at its top-left slot.
+#[derive(Debug, Clone, Copy)]
+pub struct GridCell<'a> {
+    pub node: &'a Node,
+    pub row: usize,
+    pub column: usize,
+    pub rowspan: usize,
+    pub colspan: usize,
+    /// Position of the cell within its `tableRow`.
+    pub index: usize,
+}
+
+impl<'a> GridCell<'a> {
+    pub fn is_header(&self) -> bool {
+        matches!(self.node, Node::TableHeader { .. })
+    }
+
+    pub fn content(&self) -> &'a [Node] {
+        self.node.content().map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn attrs(&self) -> Option<&'a TableCellAttrs> {
+        match self.node {
+            Node::TableCell { attrs, .. } | Node::TableHeader { attrs, .. } => attrs.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Rectangular view of a `table` node with `colspan` and `rowspan` resolved,
+/// so every (row, column) slot maps to the cell covering it.
+#[derive(Debug, Clone)]
+pub struct TableGrid<'a> {
+    cells: Vec<GridCell<'a>>,
+    // Index into `cells` for every slot, row by row.
+    slots: Vec<Vec<usize>>,
+    columns: usize,
+}
+
+impl<'a> TableGrid<'a> {
+    pub fn new(table: &'a Node) -> Result<Self, TableError> {
+        let Node::Table { content: rows, .. } = table else {
+            return Err(TableError::NotATable);
+        };
+        let mut cells: Vec<GridCell<'a>> = Vec::new();
+        let mut slots: Vec<Vec<Option<usize>>> = vec![Vec::new(); rows.len()];
+        let mut allocated = 0usize;
+
+        for (row, row_node) in rows.iter().enumerate() {
+            let Node::TableRow { content: row_cells } = row_node else {
+                return Err(TableError::UnexpectedNode { row, index: None });
+            };
+            let mut column = 0;
+            for (index, node) in row_cells.iter().enumerate() {
+                let attrs = match node {
+                    Node::TableCell { attrs, .. } | Node::TableHeader { attrs, .. } => attrs.as_ref(),
+                    _ => return Err(TableError::UnexpectedNode { row, index: Some(index) }),
+                };
+                while slots[row].get(column).is_some_and(Option::is_some) {
+                    column += 1;
+                }
+                let colspan = attrs.map_or(1, TableCellAttrs::colspan).max(1) as usize;
+                let rowspan = attrs.map_or(1, TableCellAttrs::rowspan).max(1) as usize;
+                if row + rowspan > rows.len() {
+                    return Err(TableError::SpanOutOfBounds { row, column });
+                }
+                for covered in &mut slots[row..row + rowspan] {
+                    if covered.len() < column + colspan {
+                        allocated += column + colspan - covered.len();
+                        if allocated > MAX_GRID_SLOTS {
+                            return Err(TableError::TooLarge { max: MAX_GRID_SLOTS });
+                        }
+                        covered.resize(column + colspan, None);
+                    }
+                    for (offset, slot) in covered[column..column + colspan].iter_mut().enumerate() {
+                        if slot.is_some() {
+                            return Err(TableError::OverlappingSpan { row, column: column + offset });
+                        }
+                        *slot = Some(cells.len());
+                    }
+                }
+                cells.push(GridCell { node, row, column, rowspan, colspan, index });
+                column += colspan;
+            }
+        }
+
+        let columns = slots.iter().map(Vec::len).max().unwrap_or(0);
+        let slots = slots
+            .into_iter()
+            .enumerate()
+            .map(|(row, slots)| {
+                let covered = slots.iter().filter(|slot| slot.is_some()).count();
+                if covered != columns {
+                    return Err(TableError::Ragged { row, columns: covered, expected: columns });
+                }
+                Ok(slots.into_iter().flatten().collect())
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(TableGrid { cells, slots, columns })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// The cell covering the slot, which may start in an earlier row or
+    /// column when it spans.
+    pub fn cell(&self, row: usize, column: usize) -> Option<&GridCell<'a>> {
+        let index = *self.slots.get(row)?.get(column)?;
+        Some(&self.cells[index])
+    }
+
+    /// Every cell once, in document order.
+    pub fn cells(&self) -> impl Iterator<Item = &GridCell<'a>> {
+        self.cells.iter()
+    }
+
+    /// Cells covering a row, each spanning cell once.
+    pub fn row(&self, row: usize) -> Vec<&GridCell<'a>> {
+        let mut indices = self.slots.get(row).cloned().unwrap_or_default();
+        indices.dedup();
+        indices.into_iter().map(|index| &self.cells[index]).collect()
+    }
+
+    /// Number of leading rows made up only of `tableHeader` cells.
+    pub fn header_rows(&self) -> usize {
+        (0..self.rows())
+            .take_while(|row| self.row(*row).iter().all(|cell| cell.is_header()))
+            .count()
+    }
+
+    /// Number of leading columns made up only of `tableHeader` cells.
+    pub fn header_columns(&self) -> usize {
+        (0..self.columns)
+            .take_while(|column| {
+                (0..self.rows()).all(|row| self.cell(row, *column).is_some_and(GridCell::is_header))
+            })
+            .count()
+    }
+
+    /// Width in pixels of a column, taken from the first cell whose
+    /// `colwidth` covers it.
+    pub fn column_width(&self, column: usize) -> Option<u16> {
+        self.cells.iter().find_map(|cell| {
+            let offset = column.checked_sub(cell.column).filter(|offset| *offset < cell.colspan)?;
+            cell.attrs()?.colwidth()?.get(offset).copied().filter(|width| *width > 0)
+        })
+    }
+
+    pub fn column_widths(&self) -> Vec<Option<u16>> {
+        (0..self.columns).map(|column| self.column_width(column)).collect()
+    }
+
+    /// Share of the page width each column takes, for output with no pixels
+    /// to size by. A table narrower than the editor's fixed-width page keeps
+    /// its width, a wider one is scaled down to fit; columns without a width
+    /// split what is left between them.
+    pub fn column_fractions(&self) -> Vec<f64> {
+        let widths = self.column_widths();
+        let known: f64 = widths.iter().flatten().map(|width| f64::from(*width)).sum();
+        let unknown = widths.iter().filter(|width| width.is_none()).count();
+        let fallback = if unknown == 0 {
+            0.0
+        } else {
+            ((PAGE_WIDTH - known) / unknown as f64).max(PAGE_WIDTH / self.columns as f64 / 2.0)
+        };
+        let pixels: Vec<f64> = widths.iter().map(|width| width.map_or(fallback, f64::from)).collect();
+        let total = pixels.iter().sum::<f64>().max(PAGE_WIDTH);
+        pixels.iter().map(|width| width / total).collect()
+    }
+}
+
+#[cfg(test)]
+mod table_tests {
+    use super::*;
+    use crate::model::TableCellAttrs;
+
+    fn cell(text: &str, attrs: Option<TableCellAttrs>) -> Node {
+        Node::TableCell { content: vec![Node::paragraph(vec![Node::text(text)])], attrs }
+    }
+
+    fn header(text: &str) -> Node {
+        Node::table_header(vec![Node::paragraph(vec![Node::text(text)])])
+    }
+
+    fn text_at(grid: &TableGrid, row: usize, column: usize) -> String {
+        match &grid.cell(row, column).unwrap().content()[0] {
+            Node::Paragraph { content, .. } => match &content[0] {
+                Node::Text { text, .. } => text.clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn should_resolve_colspan_and_rowspan() {
+        // | H1 | H2 | H3 |
+        // | A (2x2) | B  |
+        // |         | C  |
+        let table = Node::table(vec![
+            Node::table_row(vec![header("H1"), header("H2"), header("H3")]),
+            Node::table_row(vec![
+                cell("A", Some(TableCellAttrs::default().with_colspan(2).with_rowspan(2))),
+                cell("B", None),
+            ]),
+            Node::table_row(vec![cell("C", None)]),
+        ]);
+        let grid = TableGrid::new(&table).unwrap();
+
+        assert_eq!((grid.rows(), grid.columns()), (3, 3));
+        assert_eq!(text_at(&grid, 2, 1), "A");
+        assert_eq!(text_at(&grid, 2, 2), "C");
+        assert_eq!(grid.cell(2, 2).unwrap().index, 0);
+        assert_eq!(grid.row(2).len(), 2);
+        assert_eq!(grid.header_rows(), 1);
+        assert_eq!(grid.header_columns(), 0);
+    }
+
+    #[test]
+    fn should_report_ragged_rows() {
+        let table = Node::table(vec![
+            Node::table_row(vec![cell("A", None), cell("B", None)]),
+            Node::table_row(vec![cell("C", None)]),
+        ]);
+
+        assert_eq!(
+            TableGrid::new(&table).unwrap_err(),
+            TableError::Ragged { row: 1, columns: 1, expected: 2 }
+        );
+    }
+
+    #[test]
+    fn should_report_overlapping_spans() {
+        // The rowspan of B reaches into the slot that C's colspan claims.
+        let table = Node::table(vec![
+            Node::table_row(vec![cell("A", None), cell("B", Some(TableCellAttrs::default().with_rowspan(2)))]),
+            Node::table_row(vec![cell("C", Some(TableCellAttrs::default().with_colspan(2)))]),
+        ]);
+
+        assert_eq!(
+            TableGrid::new(&table).unwrap_err(),
+            TableError::OverlappingSpan { row: 1, column: 1 }
+        );
+    }
+
+    #[test]
+    fn should_report_spans_past_the_last_row() {
+        let table = Node::table(vec![Node::table_row(vec![cell(
+            "A",
+            Some(TableCellAttrs::default().with_rowspan(2)),
+        )])]);
+
+        assert_eq!(
+            TableGrid::new(&table).unwrap_err(),
+            TableError::SpanOutOfBounds { row: 0, column: 0 }
+        );
+    }
+
+    #[test]
+    fn should_refuse_spans_past_the_slot_limit() {
+        let wide = || cell("A", Some(TableCellAttrs::default().with_colspan(u16::MAX)));
+        let table = Node::table(vec![Node::table_row((0..20).map(|_| wide()).collect())]);
+
+        assert_eq!(TableGrid::new(&table).unwrap_err(), TableError::TooLarge { max: MAX_GRID_SLOTS });
+    }
+
+    #[test]
+    fn should_expose_header_columns_and_column_widths() {
+        let table = Node::table(vec![
+            Node::table_row(vec![
+                header("H"),
+                cell("A", Some(TableCellAttrs::default().with_colspan(2).with_colwidth(vec![100, 0]))),
+            ]),
+            Node::table_row(vec![
+                header("H"),
+                cell("B", None),
+                cell("C", Some(TableCellAttrs::default().with_colwidth(vec![240]))),
+            ]),
+        ]);
+        let grid = TableGrid::new(&table).unwrap();
+
+        assert_eq!(grid.header_columns(), 1);
+        assert_eq!(grid.column_widths(), vec![None, Some(100), Some(240)]);
+        let pixels: Vec<f64> = grid.column_fractions().iter().map(|fraction| (fraction * PAGE_WIDTH).round()).collect();
+        assert_eq!(pixels, vec![420.0, 100.0, 240.0]);
+    }
+
+    #[test]
+    fn should_reject_non_tables() {
+        assert_eq!(TableGrid::new(&Node::paragraph(vec![])).unwrap_err(), TableError::NotATable);
+    }
+}
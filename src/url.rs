@@ -0,0 +1,12 @@
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel"];
+
+/// The URL if it is relative or uses a scheme that cannot run script.
+pub(crate) fn safe_url(url: &str) -> Option<&str> {
+    let url = url.trim();
+    let scheme_end = url.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')));
+    match scheme_end.filter(|end| url[*end..].starts_with(':')) {
+        Some(end) if !URL_SCHEMES.contains(&url[..end].to_lowercase().as_str()) => None,
+        _ if url.is_empty() => None,
+        _ => Some(url),
+    }
+}